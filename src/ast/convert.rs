@@ -1,4 +1,9 @@
-use std::convert::{TryFrom, TryInto};
+use std::cmp;
+use std::convert::TryInto;
+use std::error;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
 
 use chrono::NaiveDate;
 use itertools::Itertools;
@@ -6,6 +11,8 @@ use penny;
 
 use ast::{self, ParsedRecord};
 use ast::data::{self, BaiDateOrTime, BaiDateTime};
+use dialect::{Dialect, Spec};
+use parse::Position;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -13,8 +20,18 @@ pub enum ChronoError {
     InvalidDate,
     InvalidTime,
 }
+impl fmt::Display for ChronoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ChronoError::InvalidDate => "date does not exist",
+            ChronoError::InvalidTime => "time does not exist",
+        })
+    }
+}
+impl error::Error for ChronoError {}
 
-fn chrono_date(date: &ast::Date) -> Result<NaiveDate, ChronoError> {
+/// `date`, with years from 71 taken as 19xx and the rest as 20xx, if it exists.
+pub fn chrono_date(date: &ast::Date) -> Result<NaiveDate, ChronoError> {
     NaiveDate::from_ymd_opt(
         if date.year > 70 { 1900 } else { 2000 } + date.year as i32,
         date.month as u32,
@@ -45,14 +62,178 @@ fn chrono_date_or_time(
     }
 }
 
-pub struct Converter {
+/// Receives each part of a file as it's converted. Trailers are only visited once they've been
+/// checked.
+pub trait Visitor {
+    fn visit_file_header(&mut self, _file: &data::File) {}
+    fn visit_group_header(&mut self, _group: &data::Group) {}
+    fn visit_account(&mut self, _account: &data::Account) {}
+    fn visit_transaction_detail(&mut self, _transaction_detail: &data::TransactionDetail) {}
+    fn visit_account_trailer(&mut self, _trailer: &ast::ParsedAccountTrailer) {}
+    fn visit_group_trailer(&mut self, _trailer: &ast::ParsedGroupTrailer) {}
+    fn visit_file_trailer(&mut self, _trailer: &ast::ParsedFileTrailer) {}
+}
+
+impl Visitor for () {}
+
+impl<'a, V: Visitor + ?Sized> Visitor for &'a mut V {
+    fn visit_file_header(&mut self, file: &data::File) {
+        (**self).visit_file_header(file)
+    }
+    fn visit_group_header(&mut self, group: &data::Group) {
+        (**self).visit_group_header(group)
+    }
+    fn visit_account(&mut self, account: &data::Account) {
+        (**self).visit_account(account)
+    }
+    fn visit_transaction_detail(&mut self, transaction_detail: &data::TransactionDetail) {
+        (**self).visit_transaction_detail(transaction_detail)
+    }
+    fn visit_account_trailer(&mut self, trailer: &ast::ParsedAccountTrailer) {
+        (**self).visit_account_trailer(trailer)
+    }
+    fn visit_group_trailer(&mut self, trailer: &ast::ParsedGroupTrailer) {
+        (**self).visit_group_trailer(trailer)
+    }
+    fn visit_file_trailer(&mut self, trailer: &ast::ParsedFileTrailer) {
+        (**self).visit_file_trailer(trailer)
+    }
+}
+
+pub struct Converter<V = ()> {
     state: Option<ConverterState>,
+    visitor: V,
+    retain: bool,
+    strictness: data::Strictness,
+    dialect: Arc<Dialect>,
+    warnings: Vec<ConvertError>,
+    recovery: bool,
+    skipped: Option<Skipped>,
+    errors: Vec<ConvertError>,
 }
 
 impl Default for Converter {
     fn default() -> Self {
         Converter {
             state: Some(ConverterState::Fresh),
+            visitor: (),
+            retain: true,
+            strictness: data::Strictness::Strict,
+            dialect: Arc::new(Spec),
+            warnings: Vec::new(),
+            recovery: false,
+            skipped: None,
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl<V: Visitor> Converter<V> {
+    /// Creates a converter that hands each part of the file to `visitor` instead of building it
+    /// up. The finished `data::File` only holds the file header.
+    pub fn visiting(visitor: V) -> Self {
+        Converter {
+            state: Some(ConverterState::Fresh),
+            visitor,
+            retain: false,
+            strictness: data::Strictness::Strict,
+            dialect: Arc::new(Spec),
+            warnings: Vec::new(),
+            recovery: false,
+            skipped: None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn strictness(&self) -> data::Strictness {
+        self.strictness
+    }
+
+    /// Sets whether spec violations that still leave a usable file are errors, or are only
+    /// collected as warnings.
+    pub fn set_strictness(&mut self, strictness: data::Strictness) {
+        self.strictness = strictness;
+    }
+
+    pub fn dialect(&self) -> &Dialect {
+        &*self.dialect
+    }
+
+    /// Sets the deviations from the spec to expect. Conversion errors the dialect tolerates are
+    /// collected as warnings, as if processing leniently.
+    pub fn set_dialect(&mut self, dialect: Arc<Dialect>) {
+        self.dialect = dialect;
+    }
+
+    /// Takes the errors tolerated while processing leniently so far.
+    pub fn take_warnings(&mut self) -> Vec<ConvertError> {
+        mem::replace(&mut self.warnings, Vec::new())
+    }
+
+    pub fn recovery(&self) -> bool {
+        self.recovery
+    }
+
+    /// Sets whether conversion carries on past errors. When it does, an account or group with an
+    /// error in it is dropped and skipped to its trailer, and the error is collected instead of
+    /// ending conversion. Parts of a dropped account or group already handed to the visitor
+    /// aren't taken back.
+    pub fn set_recovery(&mut self, recovery: bool) {
+        self.recovery = recovery;
+    }
+
+    /// Takes the errors recovered from so far.
+    pub fn take_errors(&mut self) -> Vec<ConvertError> {
+        mem::replace(&mut self.errors, Vec::new())
+    }
+
+    pub fn visitor(&self) -> &V {
+        &self.visitor
+    }
+
+    pub fn visitor_mut(&mut self) -> &mut V {
+        &mut self.visitor
+    }
+
+    pub fn into_visitor(self) -> V {
+        self.visitor
+    }
+}
+
+/// What's been dropped of the account or group being skipped, so the enclosing trailers can
+/// still be checked. The totals are only `exact` if every dropped record could be parsed.
+#[derive(Debug, Copy, Clone)]
+struct Skipped {
+    records_num: usize,
+    control_total: i64,
+    exact: bool,
+}
+impl Skipped {
+    fn new(records_num: usize, control_total: i64) -> Self {
+        Skipped {
+            records_num,
+            control_total,
+            exact: true,
+        }
+    }
+
+    /// Counts a dropped record that was continued with `continuations` 88 records.
+    fn add(&mut self, record: &ParsedRecord, continuations: usize) {
+        self.records_num += 1 + continuations;
+        self.control_total += match *record {
+            ParsedRecord::AccountIdent(ref ai) => ai.infos.iter().filter_map(|i| i.amount).sum(),
+            ParsedRecord::TransactionDetail(ref td) => td.amount.unwrap_or(0),
+            _ => 0,
+        };
+    }
+
+    /// The records and control total to count for the dropped part, falling back on what its
+    /// trailer says if some of it couldn't be parsed.
+    fn totals(&self, trailer_records_num: usize, trailer_control_total: i64) -> (usize, i64) {
+        if self.exact {
+            (self.records_num, self.control_total)
+        } else {
+            (trailer_records_num, trailer_control_total)
         }
     }
 }
@@ -73,6 +254,7 @@ struct FileConvState {
     data: data::File,
     records_num: usize,
     control_total: i64,
+    groups_num: usize,
 }
 impl FileConvState {
     fn new(data: data::File, records_num: usize) -> Self {
@@ -80,6 +262,7 @@ impl FileConvState {
             data,
             records_num,
             control_total: 0,
+            groups_num: 0,
         }
     }
 }
@@ -90,6 +273,7 @@ struct GroupConvState {
     data: data::Group,
     records_num: usize,
     control_total: i64,
+    accounts_num: usize,
 }
 impl GroupConvState {
     fn new(data: data::Group, records_num: usize) -> Self {
@@ -97,6 +281,7 @@ impl GroupConvState {
             data,
             records_num,
             control_total: 0,
+            accounts_num: 0,
         }
     }
 }
@@ -107,6 +292,7 @@ struct AccountConvState {
     data: data::Account,
     records_num: usize,
     control_total: i64,
+    transaction_details_num: usize,
 }
 
 impl Default for ConverterState {
@@ -123,6 +309,16 @@ pub enum ConverterProgress {
     Group,
     Account,
 }
+impl fmt::Display for ConverterProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ConverterProgress::Fresh => "a file header",
+            ConverterProgress::File => "a group header or file trailer",
+            ConverterProgress::Group => "an account identifier or group trailer",
+            ConverterProgress::Account => "a transaction detail or account trailer",
+        })
+    }
+}
 
 impl ConverterState {
     pub fn progress(&self) -> ConverterProgress {
@@ -134,6 +330,15 @@ impl ConverterState {
         }
     }
 
+    fn into_file(self) -> Option<FileConvState> {
+        match self {
+            ConverterState::Fresh => None,
+            ConverterState::File(f) |
+            ConverterState::Group(f, _) |
+            ConverterState::Account(f, _, _) => Some(f),
+        }
+    }
+
     fn unwrap_file(&self) -> &FileConvState {
         match *self {
             ConverterState::File(ref f) => f,
@@ -190,21 +395,97 @@ pub enum ConvertError {
     RecordType {
         record: usize,
         progress: ConverterProgress,
+        position: Option<Position>,
+    },
+    File(FileConvError, Option<Position>),
+    Group {
+        group: usize,
+        err: GroupConvError,
+        position: Option<Position>,
     },
-    File(FileConvError),
-    Group { group: usize, err: GroupConvError },
     Account {
         group: usize,
         account: usize,
         err: AccountConvError,
+        position: Option<Position>,
     },
     TransactionDetail {
         group: usize,
         account: usize,
         transaction: usize,
         err: TransactionDetailConvError,
+        position: Option<Position>,
     },
 }
+impl ConvertError {
+    /// The position of the record in the input, with its line, offset and type code. The
+    /// converter only sees parsed records, so this is filled in by whatever read them, with `at`.
+    pub fn position(&self) -> Option<Position> {
+        match *self {
+            ConvertError::RecordType { position, .. } |
+            ConvertError::File(_, position) |
+            ConvertError::Group { position, .. } |
+            ConvertError::Account { position, .. } |
+            ConvertError::TransactionDetail { position, .. } => position,
+        }
+    }
+
+    /// This error, found in the record at `position` in the input.
+    pub fn at(mut self, at: Position) -> Self {
+        match self {
+            ConvertError::RecordType { ref mut position, .. } |
+            ConvertError::File(_, ref mut position) |
+            ConvertError::Group { ref mut position, .. } |
+            ConvertError::Account { ref mut position, .. } |
+            ConvertError::TransactionDetail { ref mut position, .. } => *position = Some(at),
+        }
+        self
+    }
+}
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConvertError::RecordType { progress, .. } => write!(f, "expected {}", progress),
+            ConvertError::File(ref e, _) => e.fmt(f),
+            ConvertError::Group { group, ref err, .. } => {
+                write!(f, "{} (group {})", err, group + 1)
+            }
+            ConvertError::Account {
+                group,
+                account,
+                ref err,
+                ..
+            } => write!(f, "{} (group {}, account {})", err, group + 1, account + 1),
+            ConvertError::TransactionDetail {
+                group,
+                account,
+                transaction,
+                ref err,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} (group {}, account {}, transaction detail {})",
+                    err,
+                    group + 1,
+                    account + 1,
+                    transaction + 1
+                )
+            }
+        }
+    }
+}
+impl error::Error for ConvertError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            ConvertError::RecordType { .. } => None,
+            ConvertError::File(ref e, _) => Some(e),
+            ConvertError::Group { ref err, .. } => Some(err),
+            ConvertError::Account { ref err, .. } => Some(err),
+            ConvertError::TransactionDetail { ref err, .. } => Some(err),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -235,30 +516,238 @@ impl ConverterOutput {
     }
 }
 
-impl Converter {
+impl<V: Visitor> Converter<V> {
+    /// Processes a record that wasn't continued with any 88 records.
     pub fn process<'a>(&mut self, record: ParsedRecord<'a>) -> ConverterOutput {
-        let progress = match self.state {
-            Some(ref state) => state.progress(),
-            None => return ConverterOutput::Done,
+        self.process_continued(record, 0)
+    }
+
+    /// Processes a record that was continued with `continuations` 88 records, which count
+    /// towards the number of records in the trailers.
+    pub fn process_continued<'a>(
+        &mut self,
+        record: ParsedRecord<'a>,
+        continuations: usize,
+    ) -> ConverterOutput {
+        if self.state.is_none() {
+            return ConverterOutput::Done;
+        }
+        if self.skipped.is_some() {
+            return self.skip(record, continuations);
+        }
+        let code = record.field().code();
+        let trailer = match record {
+            ParsedRecord::AccountTrailer(ref at) => Some(ParsedRecord::AccountTrailer(at.clone())),
+            ParsedRecord::GroupTrailer(ref gt) => Some(ParsedRecord::GroupTrailer(gt.clone())),
+            ParsedRecord::FileTrailer(ref ft) => Some(ParsedRecord::FileTrailer(ft.clone())),
+            _ => None,
         };
+        let mut dropped = Skipped::new(0, 0);
+        dropped.add(&record, continuations);
+        match self.convert_record(record, continuations) {
+            ConverterOutput::Err(err) => {
+                if !self.recovery {
+                    self.state = None;
+                    return ConverterOutput::Err(err);
+                }
+                if code == 99 {
+                    // Everything but the file trailer's checks made it, so hand back what did.
+                    self.errors.push(err);
+                    return match self.state.take().and_then(ConverterState::into_file) {
+                        Some(file) => ConverterOutput::Ok(file.data),
+                        None => ConverterOutput::Done,
+                    };
+                }
+                if !self.recover(Some(code)) {
+                    return ConverterOutput::Err(err);
+                }
+                self.errors.push(err);
+                // A trailer that failed its checks still ends the part it was skipping, and is
+                // counted there.
+                match trailer {
+                    Some(trailer) => self.skip(trailer, continuations),
+                    None => {
+                        if let Some(ref mut skipped) = self.skipped {
+                            skipped.records_num += dropped.records_num;
+                            skipped.control_total += dropped.control_total;
+                        }
+                        ConverterOutput::Active
+                    }
+                }
+            }
+            output => output,
+        }
+    }
+
+    /// Skips a record that couldn't be parsed when recovering, along with the rest of the
+    /// account or group it belongs to. An account or group whose trailer couldn't be parsed just
+    /// ends there, and a file trailer that couldn't be parsed ends the file. Returns
+    /// `ConverterOutput::Done` if conversion can't continue.
+    pub fn skip_unparsed(&mut self, record_code: Option<u8>) -> ConverterOutput {
+        if self.state.is_none() {
+            return ConverterOutput::Done;
+        }
+        if record_code == Some(99) {
+            self.skipped = None;
+            return match self.state.take().and_then(ConverterState::into_file) {
+                Some(file) => ConverterOutput::Ok(file.data),
+                None => ConverterOutput::Done,
+            };
+        }
+        if self.skipped.is_none() && !self.recover(record_code) {
+            return ConverterOutput::Done;
+        }
+        if let Some(ref mut skipped) = self.skipped {
+            skipped.exact = false;
+        }
+        let progress = self.state.as_ref().map(ConverterState::progress);
+        match (record_code, progress) {
+            (Some(49), Some(ConverterProgress::Group)) |
+            (Some(98), Some(ConverterProgress::File)) => self.skipped = None,
+            (Some(98), Some(ConverterProgress::Group)) => {
+                let (file, _group) = self.state.take().unwrap().unwrap_group_move();
+                self.state = Some(ConverterState::File(file));
+                self.skipped = None;
+            }
+            _ => {}
+        }
+        ConverterOutput::Active
+    }
+
+    /// Drops the account or group that a failed record with `record_code` belongs to, and starts
+    /// skipping to its trailer. Returns `false`, ending conversion, if the whole file is lost.
+    fn recover(&mut self, record_code: Option<u8>) -> bool {
+        let level = match record_code {
+            Some(1) | Some(99) => 1,
+            Some(2) | Some(98) => 2,
+            _ => 3,
+        };
+        let state = match self.state.take() {
+            Some(state) => state,
+            None => return false,
+        };
+        // Records can't belong to anything deeper than what's open.
+        let level = cmp::min(
+            level,
+            match state {
+                ConverterState::Fresh => 1,
+                ConverterState::File(..) => 2,
+                ConverterState::Group(..) | ConverterState::Account(..) => 3,
+            },
+        );
+        let (state, skipped) = match (state, level) {
+            (_, 1) | (ConverterState::Fresh, _) => return false,
+            (ConverterState::File(file), _) => (ConverterState::File(file), Skipped::new(0, 0)),
+            (ConverterState::Group(file, group), 2) => {
+                let skipped = Skipped::new(group.records_num, group.control_total);
+                (ConverterState::File(file), skipped)
+            }
+            (ConverterState::Account(file, group, account), 2) => {
+                let skipped = Skipped::new(
+                    group.records_num + account.records_num,
+                    group.control_total + account.control_total,
+                );
+                (ConverterState::File(file), skipped)
+            }
+            (ConverterState::Group(file, group), _) => {
+                (ConverterState::Group(file, group), Skipped::new(0, 0))
+            }
+            (ConverterState::Account(file, group, account), _) => {
+                let skipped = Skipped::new(account.records_num, account.control_total);
+                (ConverterState::Group(file, group), skipped)
+            }
+        };
+        self.state = Some(state);
+        self.skipped = Some(skipped);
+        true
+    }
+
+    /// Handles a record while skipping to the end of a dropped account or group. The dropped
+    /// records are counted towards the enclosing trailers, so they can still be checked.
+    fn skip<'a>(&mut self, record: ParsedRecord<'a>, continuations: usize) -> ConverterOutput {
+        let mut skipped = self.skipped.take().unwrap_or_else(|| Skipped::new(0, 0));
+        match (self.state.take().unwrap(), record) {
+            (ConverterState::Group(file, mut group), ParsedRecord::AccountTrailer(at)) => {
+                skipped.records_num += 1 + continuations;
+                let (records_num, control_total) = skipped.totals(at.records_num, at.control_total);
+                group.accounts_num += 1;
+                group.records_num += records_num;
+                group.control_total += control_total;
+                self.state = Some(ConverterState::Group(file, group));
+                ConverterOutput::Active
+            }
+            (ConverterState::File(mut file), ParsedRecord::GroupTrailer(gt)) => {
+                skipped.records_num += 1 + continuations;
+                let (records_num, control_total) = skipped.totals(gt.records_num, gt.control_total);
+                file.groups_num += 1;
+                file.records_num += records_num;
+                file.control_total += control_total;
+                self.state = Some(ConverterState::File(file));
+                ConverterOutput::Active
+            }
+            // The account never ended, so the group it's in goes too.
+            (ConverterState::Group(file, group), record @ ParsedRecord::GroupTrailer(_)) => {
+                skipped.records_num += group.records_num;
+                skipped.control_total += group.control_total;
+                self.state = Some(ConverterState::File(file));
+                self.skipped = Some(skipped);
+                self.skip(record, continuations)
+            }
+            (state, record @ ParsedRecord::FileTrailer(_)) => {
+                self.state = state.into_file().map(ConverterState::File);
+                self.process_continued(record, continuations)
+            }
+            (state, record) => {
+                skipped.add(&record, continuations);
+                self.state = Some(state);
+                self.skipped = Some(skipped);
+                ConverterOutput::Active
+            }
+        }
+    }
+
+    /// Collects the trailer `errors` the dialect tolerates as warnings, and returns the first one
+    /// it doesn't.
+    fn tolerate_all<I>(&mut self, errors: I) -> Result<(), ConvertError>
+    where
+        I: IntoIterator<Item = ConvertError>,
+    {
+        for err in errors {
+            if self.dialect.tolerate(&err) {
+                self.warnings.push(err);
+            } else {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn convert_record<'a>(
+        &mut self,
+        record: ParsedRecord<'a>,
+        continuations: usize,
+    ) -> ConverterOutput {
+        let records_num = 1 + continuations;
+        let progress = self.state.as_ref().unwrap().progress();
         match progress {
             ConverterProgress::Fresh => {
                 match record {
                     ParsedRecord::FileHeader(fh) => {
                         match fh.convert() {
                             Ok(file) => {
+                                self.visitor.visit_file_header(&file);
                                 self.state =
-                                    Some(ConverterState::File(FileConvState::new(file, 1)));
+                                    Some(ConverterState::File(FileConvState::new(file, records_num)));
                                 ConverterOutput::Active
                             }
-                            Err(e) => ConverterOutput::Err(ConvertError::File(e)),
+                            Err(e) => ConverterOutput::Err(ConvertError::File(e, None)),
                         }
                     }
                     _ => {
-                        self.state = None;
                         ConverterOutput::Err(ConvertError::RecordType {
                             record: 0,
                             progress,
+                            position: None,
                         })
                     }
                 }
@@ -266,117 +755,192 @@ impl Converter {
             ConverterProgress::File => {
                 match record {
                     ParsedRecord::GroupHeader(gh) => {
-                        match gh.convert() {
+                        let (group_num, version) = {
+                            let file = self.state.as_ref().unwrap().unwrap_file();
+                            (file.groups_num, file.data.version)
+                        };
+                        let lenient = self.strictness == data::Strictness::Lenient;
+                        let dialect = &*self.dialect;
+                        let warnings = &mut self.warnings;
+                        let converted = gh.convert(version, &mut |err| {
+                            let warning = ConvertError::Group {
+                                group: group_num,
+                                err: err.clone(),
+                                position: None,
+                            };
+                            if lenient || dialect.tolerate(&warning) {
+                                warnings.push(warning);
+                                Ok(())
+                            } else {
+                                Err(err)
+                            }
+                        });
+                        match converted {
                             Ok(group) => {
+                                self.visitor.visit_group_header(&group);
                                 let file = self.state.take().unwrap().unwrap_file_move();
                                 self.state = Some(
-                                    ConverterState::Group(file, GroupConvState::new(group, 1)),
+                                    ConverterState::Group(
+                                        file,
+                                        GroupConvState::new(group, records_num),
+                                    ),
                                 );
                                 ConverterOutput::Active
                             }
                             Err(err) => {
-                                let group_num =
-                                    self.state.as_ref().unwrap().unwrap_file().data.groups.len();
-                                self.state = None;
                                 ConverterOutput::Err(ConvertError::Group {
                                     group: group_num,
                                     err,
+                                    position: None,
                                 })
                             }
                         }
                     }
                     ParsedRecord::FileTrailer(ft) => {
-                        let (control_total, groups_num) = {
+                        let (control_total, groups_num, file_records_num) = {
                             let file = self.state.as_ref().unwrap().unwrap_file();
-                            (file.control_total, file.data.groups.len())
+                            (file.control_total, file.groups_num, file.records_num + records_num)
                         };
-                        // TODO verify records_num
+                        let mut errors = Vec::new();
                         if ft.control_total != control_total {
-                            self.state = None;
-                            ConverterOutput::Err(ConvertError::File(FileConvError::ControlTotal {
+                            errors.push(FileConvError::ControlTotal {
                                 expected: ft.control_total,
                                 actual: control_total,
-                            }))
-                        } else if ft.groups_num != groups_num {
-                            self.state = None;
-                            ConverterOutput::Err(ConvertError::File(FileConvError::GroupsNum {
+                            });
+                        }
+                        if ft.groups_num != groups_num {
+                            errors.push(FileConvError::GroupsNum {
                                 expected: ft.groups_num,
                                 actual: groups_num,
-                            }))
+                            });
+                        }
+                        if ft.records_num != file_records_num {
+                            errors.push(FileConvError::RecordsNum {
+                                expected: ft.records_num,
+                                actual: file_records_num,
+                            });
+                        }
+                        let errors = errors.into_iter().map(|err| ConvertError::File(err, None));
+                        if let Err(err) = self.tolerate_all(errors) {
+                            ConverterOutput::Err(err)
                         } else {
+                            self.visitor.visit_file_trailer(&ft);
                             let file = self.state.take().unwrap().unwrap_file_move();
                             ConverterOutput::Ok(file.data)
                         }
                     }
                     _ => {
                         let record = self.state.as_ref().unwrap().unwrap_file().records_num;
-                        self.state = None;
-                        ConverterOutput::Err(ConvertError::RecordType { record, progress })
+                        ConverterOutput::Err(ConvertError::RecordType {
+                            record,
+                            progress,
+                            position: None,
+                        })
                     }
                 }
             }
             ConverterProgress::Group => {
                 match record {
                     ParsedRecord::AccountIdent(ai) => {
-                        match ai.convert() {
+                        let (group_num, account_num, group_cur, version) = {
+                            let (file, group) = self.state.as_ref().unwrap().unwrap_group();
+                            (
+                                file.groups_num,
+                                group.accounts_num,
+                                group.data.currency_def(),
+                                file.data.version,
+                            )
+                        };
+                        let lenient = self.strictness == data::Strictness::Lenient;
+                        let dialect = &*self.dialect;
+                        let warnings = &mut self.warnings;
+                        let codes = dialect.type_codes();
+                        let converted = ai.convert(group_cur, version, codes, &mut |err| {
+                            let warning = ConvertError::Account {
+                                group: group_num,
+                                account: account_num,
+                                err: err.clone(),
+                                position: None,
+                            };
+                            if lenient || dialect.tolerate(&warning) {
+                                warnings.push(warning);
+                                Ok(())
+                            } else {
+                                Err(err)
+                            }
+                        });
+                        match converted {
                             Ok((account, control_total)) => {
+                                self.visitor.visit_account(&account);
                                 let (file, group) = self.state.take().unwrap().unwrap_group_move();
                                 self.state = Some(ConverterState::Account(
                                     file,
                                     group,
                                     AccountConvState {
                                         data: account,
-                                        records_num: 1,
+                                        records_num,
                                         control_total,
+                                        transaction_details_num: 0,
                                     },
                                 ));
                                 ConverterOutput::Active
                             }
                             Err(err) => {
-                                let (group_num, account_num) = {
-                                    let (file, group) = self.state.as_ref().unwrap().unwrap_group();
-                                    (file.data.groups.len(), group.data.accounts.len())
-                                };
                                 ConverterOutput::Err(ConvertError::Account {
                                     group: group_num,
                                     account: account_num,
                                     err,
+                                    position: None,
                                 })
                             }
                         }
                     }
                     ParsedRecord::GroupTrailer(gt) => {
-                        let (group, control_total, accounts_num) = {
+                        let (group, control_total, accounts_num, group_records_num) = {
                             let (file, group) = self.state.as_ref().unwrap().unwrap_group();
                             (
-                                file.data.groups.len(),
+                                file.groups_num,
                                 group.control_total,
-                                group.data.accounts.len(),
+                                group.accounts_num,
+                                group.records_num + records_num,
                             )
                         };
-                        // TODO verify records_num
+                        let mut errors = Vec::new();
                         if gt.control_total != control_total {
-                            self.state = None;
-                            ConverterOutput::Err(ConvertError::Group {
-                                group,
-                                err: GroupConvError::ControlTotal {
-                                    expected: gt.control_total,
-                                    actual: control_total,
-                                },
-                            })
-                        } else if gt.accounts_num != accounts_num {
-                            self.state = None;
-                            ConverterOutput::Err(ConvertError::Group {
+                            errors.push(GroupConvError::ControlTotal {
+                                expected: gt.control_total,
+                                actual: control_total,
+                            });
+                        }
+                        if gt.accounts_num != accounts_num {
+                            errors.push(GroupConvError::AccountsNum {
+                                expected: gt.accounts_num,
+                                actual: accounts_num,
+                            });
+                        }
+                        if gt.records_num != group_records_num {
+                            errors.push(GroupConvError::RecordsNum {
+                                expected: gt.records_num,
+                                actual: group_records_num,
+                            });
+                        }
+                        let errors = errors.into_iter().map(|err| {
+                            ConvertError::Group {
                                 group,
-                                err: GroupConvError::AccountsNum {
-                                    expected: gt.accounts_num,
-                                    actual: accounts_num,
-                                },
-                            })
+                                err,
+                                position: None,
+                            }
+                        });
+                        if let Err(err) = self.tolerate_all(errors) {
+                            ConverterOutput::Err(err)
                         } else {
+                            self.visitor.visit_group_trailer(&gt);
                             let (mut file, group) = self.state.take().unwrap().unwrap_group_move();
-                            file.data.groups.push(group.data);
-                            file.records_num += group.records_num + 1;
+                            if self.retain {
+                                file.data.groups.push(group.data);
+                            }
+                            file.groups_num += 1;
+                            file.records_num += group_records_num;
                             file.control_total += group.control_total;
                             self.state = Some(ConverterState::File(file));
                             ConverterOutput::Active
@@ -384,69 +948,114 @@ impl Converter {
                     }
                     _ => {
                         let record = self.state.as_ref().unwrap().unwrap_group().0.records_num;
-                        self.state = None;
-                        ConverterOutput::Err(ConvertError::RecordType { record, progress })
+                        ConverterOutput::Err(ConvertError::RecordType {
+                            record,
+                            progress,
+                            position: None,
+                        })
                     }
                 }
             }
             ConverterProgress::Account => {
                 match record {
                     ParsedRecord::TransactionDetail(td) => {
-                        match td.convert() {
+                        let (group_num, account_num, transaction_num, account_cur, version) = {
+                            let (file, group, account) =
+                                self.state.as_ref().unwrap().unwrap_account();
+                            (
+                                file.groups_num,
+                                group.accounts_num,
+                                account.transaction_details_num,
+                                account.data.currency_def(group.data.currency_def()),
+                                file.data.version,
+                            )
+                        };
+                        let lenient = self.strictness == data::Strictness::Lenient;
+                        let dialect = &*self.dialect;
+                        let warnings = &mut self.warnings;
+                        let codes = dialect.type_codes();
+                        let converted = td.convert(account_cur, version, codes, &mut |err| {
+                            let warning = ConvertError::TransactionDetail {
+                                group: group_num,
+                                account: account_num,
+                                transaction: transaction_num,
+                                err: err.clone(),
+                                position: None,
+                            };
+                            if lenient || dialect.tolerate(&warning) {
+                                warnings.push(warning);
+                                Ok(())
+                            } else {
+                                Err(err)
+                            }
+                        });
+                        match converted {
                             Ok((transaction_detail, control_total)) => {
+                                self.visitor.visit_transaction_detail(&transaction_detail);
                                 let (_file, _group, account) =
                                     self.state.as_mut().unwrap().unwrap_account_mut();
-                                account.data.transaction_details.push(transaction_detail);
-                                account.records_num += 1;
+                                if self.retain {
+                                    account.data.transaction_details.push(transaction_detail);
+                                }
+                                account.transaction_details_num += 1;
+                                account.records_num += records_num;
                                 account.control_total += control_total;
                                 ConverterOutput::Active
                             }
                             Err(err) => {
-                                let (group_num, account_num, transaction_num) = {
-                                    let (file, group, account) =
-                                        self.state.as_ref().unwrap().unwrap_account();
-                                    (
-                                        file.data.groups.len(),
-                                        group.data.accounts.len(),
-                                        account.data.transaction_details.len(),
-                                    )
-                                };
-                                self.state = None;
                                 ConverterOutput::Err(ConvertError::TransactionDetail {
                                     group: group_num,
                                     account: account_num,
                                     transaction: transaction_num,
                                     err,
+                                    position: None,
                                 })
                             }
                         }
                     }
                     ParsedRecord::AccountTrailer(at) => {
-                        let (group, account, control_total) = {
+                        let (group, account, control_total, account_records_num) = {
                             let (file, group, account) =
                                 self.state.as_ref().unwrap().unwrap_account();
                             (
-                                file.data.groups.len(),
-                                group.data.accounts.len(),
+                                file.groups_num,
+                                group.accounts_num,
                                 account.control_total,
+                                account.records_num + records_num,
                             )
                         };
-                        // TODO verify records_num
+                        let mut errors = Vec::new();
                         if at.control_total != control_total {
-                            self.state = None;
-                            ConverterOutput::Err(ConvertError::Account {
+                            errors.push(AccountConvError::ControlTotal {
+                                expected: at.control_total,
+                                actual: control_total,
+                            });
+                        }
+                        if at.records_num != account_records_num {
+                            errors.push(AccountConvError::RecordsNum {
+                                expected: at.records_num,
+                                actual: account_records_num,
+                            });
+                        }
+                        let errors = errors.into_iter().map(|err| {
+                            ConvertError::Account {
                                 group,
                                 account,
-                                err: AccountConvError::ControlTotal {
-                                    expected: at.control_total,
-                                    actual: control_total,
-                                },
-                            })
+                                err,
+                                position: None,
+                            }
+                        });
+                        if let Err(err) = self.tolerate_all(errors) {
+                            ConverterOutput::Err(err)
                         } else {
+                            self.visitor.visit_account_trailer(&at);
                             let (file, mut group, account) =
                                 self.state.take().unwrap().unwrap_account_move();
-                            group.data.accounts.push(account.data);
-                            group.records_num += account.records_num + 1;
+                            if self.retain {
+                                group.data.accounts.push(account.data);
+                            }
+                            group.accounts_num += 1;
+                            group.records_num += account_records_num;
                             group.control_total += account.control_total;
                             self.state = Some(ConverterState::Group(file, group));
                             ConverterOutput::Active
@@ -454,22 +1063,28 @@ impl Converter {
                     }
                     _ => {
                         let record = self.state.as_ref().unwrap().unwrap_account().0.records_num;
-                        self.state = None;
-                        ConverterOutput::Err(ConvertError::RecordType { record, progress })
+                        ConverterOutput::Err(ConvertError::RecordType {
+                            record,
+                            progress,
+                            position: None,
+                        })
                     }
                 }
             }
         }
     }
+}
 
+/// These take each record along with the number of 88 records it was continued with.
+impl Converter {
     pub fn fold<'a, I>(iter: &mut I) -> Result<data::File, Option<ConvertError>>
     where
-        I: Iterator<Item = ast::ParsedRecord<'a>>,
+        I: Iterator<Item = (ast::ParsedRecord<'a>, usize)>,
     {
         let mut converter = ast::convert::Converter::default();
         match iter.fold(
             ConverterOutput::Active,
-            |acc, r| match converter.process(r) {
+            |acc, (r, continuations)| match converter.process_continued(r, continuations) {
                 ConverterOutput::Done => acc,
                 o => o,
             },
@@ -483,13 +1098,13 @@ impl Converter {
 
     pub fn fold_results<'a, E, I, O>(iter: &mut I, op: O) -> Result<data::File, E>
     where
-        I: Iterator<Item = Result<ast::ParsedRecord<'a>, E>>,
+        I: Iterator<Item = Result<(ast::ParsedRecord<'a>, usize), E>>,
         O: FnOnce(Option<ConvertError>) -> E,
     {
         let mut converter = ast::convert::Converter::default();
         match iter.fold_results(
             ConverterOutput::Active,
-            |acc, r| match converter.process(r) {
+            |acc, (r, continuations)| match converter.process_continued(r, continuations) {
                 ConverterOutput::Done => acc,
                 o => o,
             },
@@ -507,19 +1122,63 @@ impl Converter {
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub enum FileConvError {
     Creation(ChronoError),
+    VersionNumber(u8),
     ControlTotal { expected: i64, actual: i64 },
     GroupsNum { expected: usize, actual: usize },
     RecordsNum { expected: usize, actual: usize },
 }
+impl fmt::Display for FileConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileConvError::Creation(ref e) => write!(f, "file creation date and time: {}", e),
+            FileConvError::VersionNumber(v) => write!(f, "unsupported version number {}", v),
+            FileConvError::ControlTotal { expected, actual } => {
+                write!(
+                    f,
+                    "file trailer control total {} does not match computed {}",
+                    expected,
+                    actual
+                )
+            }
+            FileConvError::GroupsNum { expected, actual } => {
+                write!(
+                    f,
+                    "file trailer number of groups {} does not match counted {}",
+                    expected,
+                    actual
+                )
+            }
+            FileConvError::RecordsNum { expected, actual } => {
+                write!(
+                    f,
+                    "file trailer number of records {} does not match counted {}",
+                    expected,
+                    actual
+                )
+            }
+        }
+    }
+}
+impl error::Error for FileConvError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            FileConvError::Creation(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl<'a> ast::ParsedFileHeader<'a> {
-    fn convert(&self) -> Result<data::File, FileConvError> {
+    /// Converts a file header into a file with no groups yet.
+    pub fn convert(&self) -> Result<data::File, FileConvError> {
         Ok(data::File {
             sender: data::Party(self.sender_ident.to_owned()),
             receiver: data::Party(self.receiver_ident.to_owned()),
             creation: chrono_date_time(&self.creation_date, &self.creation_time)
                 .map_err(FileConvError::Creation)?,
             ident: data::FileIdent(self.ident_num),
+            version: data::Version::from_version_number(self.version_number)
+                .ok_or(FileConvError::VersionNumber(self.version_number))?,
             groups: Vec::new(),
         })
     }
@@ -529,17 +1188,73 @@ impl<'a> ast::ParsedFileHeader<'a> {
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub enum GroupConvError {
     Status,
+    RetiredStatus(data::GroupStatus),
     AsOf(ChronoError),
     Currency(String),
     AsOfDateMod,
+    RetiredAsOfDateMod(data::AsOfDateModifier),
     ControlTotal { expected: i64, actual: i64 },
     AccountsNum { expected: usize, actual: usize },
     RecordsNum { expected: usize, actual: usize },
 }
+impl fmt::Display for GroupConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GroupConvError::Status => f.write_str("invalid group status"),
+            GroupConvError::RetiredStatus(s) => {
+                write!(f, "group status {} is retired in BTRS", u8::from(s))
+            }
+            GroupConvError::AsOf(ref e) => write!(f, "group as-of date and time: {}", e),
+            GroupConvError::Currency(ref c) => write!(f, "unknown group currency {:?}", c),
+            GroupConvError::AsOfDateMod => f.write_str("invalid group as-of date modifier"),
+            GroupConvError::RetiredAsOfDateMod(m) => {
+                write!(f, "group as-of date modifier {} is retired in BTRS", u8::from(m))
+            }
+            GroupConvError::ControlTotal { expected, actual } => {
+                write!(
+                    f,
+                    "group trailer control total {} does not match computed {}",
+                    expected,
+                    actual
+                )
+            }
+            GroupConvError::AccountsNum { expected, actual } => {
+                write!(
+                    f,
+                    "group trailer number of accounts {} does not match counted {}",
+                    expected,
+                    actual
+                )
+            }
+            GroupConvError::RecordsNum { expected, actual } => {
+                write!(
+                    f,
+                    "group trailer number of records {} does not match counted {}",
+                    expected,
+                    actual
+                )
+            }
+        }
+    }
+}
+impl error::Error for GroupConvError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            GroupConvError::AsOf(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl<'a> ast::ParsedGroupHeader<'a> {
-    fn convert(&self) -> Result<data::Group, GroupConvError> {
-        Ok(data::Group {
+    /// Converts the header of a group in a file of `version`, passing the errors the caller may
+    /// choose to carry on past to `tolerate`.
+    pub fn convert(
+        &self,
+        version: data::Version,
+        tolerate: &mut FnMut(GroupConvError) -> Result<(), GroupConvError>,
+    ) -> Result<data::Group, GroupConvError> {
+        let group = data::Group {
             ultimate_receiver: self.ultimate_receiver_ident
                 .map(|s| data::Party(s.to_owned())),
             originator: self.originator_ident.map(|s| data::Party(s.to_owned())),
@@ -558,7 +1273,17 @@ impl<'a> ast::ParsedGroupHeader<'a> {
                     m.try_into().or(Err(GroupConvError::AsOfDateMod)).map(Some)
                 })?,
             accounts: Vec::new(),
-        })
+        };
+        if version == data::Version::Btrs3 {
+            match group.status {
+                data::GroupStatus::Update => {}
+                status => tolerate(GroupConvError::RetiredStatus(status))?,
+            }
+            if let Some(m @ data::AsOfDateModifier::InterimPrevious) = group.as_of_date_mod {
+                tolerate(GroupConvError::RetiredAsOfDateMod(m))?;
+            }
+        }
+        Ok(group)
     }
 }
 
@@ -570,18 +1295,59 @@ pub enum AccountConvError {
     ControlTotal { expected: i64, actual: i64 },
     RecordsNum { expected: usize, actual: usize },
 }
+impl fmt::Display for AccountConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccountConvError::Currency(ref c) => write!(f, "unknown account currency {:?}", c),
+            AccountConvError::AccountInfo(i, ref e) => write!(f, "account info {}: {}", i + 1, e),
+            AccountConvError::ControlTotal { expected, actual } => {
+                write!(
+                    f,
+                    "account trailer control total {} does not match computed {}",
+                    expected,
+                    actual
+                )
+            }
+            AccountConvError::RecordsNum { expected, actual } => {
+                write!(
+                    f,
+                    "account trailer number of records {} does not match counted {}",
+                    expected,
+                    actual
+                )
+            }
+        }
+    }
+}
+impl error::Error for AccountConvError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            AccountConvError::AccountInfo(_, ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl<'a> ast::ParsedAccountIdent<'a> {
-    fn convert(&self) -> Result<(data::Account, i64), AccountConvError> {
-        let (infos, control_total) = convert_infos(&self.infos)
-            .map_err(|(i, e)| AccountConvError::AccountInfo(i, e))?;
+    /// Converts an account identifier along with its account infos, returning their control
+    /// total with it.
+    pub fn convert(
+        &self,
+        group_cur: penny::Currency,
+        version: data::Version,
+        codes: Option<&data::TypeCodeRegistry>,
+        tolerate: &mut FnMut(AccountConvError) -> Result<(), AccountConvError>,
+    ) -> Result<(data::Account, i64), AccountConvError> {
+        let currency = self.currency.map_or(Ok(None), |s| {
+            s.parse::<penny::Currency>()
+                .map(Some)
+                .map_err(|_| AccountConvError::Currency(s.to_owned()))
+        })?;
+        let (infos, control_total) =
+            convert_infos(&self.infos, currency.unwrap_or(group_cur), version, codes, tolerate)?;
         let account = data::Account {
             customer_account: data::AccountNumber(self.customer_account_num.to_owned()),
-            currency: self.currency.map_or(Ok(None), |s| {
-                s.parse::<penny::Currency>()
-                    .map(Some)
-                    .map_err(|_| AccountConvError::Currency(s.to_owned()))
-            })?,
+            currency: currency,
             infos: infos,
             transaction_details: Vec::new(),
         };
@@ -599,23 +1365,101 @@ pub enum AccountInfoConvError {
     SummaryNegativeAmount,
     Funds(FundsTypeConvError),
 }
+impl fmt::Display for AccountInfoConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccountInfoConvError::NoCode => f.write_str("missing type code"),
+            AccountInfoConvError::InvalidCode => {
+                f.write_str("type code is not a status or summary code")
+            }
+            AccountInfoConvError::StatusItemCount => {
+                f.write_str("status type codes can't have an item count")
+            }
+            AccountInfoConvError::StatusFunds => {
+                f.write_str("status type codes can't have a funds type")
+            }
+            AccountInfoConvError::SummaryNegativeAmount => {
+                f.write_str("summary amounts can't be negative")
+            }
+            AccountInfoConvError::Funds(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for AccountInfoConvError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            AccountInfoConvError::Funds(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 fn convert_infos(
     pinfos: &[ast::ParsedAccountInfo],
-) -> Result<(Vec<data::AccountInfo>, i64), (usize, AccountInfoConvError)> {
+    currency: penny::Currency,
+    version: data::Version,
+    codes: Option<&data::TypeCodeRegistry>,
+    tolerate: &mut FnMut(AccountConvError) -> Result<(), AccountConvError>,
+) -> Result<(Vec<data::AccountInfo>, i64), AccountConvError> {
     let mut control_total = 0;
     let mut infos = Vec::with_capacity(pinfos.len());
     for (i, pi) in pinfos.iter().enumerate() {
-        pi.convert().map_err(|e| (i, e))?.map(|(i, t)| {
-            control_total += t;
-            infos.push(i);
-        });
+        pi.convert(currency, version, codes, &mut |e| {
+            tolerate(AccountConvError::AccountInfo(i, e)).map_err(|_| e)
+        }).map_err(|e| AccountConvError::AccountInfo(i, e))?
+            .map(|(i, t)| {
+                control_total += t;
+                infos.push(i);
+            });
     }
     Ok((infos, control_total))
 }
 
+/// `code` as a status or summary code registered in `codes`, or else as the spec defines it.
+fn info_code(
+    code: u16,
+    version: data::Version,
+    codes: Option<&data::TypeCodeRegistry>,
+) -> Option<data::TypeCode> {
+    use data::TypeCode;
+
+    codes
+        .and_then(|r| {
+            r.status_code(code)
+                .map(TypeCode::Status)
+                .or_else(|| r.summary_code(code).map(TypeCode::Summary))
+        })
+        .or_else(|| {
+            data::StatusCode::from_version(code, version)
+                .ok()
+                .map(TypeCode::Status)
+        })
+        .or_else(|| {
+            data::SummaryCode::from_version(code, version)
+                .ok()
+                .map(TypeCode::Summary)
+        })
+}
+
+/// `code` as a detail code registered in `codes`, or else as the spec defines it.
+fn detail_code(
+    code: u16,
+    version: data::Version,
+    codes: Option<&data::TypeCodeRegistry>,
+) -> Result<data::DetailCode, u16> {
+    codes
+        .and_then(|r| r.detail_code(code))
+        .map_or_else(|| data::DetailCode::from_version(code, version), Ok)
+}
+
 impl ast::ParsedAccountInfo {
-    fn convert(&self) -> Result<Option<(data::AccountInfo, i64)>, AccountInfoConvError> {
+    fn convert(
+        &self,
+        currency: penny::Currency,
+        version: data::Version,
+        codes: Option<&data::TypeCodeRegistry>,
+        tolerate: &mut FnMut(AccountInfoConvError) -> Result<(), AccountInfoConvError>,
+    ) -> Result<Option<(data::AccountInfo, i64)>, AccountInfoConvError> {
         use data::AccountInfo as AI;
         use self::AccountInfoConvError as CE;
 
@@ -627,41 +1471,47 @@ impl ast::ParsedAccountInfo {
             self.funds_type.as_ref(),
         ) {
             (None, None, None, None) => None,
-            (Some(code), amount, item_count, funds) => {
-                if let Ok(code) = data::StatusCode::try_from(code) {
+            (Some(code), amount, item_count, funds) => match info_code(code, version, codes) {
+                Some(data::TypeCode::Status(code)) => {
                     match (item_count, funds) {
                         (None, None) => {
                             Some(AI::Status {
                                 code: code,
-                                amount: {
-                                    if let Some(a) = amount {
-                                        control_total += a;
-                                    }
-                                    amount
-                                },
+                                amount: amount.map(|a| {
+                                    control_total += a;
+                                    data::Amount::new(a, currency)
+                                }),
                             })
                         }
                         (Some(_), _) => return Err(CE::StatusItemCount),
                         (_, Some(_)) => return Err(CE::StatusFunds),
                     }
-                } else if let Ok(code) = data::SummaryCode::try_from(code) {
+                }
+                Some(data::TypeCode::Summary(code)) => {
                     Some(AI::Summary {
                         code: code,
-                        amount: amount.map_or(Ok(None), |a| if a >= 0 {
-                            control_total += a;
-                            Ok(Some(a as u64))
-                        } else {
-                            Err(CE::SummaryNegativeAmount)
-                        })?,
+                        amount: match amount {
+                            Some(a) => {
+                                if a < 0 {
+                                    tolerate(CE::SummaryNegativeAmount)?;
+                                }
+                                control_total += a;
+                                Some(data::Amount::new(a, currency))
+                            }
+                            None => None,
+                        },
                         item_count: item_count,
                         funds: funds
-                            .map_or(Ok(None), |f| f.convert().map(Some))
+                            .map_or(Ok(None), |f| {
+                                f.convert(currency, version, &mut |e| {
+                                    tolerate(CE::Funds(e)).map_err(|_| e)
+                                }).map(Some)
+                            })
                             .map_err(CE::Funds)?,
                     })
-                } else {
-                    return Err(CE::InvalidCode);
                 }
-            }
+                _ => return Err(CE::InvalidCode),
+            },
             _ => return Err(CE::NoCode),
         };
         Ok(info.map(|i| (i, control_total)))
@@ -673,10 +1523,38 @@ impl ast::ParsedAccountInfo {
 pub enum FundsTypeConvError {
     ValueDated(ChronoError),
     DistributedAvailDNum(usize),
+    RetiredDistributedAvailD,
+}
+impl fmt::Display for FundsTypeConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FundsTypeConvError::ValueDated(ref e) => write!(f, "value date and time: {}", e),
+            FundsTypeConvError::DistributedAvailDNum(num) => {
+                write!(f, "number of distributions does not match the {} given", num)
+            }
+            FundsTypeConvError::RetiredDistributedAvailD => {
+                f.write_str("distributed availability funds type D is retired in BTRS")
+            }
+        }
+    }
+}
+impl error::Error for FundsTypeConvError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            FundsTypeConvError::ValueDated(ref e) => Some(e),
+            FundsTypeConvError::DistributedAvailDNum(_) |
+            FundsTypeConvError::RetiredDistributedAvailD => None,
+        }
+    }
 }
 
 impl ast::ParsedFundsType {
-    fn convert(&self) -> Result<data::FundsType, FundsTypeConvError> {
+    fn convert(
+        &self,
+        currency: penny::Currency,
+        version: data::Version,
+        tolerate: &mut FnMut(FundsTypeConvError) -> Result<(), FundsTypeConvError>,
+    ) -> Result<data::FundsType, FundsTypeConvError> {
         use ast::ParsedFundsType as PFT;
         use ast::data::FundsType as FT;
         use self::FundsTypeConvError as CE;
@@ -691,10 +1569,11 @@ impl ast::ParsedFundsType {
                 one_day,
                 more_than_one_day,
             } => {
+                let amount = |a| data::Amount::new(a, currency);
                 FT::DistributedAvailS {
-                    immediate,
-                    one_day,
-                    more_than_one_day,
+                    immediate: immediate.map(amount),
+                    one_day: one_day.map(amount),
+                    more_than_one_day: more_than_one_day.map(amount),
                 }
             }
             PFT::ValueDated { ref date, ref time } => {
@@ -703,16 +1582,22 @@ impl ast::ParsedFundsType {
                     .map(FT::ValueDated)?
             }
             PFT::DistributedAvailD { num, ref dists } => {
+                if version == data::Version::Btrs3 {
+                    tolerate(CE::RetiredDistributedAvailD)?;
+                }
                 let ndists = dists.len();
                 if num != ndists {
-                    return Err(CE::DistributedAvailDNum(ndists));
+                    tolerate(CE::DistributedAvailDNum(ndists))?;
                 }
                 FT::DistributedAvailD(
                     dists
                         .iter()
                         .map(
                             |&ast::ParsedDistributedAvailDistribution { days, amount }| {
-                                data::DistributedAvailDistribution { days, amount }
+                                data::DistributedAvailDistribution {
+                                    days,
+                                    amount: data::Amount::new(amount, currency),
+                                }
                             },
                         )
                         .collect(),
@@ -729,22 +1614,58 @@ pub enum TransactionDetailConvError {
     DetailCode(u16),
     Funds(FundsTypeConvError),
 }
+impl fmt::Display for TransactionDetailConvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransactionDetailConvError::NotTransactionDetailOrAccountTrailer => {
+                f.write_str("expected a transaction detail or account trailer")
+            }
+            TransactionDetailConvError::DetailCode(code) => {
+                write!(f, "type code {:03} is not a detail code", code)
+            }
+            TransactionDetailConvError::Funds(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for TransactionDetailConvError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            TransactionDetailConvError::Funds(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl<'a> ast::ParsedTransactionDetail<'a> {
-    fn convert(self) -> Result<(data::TransactionDetail, i64), TransactionDetailConvError> {
+    /// Converts a transaction detail, returning its amount's contribution to the control total
+    /// with it.
+    pub fn convert(
+        self,
+        currency: penny::Currency,
+        version: data::Version,
+        codes: Option<&data::TypeCodeRegistry>,
+        tolerate: &mut FnMut(TransactionDetailConvError) -> Result<(), TransactionDetailConvError>,
+    ) -> Result<(data::TransactionDetail, i64), TransactionDetailConvError> {
         let mut control_total: i64 = 0;
         let transaction_detail = data::TransactionDetail {
-            code: data::DetailCode::try_from(self.type_code)
-                .map_err(TransactionDetailConvError::DetailCode)?,
-            amount: {
-                if let Some(a) = self.amount {
-                    control_total += a;
+            code: match detail_code(self.type_code, version, codes) {
+                Ok(code) => code,
+                Err(code) => {
+                    tolerate(TransactionDetailConvError::DetailCode(code))?;
+                    data::DetailCode::Unknown(code)
                 }
-                self.amount
             },
+            amount: self.amount.map(|a| {
+                control_total += a;
+                data::Amount::new(a, currency)
+            }),
             funds: self.funds_type
                 .as_ref()
-                .map_or(Ok(None), |ft| ft.convert().map(Some))
+                .map_or(Ok(None), |ft| {
+                    ft.convert(currency, version, &mut |e| {
+                        tolerate(TransactionDetailConvError::Funds(e)).map_err(|_| e)
+                    }).map(Some)
+                })
                 .map_err(TransactionDetailConvError::Funds)?,
             bank_ref_num: self.bank_ref_num.map(|s| data::ReferenceNum(s.to_owned())),
             customer_ref_num: self.customer_ref_num
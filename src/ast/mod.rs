@@ -1,3 +1,4 @@
+use std::fmt;
 use std::marker::PhantomData;
 use std::num;
 use std::str;
@@ -86,7 +87,7 @@ parsed! {
         pub IdentNum(ident_num): &'a [u8] => u32,
         pub PhysicalRecordLen(physical_record_len): Option<&'a [u8]> => Option<u16>,
         pub BlockSize(block_size): Option<&'a [u8]> => Option<u16>,
-        pub VersionNumber(version_number): &'a [u8] => (),
+        pub VersionNumber(version_number): &'a [u8] => u8,
     }
     pub struct GroupHeader[GroupHeaderField] (RawGroupHeader<'a> => ParsedGroupHeader<'a>) {
         // 02
@@ -224,6 +225,20 @@ pub enum RecordField {
     GroupTrailer,
     FileTrailer,
 }
+impl RecordField {
+    /// The record code the record starts with.
+    pub fn code(self) -> u8 {
+        match self {
+            RecordField::FileHeader => 1,
+            RecordField::GroupHeader => 2,
+            RecordField::AccountIdent => 3,
+            RecordField::TransactionDetail => 16,
+            RecordField::AccountTrailer => 49,
+            RecordField::GroupTrailer => 98,
+            RecordField::FileTrailer => 99,
+        }
+    }
+}
 #[derive(Debug, Clone)]
 pub enum RawRecord<'a> {
     FileHeader(RawFileHeader<'a>),
@@ -272,6 +287,94 @@ impl<'a> ParsedRecord<'a> {
     }
 }
 
+macro_rules! field_names {
+    ($($fields:ident { $($field:ident => $name:expr,)* })*) => {
+        $(impl fmt::Display for $fields {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(match *self {
+                    $($fields::$field => $name,)*
+                })
+            }
+        })*
+    };
+}
+field_names! {
+    FileHeaderField {
+        SenderIdent => "sender identification",
+        ReceiverIdent => "receiver identification",
+        CreationDate => "file creation date",
+        CreationTime => "file creation time",
+        IdentNum => "file identification number",
+        PhysicalRecordLen => "physical record length",
+        BlockSize => "block size",
+        VersionNumber => "version number",
+    }
+    GroupHeaderField {
+        UltimateReceiverIdent => "ultimate receiver identification",
+        OriginatorIdent => "originator identification",
+        Status => "group status",
+        AsOfDate => "as-of date",
+        AsOfTime => "as-of time",
+        Currency => "currency code",
+        AsOfDateMod => "as-of date modifier",
+    }
+    AccountIdentField {
+        CustomerAccountNum => "customer account number",
+        Currency => "currency code",
+        Infos => "account info",
+    }
+    TransactionDetailField {
+        TypeCode => "type code",
+        Amount => "amount",
+        FundsType => "funds type",
+        BankRefNum => "bank reference number",
+        CustomerRefNum => "customer reference number",
+        Text => "text",
+    }
+    AccountTrailerField {
+        ControlTotal => "account control total",
+        RecordsNum => "number of records",
+    }
+    GroupTrailerField {
+        ControlTotal => "group control total",
+        AccountsNum => "number of accounts",
+        RecordsNum => "number of records",
+    }
+    FileTrailerField {
+        ControlTotal => "file control total",
+        GroupsNum => "number of groups",
+        RecordsNum => "number of records",
+    }
+    AccountInfoField {
+        TypeCode => "type code",
+        Amount => "amount",
+        ItemCount => "item count",
+        FundsType => "funds type",
+    }
+    DistributedAvailDistributionField {
+        Days => "availability days",
+        Amount => "available amount",
+    }
+    FundsTypeField {
+        DistributedAvailSImmediate => "immediate availability amount",
+        DistributedAvailSOneDay => "one-day availability amount",
+        DistributedAvailSMoreThanOneDay => "two-or-more-day availability amount",
+        ValueDatedDate => "value date",
+        ValueDatedTime => "value time",
+        DistributedAvailDNum => "number of distributions",
+        DistributedAvailDDists => "distributions",
+    }
+    RecordField {
+        FileHeader => "file header",
+        GroupHeader => "group header",
+        AccountIdent => "account identifier",
+        TransactionDetail => "transaction detail",
+        AccountTrailer => "account trailer",
+        GroupTrailer => "group trailer",
+        FileTrailer => "file trailer",
+    }
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct Date {
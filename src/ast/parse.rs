@@ -1,151 +1,299 @@
+use std::error;
+
+use dialect::{Dialect, Spec};
+use parse::Position;
+
 use super::*;
 
 pub trait Parsed {
     type Raw;
     type Parsed;
-    type Field;
-    type Err;
+    type Field: Field;
+
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>>;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>>;
+    /// Like `parse`, but allowing for the deviations from the spec that `dialect` expects.
+    fn parse_with(
+        raw: &Self::Raw,
+        _dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse(raw)
+    }
+
+    /// Finds the raw field that a parse error came from.
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]>;
 }
 
+pub trait Field: Copy + fmt::Debug + fmt::Display {
+    type Err: error::Error + 'static;
+}
+
+/// An error parsing a field, with the position of the field in the input once it's known.
+/// Parsing works on records already split out of the input, so the position is only filled in
+/// by whatever split them out, with `at`.
 #[derive(Debug, Clone)]
-pub enum ParseError<T: Parsed + ?Sized> {
-    Utf8(T::Field, str::Utf8Error),
-    Int(T::Field, num::ParseIntError),
-    Format(T::Field),
-    Error(T::Field, T::Err),
+pub enum ParseError<F: Field> {
+    Utf8(F, str::Utf8Error, Option<Position>),
+    Int(F, num::ParseIntError, Option<Position>),
+    Format(F, Option<Position>),
+    Error(F, F::Err, Option<Position>),
+}
+impl<F: Field> ParseError<F> {
+    pub fn field(&self) -> F {
+        match *self {
+            ParseError::Utf8(f, _, _) |
+            ParseError::Int(f, _, _) |
+            ParseError::Format(f, _) |
+            ParseError::Error(f, _, _) => f,
+        }
+    }
+
+    /// The position of the field in the input, with its record's line, offset and type code.
+    pub fn position(&self) -> Option<Position> {
+        match *self {
+            ParseError::Utf8(_, _, p) |
+            ParseError::Int(_, _, p) |
+            ParseError::Format(_, p) |
+            ParseError::Error(_, _, p) => p,
+        }
+    }
+
+    /// This error, found at `position` in the input.
+    pub fn at(mut self, position: Position) -> Self {
+        match self {
+            ParseError::Utf8(_, _, ref mut p) |
+            ParseError::Int(_, _, ref mut p) |
+            ParseError::Format(_, ref mut p) |
+            ParseError::Error(_, _, ref mut p) => *p = Some(position),
+        }
+        self
+    }
+}
+impl<F: Field> fmt::Display for ParseError<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Utf8(field, ref e, _) => write!(f, "{} is not valid UTF-8: {}", field, e),
+            ParseError::Int(field, ref e, _) => {
+                write!(f, "{} is not a valid number: {}", field, e)
+            }
+            ParseError::Format(field, _) => write!(f, "{} is malformed", field),
+            ParseError::Error(field, ref e, _) => write!(f, "{}: {}", field, e),
+        }
+    }
+}
+impl<F: Field> error::Error for ParseError<F> {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            ParseError::Utf8(_, ref e, _) => Some(e),
+            ParseError::Int(_, ref e, _) => Some(e),
+            ParseError::Format(_, _) => None,
+            ParseError::Error(_, ref e, _) => Some(e),
+        }
+    }
 }
-fn parse_str<T: Parsed>(i: &[u8], f: T::Field) -> Result<&str, ParseError<T>> {
-    str::from_utf8(i).map_err(|e| ParseError::Utf8(f, e))
+fn parse_str<F: Field>(i: &[u8], f: F) -> Result<&str, ParseError<F>> {
+    str::from_utf8(i).map_err(|e| ParseError::Utf8(f, e, None))
 }
-fn parse_from<T, P, F>(i: &str, f: T::Field, fs: F) -> Result<P, ParseError<T>>
+fn parse_from<F, P, E>(i: &str, f: F, fs: E) -> Result<P, ParseError<F>>
 where
-    T: Parsed,
-    T::Field: Copy,
+    F: Field,
     P: str::FromStr,
-    F: FnOnce(P::Err) -> T::Err,
+    E: FnOnce(P::Err) -> F::Err,
 {
-    i.parse::<P>().map_err(|e| ParseError::Error(f, fs(e)))
+    i.parse::<P>().map_err(|e| ParseError::Error(f, fs(e), None))
 }
-fn parse_int<T, P>(i: &str, f: T::Field) -> Result<P, ParseError<T>>
+fn parse_int<F, P>(i: &str, f: F) -> Result<P, ParseError<F>>
 where
-    T: Parsed,
-    T::Field: Copy,
+    F: Field,
     P: str::FromStr<Err = num::ParseIntError>,
 {
-    i.parse::<P>().map_err(|e| ParseError::Int(f, e))
+    i.parse::<P>().map_err(|e| ParseError::Int(f, e, None))
 }
-fn parse_strfrom<T, P, F>(i: &[u8], f: T::Field, fs: F) -> Result<P, ParseError<T>>
+fn parse_strfrom<F, P, E>(i: &[u8], f: F, fs: E) -> Result<P, ParseError<F>>
 where
-    T: Parsed,
-    T::Field: Copy,
+    F: Field,
     P: str::FromStr,
-    F: FnOnce(P::Err) -> T::Err,
+    E: FnOnce(P::Err) -> F::Err,
 {
     parse_str(i, f).and_then(|s| parse_from(s, f, fs))
 }
-fn parse_strint<T, P>(i: &[u8], f: T::Field) -> Result<P, ParseError<T>>
+fn parse_strint<F, P>(i: &[u8], f: F) -> Result<P, ParseError<F>>
 where
-    T: Parsed,
-    T::Field: Copy,
+    F: Field,
     P: str::FromStr<Err = num::ParseIntError>,
 {
     parse_str(i, f).and_then(|s| parse_int(s, f))
 }
-fn parse_optstr<T: Parsed>(i: Option<&[u8]>, f: T::Field) -> Result<Option<&str>, ParseError<T>> {
+fn parse_optstr<F: Field>(i: Option<&[u8]>, f: F) -> Result<Option<&str>, ParseError<F>> {
     i.map_or(Ok(None), |s| parse_str(s, f).map(Some))
 }
-fn parse_optstrfrom<T, P, F>(
-    i: Option<&[u8]>,
-    f: T::Field,
-    fs: F,
-) -> Result<Option<P>, ParseError<T>>
+fn parse_optstrfrom<F, P, E>(i: Option<&[u8]>, f: F, fs: E) -> Result<Option<P>, ParseError<F>>
 where
-    T: Parsed,
-    T::Field: Copy,
+    F: Field,
     P: str::FromStr,
-    F: FnOnce(P::Err) -> T::Err,
+    E: FnOnce(P::Err) -> F::Err,
 {
     i.map_or(Ok(None), |s| parse_strfrom(s, f, fs).map(Some))
 }
-fn parse_optstrint<T, P>(i: Option<&[u8]>, f: T::Field) -> Result<Option<P>, ParseError<T>>
+fn parse_optstrint<F, P>(i: Option<&[u8]>, f: F) -> Result<Option<P>, ParseError<F>>
 where
-    T: Parsed,
-    T::Field: Copy,
+    F: Field,
     P: str::FromStr<Err = num::ParseIntError>,
 {
     i.map_or(Ok(None), |s| parse_strint(s, f).map(Some))
 }
 
+/// Drops the century from a date with a four-digit year, if `dialect` expects them.
+fn short_date<'r>(date: &'r [u8], dialect: &Dialect) -> &'r [u8] {
+    if dialect.four_digit_year() && date.len() == 8 {
+        &date[2..]
+    } else {
+        date
+    }
+}
+/// Leaves out a time made of spaces, if `dialect` expects them.
+fn nonblank_time<'r>(time: Option<&'r [u8]>, dialect: &Dialect) -> Option<&'r [u8]> {
+    match time {
+        Some(t) if dialect.blank_as_of_time() && t.iter().all(|&c| c == b' ') => None,
+        t => t,
+    }
+}
+
 #[derive(Debug, Clone)]
-pub enum RecordError<'a> {
-    FileHeader(ParseError<FileHeader<'a>>),
-    GroupHeader(ParseError<GroupHeader<'a>>),
-    AccountIdent(ParseError<AccountIdent<'a>>),
-    TransactionDetail(ParseError<TransactionDetail<'a>>),
-    AccountTrailer(ParseError<AccountTrailer<'a>>),
-    GroupTrailer(ParseError<GroupTrailer<'a>>),
-    FileTrailer(ParseError<FileTrailer<'a>>),
+pub enum RecordError {
+    FileHeader(Box<ParseError<FileHeaderField>>),
+    GroupHeader(Box<ParseError<GroupHeaderField>>),
+    AccountIdent(Box<ParseError<AccountIdentField>>),
+    TransactionDetail(Box<ParseError<TransactionDetailField>>),
+    AccountTrailer(Box<ParseError<AccountTrailerField>>),
+    GroupTrailer(Box<ParseError<GroupTrailerField>>),
+    FileTrailer(Box<ParseError<FileTrailerField>>),
+}
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecordError::FileHeader(ref e) => e.fmt(f),
+            RecordError::GroupHeader(ref e) => e.fmt(f),
+            RecordError::AccountIdent(ref e) => e.fmt(f),
+            RecordError::TransactionDetail(ref e) => e.fmt(f),
+            RecordError::AccountTrailer(ref e) => e.fmt(f),
+            RecordError::GroupTrailer(ref e) => e.fmt(f),
+            RecordError::FileTrailer(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for RecordError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        Some(match *self {
+            RecordError::FileHeader(ref e) => e,
+            RecordError::GroupHeader(ref e) => e,
+            RecordError::AccountIdent(ref e) => e,
+            RecordError::TransactionDetail(ref e) => e,
+            RecordError::AccountTrailer(ref e) => e,
+            RecordError::GroupTrailer(ref e) => e,
+            RecordError::FileTrailer(ref e) => e,
+        })
+    }
+}
+impl Field for RecordField {
+    type Err = RecordError;
 }
 impl<'a> Parsed for Record<'a> {
     type Raw = RawRecord<'a>;
     type Parsed = ParsedRecord<'a>;
     type Field = RecordField;
-    type Err = RecordError<'a>;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::ParseError as PE;
         use self::RecordField as F;
         use self::RecordError as E;
 
         use self::RawRecord as R;
         use self::ParsedRecord as P;
+        let error = |field, err| PE::Error(field, err, None);
         Ok(match *raw {
             R::FileHeader(ref fh) => {
                 P::FileHeader(
-                    FileHeader::parse(fh)
-                        .map_err(|e| PE::Error(F::FileHeader, E::FileHeader(e)))?,
+                    FileHeader::parse_with(fh, dialect)
+                        .map_err(|e| error(F::FileHeader, E::FileHeader(Box::new(e))))?,
                 )
             }
             R::GroupHeader(ref gh) => {
                 P::GroupHeader(
-                    GroupHeader::parse(gh)
-                        .map_err(|e| PE::Error(F::GroupHeader, E::GroupHeader(e)))?,
+                    GroupHeader::parse_with(gh, dialect)
+                        .map_err(|e| error(F::GroupHeader, E::GroupHeader(Box::new(e))))?,
                 )
             }
             R::AccountIdent(ref ai) => {
                 P::AccountIdent(
-                    AccountIdent::parse(ai)
-                        .map_err(|e| PE::Error(F::AccountIdent, E::AccountIdent(e)))?,
+                    AccountIdent::parse_with(ai, dialect)
+                        .map_err(|e| error(F::AccountIdent, E::AccountIdent(Box::new(e))))?,
                 )
             }
             R::TransactionDetail(ref td) => {
                 P::TransactionDetail(
-                    TransactionDetail::parse(td)
-                        .map_err(|e| PE::Error(F::TransactionDetail, E::TransactionDetail(e)))?,
+                    TransactionDetail::parse_with(td, dialect).map_err(|e| {
+                        error(F::TransactionDetail, E::TransactionDetail(Box::new(e)))
+                    })?,
                 )
             }
             R::AccountTrailer(ref at) => {
                 P::AccountTrailer(
-                    AccountTrailer::parse(at)
-                        .map_err(|e| PE::Error(F::AccountTrailer, E::AccountTrailer(e)))?,
+                    AccountTrailer::parse_with(at, dialect)
+                        .map_err(|e| error(F::AccountTrailer, E::AccountTrailer(Box::new(e))))?,
                 )
             }
             R::GroupTrailer(ref gt) => {
                 P::GroupTrailer(
-                    GroupTrailer::parse(gt)
-                        .map_err(|e| PE::Error(F::GroupTrailer, E::GroupTrailer(e)))?,
+                    GroupTrailer::parse_with(gt, dialect)
+                        .map_err(|e| error(F::GroupTrailer, E::GroupTrailer(Box::new(e))))?,
                 )
             }
             R::FileTrailer(ref ft) => {
                 P::FileTrailer(
-                    FileTrailer::parse(ft)
-                        .map_err(|e| PE::Error(F::FileTrailer, E::FileTrailer(e)))?,
+                    FileTrailer::parse_with(ft, dialect)
+                        .map_err(|e| error(F::FileTrailer, E::FileTrailer(Box::new(e))))?,
                 )
             }
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::ParseError as PE;
+        use self::RecordError as E;
+        use self::RawRecord as R;
+        match (raw, err) {
+            (&R::FileHeader(ref r), &PE::Error(_, E::FileHeader(ref e), _)) => {
+                FileHeader::locate(r, e)
+            }
+            (&R::GroupHeader(ref r), &PE::Error(_, E::GroupHeader(ref e), _)) => {
+                GroupHeader::locate(r, e)
+            }
+            (&R::AccountIdent(ref r), &PE::Error(_, E::AccountIdent(ref e), _)) => {
+                AccountIdent::locate(r, e)
+            }
+            (&R::TransactionDetail(ref r), &PE::Error(_, E::TransactionDetail(ref e), _)) => {
+                TransactionDetail::locate(r, e)
+            }
+            (&R::AccountTrailer(ref r), &PE::Error(_, E::AccountTrailer(ref e), _)) => {
+                AccountTrailer::locate(r, e)
+            }
+            (&R::GroupTrailer(ref r), &PE::Error(_, E::GroupTrailer(ref e), _)) => {
+                GroupTrailer::locate(r, e)
+            }
+            (&R::FileTrailer(ref r), &PE::Error(_, E::FileTrailer(ref e), _)) => {
+                FileTrailer::locate(r, e)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -153,31 +301,74 @@ pub enum FileHeaderError {
     Date(DateError),
     Time(TimeError),
 }
+impl fmt::Display for FileHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileHeaderError::Date(ref e) => e.fmt(f),
+            FileHeaderError::Time(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for FileHeaderError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        Some(match *self {
+            FileHeaderError::Date(ref e) => e,
+            FileHeaderError::Time(ref e) => e,
+        })
+    }
+}
+impl Field for FileHeaderField {
+    type Err = FileHeaderError;
+}
 impl<'a> Parsed for FileHeader<'a> {
     type Raw = RawFileHeader<'a>;
     type Parsed = ParsedFileHeader<'a>;
     type Field = FileHeaderField;
-    type Err = FileHeaderError;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::ParseError as PE;
         use self::FileHeaderField as F;
         use self::FileHeaderError as E;
         Ok(ParsedFileHeader {
             sender_ident: parse_str(raw.sender_ident, F::SenderIdent)?,
             receiver_ident: parse_str(raw.receiver_ident, F::ReceiverIdent)?,
-            creation_date: parse_strfrom(raw.creation_date, F::CreationDate, E::Date)?,
+            creation_date: parse_strfrom(
+                short_date(raw.creation_date, dialect),
+                F::CreationDate,
+                E::Date,
+            )?,
             creation_time: parse_strfrom(raw.creation_time, F::CreationTime, E::Time)?,
             ident_num: parse_strint(raw.ident_num, F::IdentNum)?,
             physical_record_len: parse_optstrint(raw.physical_record_len, F::PhysicalRecordLen)?,
             block_size: parse_optstrint(raw.block_size, F::BlockSize)?,
-            version_number: if parse_str(raw.version_number, F::VersionNumber)? == "2" {
-                ()
-            } else {
-                return Err(PE::Format(F::VersionNumber));
+            // 2 is BAI2, and 3 is BTRS Version 3.
+            version_number: match parse_strint(raw.version_number, F::VersionNumber)? {
+                v @ 2 | v @ 3 => v,
+                _ => return Err(PE::Format(F::VersionNumber, None)),
             },
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::FileHeaderField as F;
+        match err.field() {
+            F::SenderIdent => Some(raw.sender_ident),
+            F::ReceiverIdent => Some(raw.receiver_ident),
+            F::CreationDate => Some(raw.creation_date),
+            F::CreationTime => Some(raw.creation_time),
+            F::IdentNum => Some(raw.ident_num),
+            F::PhysicalRecordLen => raw.physical_record_len,
+            F::BlockSize => raw.block_size,
+            F::VersionNumber => Some(raw.version_number),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -185,13 +376,38 @@ pub enum GroupHeaderError {
     Date(DateError),
     Time(TimeError),
 }
+impl fmt::Display for GroupHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GroupHeaderError::Date(ref e) => e.fmt(f),
+            GroupHeaderError::Time(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for GroupHeaderError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        Some(match *self {
+            GroupHeaderError::Date(ref e) => e,
+            GroupHeaderError::Time(ref e) => e,
+        })
+    }
+}
+impl Field for GroupHeaderField {
+    type Err = GroupHeaderError;
+}
 impl<'a> Parsed for GroupHeader<'a> {
     type Raw = RawGroupHeader<'a>;
     type Parsed = ParsedGroupHeader<'a>;
     type Field = GroupHeaderField;
-    type Err = GroupHeaderError;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::GroupHeaderField as F;
         use self::GroupHeaderError as E;
         Ok(ParsedGroupHeader {
@@ -201,25 +417,65 @@ impl<'a> Parsed for GroupHeader<'a> {
             )?,
             originator_ident: parse_optstr(raw.originator_ident, F::OriginatorIdent)?,
             status: parse_strint(raw.status, F::Status)?,
-            as_of_date: parse_strfrom(raw.as_of_date, F::AsOfDate, E::Date)?,
-            as_of_time: parse_optstrfrom(raw.as_of_time, F::AsOfTime, E::Time)?,
-            currency: parse_optstr(raw.currency, F::AsOfTime)?,
+            as_of_date: parse_strfrom(short_date(raw.as_of_date, dialect), F::AsOfDate, E::Date)?,
+            as_of_time: parse_optstrfrom(
+                nonblank_time(raw.as_of_time, dialect),
+                F::AsOfTime,
+                E::Time,
+            )?,
+            currency: parse_optstr(raw.currency, F::Currency)?,
             as_of_date_mod: parse_optstrint(raw.as_of_date_mod, F::AsOfDateMod)?,
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::GroupHeaderField as F;
+        match err.field() {
+            F::UltimateReceiverIdent => raw.ultimate_receiver_ident,
+            F::OriginatorIdent => raw.originator_ident,
+            F::Status => Some(raw.status),
+            F::AsOfDate => Some(raw.as_of_date),
+            F::AsOfTime => raw.as_of_time,
+            F::Currency => raw.currency,
+            F::AsOfDateMod => raw.as_of_date_mod,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub enum AccountIdentError<'a> {
-    Info(usize, ParseError<AccountInfo<'a>>),
+pub enum AccountIdentError {
+    Info(usize, ParseError<AccountInfoField>),
+}
+impl fmt::Display for AccountIdentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccountIdentError::Info(i, ref e) => write!(f, "item {}: {}", i + 1, e),
+        }
+    }
+}
+impl error::Error for AccountIdentError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            AccountIdentError::Info(_, ref e) => Some(e),
+        }
+    }
+}
+impl Field for AccountIdentField {
+    type Err = AccountIdentError;
 }
 impl<'a> Parsed for AccountIdent<'a> {
     type Raw = RawAccountIdent<'a>;
     type Parsed = ParsedAccountIdent<'a>;
     type Field = AccountIdentField;
-    type Err = AccountIdentError<'a>;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::ParseError as PE;
         use self::AccountIdentField as F;
         use self::AccountIdentError as E;
@@ -229,28 +485,77 @@ impl<'a> Parsed for AccountIdent<'a> {
             infos: {
                 let mut p = Vec::with_capacity(raw.infos.len());
                 for (i, info) in raw.infos.iter().enumerate() {
-                    p.push(Parsed::parse(info)
-                        .map_err(|e| PE::Error(F::Infos, E::Info(i, e)))?)
+                    p.push(AccountInfo::parse_with(info, dialect)
+                        .map_err(|e| PE::Error(F::Infos, E::Info(i, e), None))?)
                 }
                 p
             },
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::ParseError as PE;
+        use self::AccountIdentField as F;
+        use self::AccountIdentError as E;
+        match *err {
+            PE::Error(F::Infos, E::Info(i, ref e), _) => {
+                raw.infos.get(i).and_then(|info| AccountInfo::locate(info, e))
+            }
+            _ => {
+                match err.field() {
+                    F::CustomerAccountNum => Some(raw.customer_account_num),
+                    F::Currency => raw.currency,
+                    F::Infos => None,
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub enum TransactionDetailError<'a> {
+pub enum TransactionDetailError {
     OversizedAmount(u64),
-    FundsType(ParseError<FundsType<'a>>),
+    FundsType(Box<ParseError<FundsTypeField>>),
     Text(usize, str::Utf8Error),
 }
+impl fmt::Display for TransactionDetailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransactionDetailError::OversizedAmount(amount) => {
+                write!(f, "{} is too large", amount)
+            }
+            TransactionDetailError::FundsType(ref e) => e.fmt(f),
+            TransactionDetailError::Text(i, ref e) => {
+                write!(f, "line {} is not valid UTF-8: {}", i + 1, e)
+            }
+        }
+    }
+}
+impl error::Error for TransactionDetailError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            TransactionDetailError::OversizedAmount(_) => None,
+            TransactionDetailError::FundsType(ref e) => Some(e),
+            TransactionDetailError::Text(_, ref e) => Some(e),
+        }
+    }
+}
+impl Field for TransactionDetailField {
+    type Err = TransactionDetailError;
+}
 impl<'a> Parsed for TransactionDetail<'a> {
     type Raw = RawTransactionDetail<'a>;
     type Parsed = ParsedTransactionDetail<'a>;
     type Field = TransactionDetailField;
-    type Err = TransactionDetailError<'a>;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::ParseError as PE;
         use self::TransactionDetailField as F;
         use self::TransactionDetailError as E;
@@ -259,7 +564,7 @@ impl<'a> Parsed for TransactionDetail<'a> {
             amount: {
                 if let Some(amount) = parse_optstrint::<_, u64>(raw.amount, F::Amount)? {
                     if amount > i64::max_value() as u64 {
-                        return Err(PE::Error(F::Amount, E::OversizedAmount(amount)));
+                        return Err(PE::Error(F::Amount, E::OversizedAmount(amount), None));
                     }
                     Some(amount as i64)
                 } else {
@@ -267,8 +572,8 @@ impl<'a> Parsed for TransactionDetail<'a> {
                 }
             },
             funds_type: raw.funds_type.as_ref().map_or(Ok(None), |ft| {
-                FundsType::parse(ft)
-                    .map_err(|e| PE::Error(F::FundsType, E::FundsType(e)))
+                FundsType::parse_with(ft, dialect)
+                    .map_err(|e| PE::Error(F::FundsType, E::FundsType(Box::new(e)), None))
                     .map(Some)
             })?,
             bank_ref_num: parse_optstr(raw.bank_ref_num, F::BankRefNum)?,
@@ -278,7 +583,7 @@ impl<'a> Parsed for TransactionDetail<'a> {
                     let mut out = Vec::with_capacity(raw_text.len());
                     for (i, line) in raw_text.iter().enumerate() {
                         out.push(str::from_utf8(line)
-                            .map_err(|e| PE::Error(F::Text, E::Text(i, e)))?);
+                            .map_err(|e| PE::Error(F::Text, E::Text(i, e), None))?);
                     }
                     Some(out)
                 } else {
@@ -287,30 +592,65 @@ impl<'a> Parsed for TransactionDetail<'a> {
             },
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::ParseError as PE;
+        use self::TransactionDetailField as F;
+        use self::TransactionDetailError as E;
+        match *err {
+            PE::Error(F::FundsType, E::FundsType(ref e), _) => {
+                raw.funds_type.as_ref().and_then(|ft| FundsType::locate(ft, e))
+            }
+            PE::Error(F::Text, E::Text(i, _), _) => {
+                raw.text.as_ref().and_then(|text| text.get(i).cloned())
+            }
+            _ => {
+                match err.field() {
+                    F::TypeCode => Some(raw.type_code),
+                    F::Amount => raw.amount,
+                    F::FundsType | F::Text => None,
+                    F::BankRefNum => raw.bank_ref_num,
+                    F::CustomerRefNum => raw.customer_ref_num,
+                }
+            }
+        }
+    }
 }
 
+impl Field for AccountTrailerField {
+    type Err = void::Void;
+}
 impl<'a> Parsed for AccountTrailer<'a> {
     type Raw = RawAccountTrailer<'a>;
     type Parsed = ParsedAccountTrailer;
     type Field = AccountTrailerField;
-    type Err = void::Void;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::AccountTrailerField as F;
         Ok(ParsedAccountTrailer {
             control_total: parse_strint(raw.control_total, F::ControlTotal)?,
             records_num: parse_strint(raw.records_num, F::RecordsNum)?,
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::AccountTrailerField as F;
+        Some(match err.field() {
+            F::ControlTotal => raw.control_total,
+            F::RecordsNum => raw.records_num,
+        })
+    }
 }
 
+impl Field for GroupTrailerField {
+    type Err = void::Void;
+}
 impl<'a> Parsed for GroupTrailer<'a> {
     type Raw = RawGroupTrailer<'a>;
     type Parsed = ParsedGroupTrailer;
     type Field = GroupTrailerField;
-    type Err = void::Void;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::GroupTrailerField as F;
         Ok(ParsedGroupTrailer {
             control_total: parse_strint(raw.control_total, F::ControlTotal)?,
@@ -318,15 +658,26 @@ impl<'a> Parsed for GroupTrailer<'a> {
             records_num: parse_strint(raw.records_num, F::RecordsNum)?,
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::GroupTrailerField as F;
+        Some(match err.field() {
+            F::ControlTotal => raw.control_total,
+            F::AccountsNum => raw.accounts_num,
+            F::RecordsNum => raw.records_num,
+        })
+    }
 }
 
+impl Field for FileTrailerField {
+    type Err = void::Void;
+}
 impl<'a> Parsed for FileTrailer<'a> {
     type Raw = RawFileTrailer<'a>;
     type Parsed = ParsedFileTrailer;
     type Field = FileTrailerField;
-    type Err = void::Void;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::FileTrailerField as F;
         Ok(ParsedFileTrailer {
             control_total: parse_strint(raw.control_total, F::ControlTotal)?,
@@ -334,6 +685,15 @@ impl<'a> Parsed for FileTrailer<'a> {
             records_num: parse_strint(raw.records_num, F::RecordsNum)?,
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::FileTrailerField as F;
+        Some(match err.field() {
+            F::ControlTotal => raw.control_total,
+            F::GroupsNum => raw.groups_num,
+            F::RecordsNum => raw.records_num,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -343,6 +703,17 @@ pub enum DateError {
     Month,
     Day,
 }
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DateError::All => "invalid date",
+            DateError::Year => "invalid year in date",
+            DateError::Month => "invalid month in date",
+            DateError::Day => "invalid day in date",
+        })
+    }
+}
+impl error::Error for DateError {}
 named!(
     date<&[u8], (&[u8], &[u8], &[u8]), DateError>,
     do_parse!(
@@ -376,6 +747,16 @@ pub enum TimeError {
     Hour,
     Minute,
 }
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            TimeError::All => "invalid time",
+            TimeError::Hour => "invalid hour in time",
+            TimeError::Minute => "invalid minute in time",
+        })
+    }
+}
+impl error::Error for TimeError {}
 named!(
     time<&[u8], (&[u8], &[u8]), TimeError>,
     do_parse!(
@@ -402,16 +783,39 @@ impl str::FromStr for Time {
 }
 
 #[derive(Debug, Clone)]
-pub enum AccountInfoError<'a> {
-    FundsType(ParseError<FundsType<'a>>),
+pub enum AccountInfoError {
+    FundsType(Box<ParseError<FundsTypeField>>),
+}
+impl fmt::Display for AccountInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccountInfoError::FundsType(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for AccountInfoError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            AccountInfoError::FundsType(ref e) => Some(e),
+        }
+    }
+}
+impl Field for AccountInfoField {
+    type Err = AccountInfoError;
 }
 impl<'a> Parsed for AccountInfo<'a> {
     type Raw = RawAccountInfo<'a>;
     type Parsed = ParsedAccountInfo;
     type Field = AccountInfoField;
-    type Err = AccountInfoError<'a>;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::ParseError as PE;
         use self::AccountInfoField as F;
         use self::AccountInfoError as E;
@@ -420,27 +824,75 @@ impl<'a> Parsed for AccountInfo<'a> {
             amount: parse_optstrint(raw.amount, F::Amount)?,
             item_count: parse_optstrint(raw.item_count, F::ItemCount)?,
             funds_type: raw.funds_type.as_ref().map_or(Ok(None), |ft| {
-                FundsType::parse(ft)
-                    .map_err(|e| PE::Error(F::FundsType, E::FundsType(e)))
+                FundsType::parse_with(ft, dialect)
+                    .map_err(|e| PE::Error(F::FundsType, E::FundsType(Box::new(e)), None))
                     .map(Some)
             })?,
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::ParseError as PE;
+        use self::AccountInfoField as F;
+        use self::AccountInfoError as E;
+        match *err {
+            PE::Error(F::FundsType, E::FundsType(ref e), _) => {
+                raw.funds_type.as_ref().and_then(|ft| FundsType::locate(ft, e))
+            }
+            _ => {
+                match err.field() {
+                    F::TypeCode => raw.type_code,
+                    F::Amount => raw.amount,
+                    F::ItemCount => raw.item_count,
+                    F::FundsType => None,
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub enum FundsTypeError<'a> {
+pub enum FundsTypeError {
     Date(DateError),
     Time(TimeError),
-    DistributedAvailDDist(usize, ParseError<DistributedAvailDistribution<'a>>),
+    DistributedAvailDDist(usize, ParseError<DistributedAvailDistributionField>),
+}
+impl fmt::Display for FundsTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FundsTypeError::Date(ref e) => e.fmt(f),
+            FundsTypeError::Time(ref e) => e.fmt(f),
+            FundsTypeError::DistributedAvailDDist(i, ref e) => {
+                write!(f, "distribution {}: {}", i + 1, e)
+            }
+        }
+    }
+}
+impl error::Error for FundsTypeError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        Some(match *self {
+            FundsTypeError::Date(ref e) => e,
+            FundsTypeError::Time(ref e) => e,
+            FundsTypeError::DistributedAvailDDist(_, ref e) => e,
+        })
+    }
+}
+impl Field for FundsTypeField {
+    type Err = FundsTypeError;
 }
 impl<'a> Parsed for FundsType<'a> {
     type Raw = RawFundsType<'a>;
     type Parsed = ParsedFundsType;
     type Field = FundsTypeField;
-    type Err = FundsTypeError<'a>;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
+        Self::parse_with(raw, &Spec)
+    }
+
+    fn parse_with(
+        raw: &Self::Raw,
+        dialect: &Dialect,
+    ) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::ParseError as PE;
         use self::FundsTypeField as F;
         use self::FundsTypeError as E;
@@ -468,7 +920,7 @@ impl<'a> Parsed for FundsType<'a> {
             }
             R::ValueDated { date, time } => {
                 P::ValueDated {
-                    date: parse_strfrom(date, F::ValueDatedDate, E::Date)?,
+                    date: parse_strfrom(short_date(date, dialect), F::ValueDatedDate, E::Date)?,
                     time: parse_optstrfrom(time, F::ValueDatedTime, E::Time)?,
                 }
             }
@@ -483,6 +935,7 @@ impl<'a> Parsed for FundsType<'a> {
                                     PE::Error(
                                         F::DistributedAvailDDists,
                                         E::DistributedAvailDDist(i, e),
+                                        None,
                                     )
                                 })?)
                         }
@@ -492,19 +945,55 @@ impl<'a> Parsed for FundsType<'a> {
             }
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::ParseError as PE;
+        use self::FundsTypeField as F;
+        use self::FundsTypeError as E;
+
+        use self::RawFundsType as R;
+        match (raw, err.field()) {
+            (&R::DistributedAvailS { immediate, .. }, F::DistributedAvailSImmediate) => immediate,
+            (&R::DistributedAvailS { one_day, .. }, F::DistributedAvailSOneDay) => one_day,
+            (&R::DistributedAvailS { more_than_one_day, .. },
+             F::DistributedAvailSMoreThanOneDay) => more_than_one_day,
+            (&R::ValueDated { date, .. }, F::ValueDatedDate) => Some(date),
+            (&R::ValueDated { time, .. }, F::ValueDatedTime) => time,
+            (&R::DistributedAvailD { num, .. }, F::DistributedAvailDNum) => Some(num),
+            (&R::DistributedAvailD { ref dists, .. }, F::DistributedAvailDDists) => {
+                match *err {
+                    PE::Error(_, E::DistributedAvailDDist(i, ref e), _) => {
+                        dists.get(i).and_then(|dist| DistributedAvailDistribution::locate(dist, e))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
+impl Field for DistributedAvailDistributionField {
+    type Err = void::Void;
+}
 impl<'a> Parsed for DistributedAvailDistribution<'a> {
     type Raw = RawDistributedAvailDistribution<'a>;
     type Parsed = ParsedDistributedAvailDistribution;
     type Field = DistributedAvailDistributionField;
-    type Err = void::Void;
 
-    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self>> {
+    fn parse(raw: &Self::Raw) -> Result<Self::Parsed, ParseError<Self::Field>> {
         use self::DistributedAvailDistributionField as F;
         Ok(ParsedDistributedAvailDistribution {
             days: parse_strint(raw.days, F::Days)?,
             amount: parse_strint(raw.amount, F::Amount)?,
         })
     }
+
+    fn locate<'r>(raw: &'r Self::Raw, err: &ParseError<Self::Field>) -> Option<&'r [u8]> {
+        use self::DistributedAvailDistributionField as F;
+        Some(match err.field() {
+            F::Days => raw.days,
+            F::Amount => raw.amount,
+        })
+    }
 }
@@ -0,0 +1,436 @@
+extern crate baimax;
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+#[cfg(feature = "camt")]
+use baimax::camt;
+use baimax::csv::{self, Column, CsvOptions};
+use baimax::data::{self, Amount, Direction, ParseOptions, Strictness};
+use baimax::dialect::Dialects;
+#[cfg(any(feature = "json", feature = "yaml"))]
+use baimax::export;
+use baimax::swift;
+
+const USAGE: &str = "\
+Usage: baimax <command> [options] [FILE...]
+
+Reads each FILE, or standard input if there are none or FILE is -.
+
+Commands:
+    validate    Report every error and warning, exiting with 1 if there were errors
+    dump        Print the file as a tree
+    json        Print the file as JSON, in the export schema
+    yaml        Print the file as YAML, in the export schema
+                (each needs baimax built with the feature of the same name)
+    csv         Print a CSV row per transaction detail
+    camt053     Print the final groups as an ISO 20022 camt.053 statement
+    camt052     Print the interim groups as an ISO 20022 camt.052 report
+                (both need baimax built with the camt feature)
+    mt940       Print the final groups as SWIFT MT940 statements
+    mt942       Print the interim groups as SWIFT MT942 reports
+    stats       Print counts and totals per group and account
+
+Options:
+    --lenient   Tolerate spec violations that leave the file usable, as warnings
+    --checked   Check physical record lengths and block sizes
+    --from=FORMAT
+                Read files as bai (the default), mt940 or camt053
+    --sender=ID, --receiver=ID
+                With --from=mt940 or camt053, the sender and receiver of
+                statements that don't name them
+    --infos     With csv, print a row per account status and summary instead
+    --columns=COLUMN,...
+                With csv, the columns to print, such as sender, account_num,
+                type_code, description, signed_amount or text
+    -h, --help  Print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Validate,
+    Dump,
+    Json,
+    Yaml,
+    Csv,
+    Camt053,
+    Camt052,
+    Mt940,
+    Mt942,
+    Stats,
+}
+
+/// The formats files can be read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Bai,
+    Mt940,
+    Camt053,
+}
+
+/// Exit codes: 1 for invalid files, 2 for bad arguments or files that can't be read.
+const EXIT_INVALID: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = match args.next().as_ref().map(|s| &**s) {
+        Some("validate") => Command::Validate,
+        Some("dump") => Command::Dump,
+        Some("json") => Command::Json,
+        Some("yaml") => Command::Yaml,
+        Some("csv") => Command::Csv,
+        Some("camt053") => Command::Camt053,
+        Some("camt052") => Command::Camt052,
+        Some("mt940") => Command::Mt940,
+        Some("mt942") => Command::Mt942,
+        Some("stats") => Command::Stats,
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        Some(other) => usage_error(&format!("unknown command {:?}", other)),
+        None => usage_error("missing command"),
+    };
+
+    let mut options = ParseOptions::default();
+    let mut csv_options = CsvOptions::default();
+    let mut csv_infos = false;
+    let mut format = Format::Bai;
+    let mut sender = None;
+    let mut receiver = None;
+    let mut paths = Vec::new();
+    for arg in args {
+        match &*arg {
+            "--lenient" => options.strictness = Strictness::Lenient,
+            "--checked" => options.check_layout = true,
+            "--infos" => csv_infos = true,
+            _ if arg.starts_with("--from=") => {
+                format = match &arg["--from=".len()..] {
+                    "bai" => Format::Bai,
+                    "mt940" => Format::Mt940,
+                    "camt053" => Format::Camt053,
+                    other => usage_error(&format!("unknown format {:?}", other)),
+                }
+            }
+            _ if arg.starts_with("--sender=") => {
+                sender = Some(data::Party(arg["--sender=".len()..].to_owned()))
+            }
+            _ if arg.starts_with("--receiver=") => {
+                receiver = Some(data::Party(arg["--receiver=".len()..].to_owned()))
+            }
+            _ if arg.starts_with("--columns=") => {
+                let columns = arg["--columns=".len()..]
+                    .split(',')
+                    .map(|name| {
+                        name.parse().unwrap_or_else(|()| {
+                            usage_error(&format!("unknown column {:?}", name))
+                        })
+                    })
+                    .collect::<Vec<Column>>();
+                csv_options.transaction_columns = columns.clone();
+                csv_options.info_columns = columns;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                usage_error(&format!("unknown option {:?}", arg))
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        paths.push("-".to_owned());
+    }
+    let camt = match command {
+        Command::Camt053 | Command::Camt052 => true,
+        _ => format == Format::Camt053,
+    };
+    if camt && !cfg!(feature = "camt") {
+        usage_error("camt053 and camt052 need baimax built with the camt feature");
+    }
+    if command == Command::Json && !cfg!(feature = "json") {
+        usage_error("json needs baimax built with the json feature");
+    }
+    if command == Command::Yaml && !cfg!(feature = "yaml") {
+        usage_error("yaml needs baimax built with the yaml feature");
+    }
+    let parties = match (sender, receiver) {
+        (Some(sender), Some(receiver)) => Some((sender, receiver)),
+        (None, None) => None,
+        _ => usage_error("--sender and --receiver must be given together"),
+    };
+
+    let mut code = 0;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for path in &paths {
+        let name = if path == "-" { "<stdin>" } else { path };
+        let input = match read_input(path) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                code = EXIT_USAGE;
+                continue;
+            }
+        };
+        let result = match command {
+            Command::Validate if format == Format::Bai => {
+                validate(&mut out, name, &input, &options)
+            }
+            _ => {
+                match process(format, &input, &options, parties.as_ref()) {
+                    Ok((file, warnings)) => {
+                        for warning in warnings {
+                            eprintln!("{}: warning: {}", name, warning);
+                        }
+                        let written = match command {
+                            Command::Csv if csv_infos => {
+                                csv::write_infos(&file, &csv_options, &mut out)
+                            }
+                            Command::Csv => csv::write_transactions(&file, &csv_options, &mut out),
+                            Command::Validate => writeln!(out, "{}: ok", name),
+                            _ => output(&mut out, command, &file),
+                        };
+                        written.map(|()| true)
+                    }
+                    Err(e) => {
+                        eprintln!("{}: error: {}", name, e);
+                        Ok(false)
+                    }
+                }
+            }
+        };
+        match result {
+            Ok(true) => {}
+            Ok(false) => code = code.max(EXIT_INVALID),
+            // Output piped into something like `head` that stopped reading.
+            Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(code),
+            Err(e) => {
+                eprintln!("baimax: {}", e);
+                process::exit(EXIT_USAGE);
+            }
+        }
+    }
+    process::exit(code);
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("baimax: {}\n\n{}", msg, USAGE);
+    process::exit(EXIT_USAGE);
+}
+
+/// Reads `input` in `format`, with the warnings from processing it leniently. MT940 and
+/// camt.053 statements that don't name their sender and receiver take them from `parties`.
+fn process(
+    format: Format,
+    input: &[u8],
+    options: &ParseOptions,
+    parties: Option<&(data::Party, data::Party)>,
+) -> Result<(data::File, Vec<data::Warning>), String> {
+    match format {
+        Format::Bai => data::File::process_with(input, options).map_err(|e| e.to_string()),
+        Format::Mt940 => {
+            let read = match parties {
+                Some(&(ref sender, ref receiver)) => {
+                    swift::read_mt940_with_parties(input, sender.clone(), receiver.clone())
+                }
+                None => swift::read_mt940(input),
+            };
+            read.map(|file| (file, Vec::new())).map_err(|e| e.to_string())
+        }
+        #[cfg(feature = "camt")]
+        Format::Camt053 => {
+            let read = match parties {
+                Some(&(ref sender, ref receiver)) => {
+                    camt::read_camt053_with_parties(input, sender.clone(), receiver.clone())
+                }
+                None => camt::read_camt053(input),
+            };
+            read.map(|file| (file, Vec::new())).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "camt"))]
+        Format::Camt053 => unreachable!(),
+    }
+}
+
+fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    let mut input = Vec::new();
+    if path == "-" {
+        io::stdin().read_to_end(&mut input)?;
+    } else {
+        fs::File::open(path)?.read_to_end(&mut input)?;
+    }
+    Ok(input)
+}
+
+/// Prints every error and warning in the file, returning whether it had no errors.
+fn validate<W: Write>(
+    out: &mut W,
+    name: &str,
+    input: &[u8],
+    options: &ParseOptions,
+) -> io::Result<bool> {
+    let recovered = data::File::process_recovering(input, options, &Dialects::default());
+    for error in &recovered.errors {
+        writeln!(out, "{}: error: {}", name, error)?;
+    }
+    for warning in &recovered.warnings {
+        writeln!(out, "{}: warning: {}", name, warning)?;
+    }
+    let valid = recovered.errors.is_empty();
+    if valid {
+        writeln!(out, "{}: ok", name)?;
+    }
+    Ok(valid)
+}
+
+fn output<W: Write>(out: &mut W, command: Command, file: &data::File) -> io::Result<()> {
+    match command {
+        Command::Dump => writeln!(out, "{}", file),
+        #[cfg(feature = "json")]
+        Command::Json => {
+            export::write_json(file, &mut *out)?;
+            writeln!(out)
+        }
+        #[cfg(feature = "yaml")]
+        Command::Yaml => {
+            export::write_yaml(file, &mut *out)?;
+            writeln!(out)
+        }
+        #[cfg(not(feature = "json"))]
+        Command::Json => unreachable!(),
+        #[cfg(not(feature = "yaml"))]
+        Command::Yaml => unreachable!(),
+        #[cfg(feature = "camt")]
+        Command::Camt053 => camt::write_camt053(file, out),
+        #[cfg(feature = "camt")]
+        Command::Camt052 => camt::write_camt052(file, out),
+        #[cfg(not(feature = "camt"))]
+        Command::Camt053 | Command::Camt052 => unreachable!(),
+        Command::Mt940 => swift::write_mt940(file, out),
+        Command::Mt942 => swift::write_mt942(file, out),
+        Command::Stats => stats(out, file),
+        Command::Validate | Command::Csv => unreachable!(),
+    }
+}
+
+/// The number and total of the credits and debits in an account or group, by currency.
+#[derive(Default)]
+struct Totals {
+    credits: Vec<(usize, Amount)>,
+    debits: Vec<(usize, Amount)>,
+}
+
+impl Totals {
+    fn add(&mut self, direction: Direction, count: usize, amount: Amount) {
+        let totals = match direction {
+            Direction::Credit => &mut self.credits,
+            Direction::Debit => &mut self.debits,
+            Direction::NonMonetary => return,
+        };
+        match totals.iter_mut().find(|t| t.1.currency == amount.currency) {
+            Some(total) => {
+                total.0 += count;
+                total.1.value += amount.value;
+            }
+            None => totals.push((count, amount)),
+        }
+    }
+
+    fn extend(&mut self, other: &Totals) {
+        for &(count, amount) in &other.credits {
+            self.add(Direction::Credit, count, amount);
+        }
+        for &(count, amount) in &other.debits {
+            self.add(Direction::Debit, count, amount);
+        }
+    }
+}
+
+impl fmt::Display for Totals {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_totals(f, "credits", &self.credits)?;
+        f.write_str("; ")?;
+        write_totals(f, "debits", &self.debits)
+    }
+}
+
+fn write_totals(f: &mut fmt::Formatter, label: &str, totals: &[(usize, Amount)]) -> fmt::Result {
+    if totals.is_empty() {
+        return write!(f, "0 {}", label);
+    }
+    for (i, &(count, total)) in totals.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{} {} totaling {}", count, label, total)?;
+    }
+    Ok(())
+}
+
+fn stats<W: Write>(out: &mut W, file: &data::File) -> io::Result<()> {
+    let accounts_num: usize = file.groups.iter().map(|g| g.accounts.len()).sum();
+    writeln!(
+        out,
+        "File {} from {} to {} ({}): {} groups, {} accounts, control total {}",
+        file.ident,
+        file.sender,
+        file.receiver,
+        file.version,
+        file.groups.len(),
+        accounts_num,
+        file.control_total()
+    )?;
+    for (i, group) in file.groups.iter().enumerate() {
+        let account_totals: Vec<Totals> = group
+            .accounts
+            .iter()
+            .map(|account| {
+                let mut totals = Totals::default();
+                for detail in &account.transaction_details {
+                    if let Some(amount) = detail.amount {
+                        totals.add(detail.code.direction(), 1, amount);
+                    }
+                }
+                totals
+            })
+            .collect();
+        let mut group_totals = Totals::default();
+        for totals in &account_totals {
+            group_totals.extend(totals);
+        }
+
+        let originator = group
+            .originator
+            .as_ref()
+            .map_or_else(|| "an unknown originator".to_owned(), |o| o.to_string());
+        writeln!(
+            out,
+            "  Group {} from {} as of {}: {} accounts, control total {}",
+            i + 1,
+            originator,
+            group.as_of,
+            group.accounts.len(),
+            group.control_total()
+        )?;
+        writeln!(out, "    {}", group_totals)?;
+        for (account, totals) in group.accounts.iter().zip(&account_totals) {
+            writeln!(
+                out,
+                "    Account {} ({}): {} infos, {} transaction details, control total {}",
+                account.customer_account.0,
+                account.currency_def(group.currency_def()),
+                account.infos.len(),
+                account.transaction_details.len(),
+                account.control_total()
+            )?;
+            writeln!(out, "      {}", totals)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,528 @@
+//! Fluent builders for files, for mock feeds and tests.
+//!
+//! ```ignore
+//! let file = FileBuilder::new("122099999", "123456789", creation, 1)
+//!     .group(GroupBuilder::new(GroupStatus::Update, as_of)
+//!         .originator("031001234")
+//!         .account(AccountBuilder::new("0123456789")
+//!             .status(opening_ledger, 4350000)
+//!             .summary(total_lockbox, 1020000).item_count(3)
+//!             .detail(lockbox_deposit, 450000).funds(FundsType::ImmediateAvail)
+//!             .text("LOCK BOX NO.68751")))
+//!     .build()?;
+//! ```
+//!
+//! Amounts are in the minor units of the account's currency. Trailer control totals and record
+//! counts are left to the writer, and anything the converter would reject is refused by `build`.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use penny::Currency;
+
+use ast::convert::{AccountConvError, AccountInfoConvError, ConvertError, FundsTypeConvError,
+                   GroupConvError, TransactionDetailConvError};
+use data::{self, AccountInfo, AsOfDateModifier, BaiDateOrTime, BaiDateTime, DetailCode,
+           FundsType, GroupStatus, StatusCode, SummaryCode, TransactionDetail, Version};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize))]
+pub enum BuildError {
+    /// Something the converter would reject, where it would report it.
+    Convert(ConvertError),
+    /// A transaction detail with a negative amount, which can't be written.
+    NegativeAmount {
+        group: usize,
+        account: usize,
+        transaction: usize,
+    },
+    /// A modifier, such as `item_count` or `text`, with no account info or transaction detail
+    /// before it to modify.
+    Misplaced {
+        group: usize,
+        account: usize,
+        modifier: &'static str,
+    },
+}
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::Convert(ref e) => e.fmt(f),
+            BuildError::NegativeAmount {
+                group,
+                account,
+                transaction,
+            } => {
+                write!(
+                    f,
+                    "transaction detail amounts can't be negative \
+                     (group {}, account {}, transaction detail {})",
+                    group + 1,
+                    account + 1,
+                    transaction + 1
+                )
+            }
+            BuildError::Misplaced {
+                group,
+                account,
+                modifier,
+            } => {
+                write!(
+                    f,
+                    "{} has nothing before it to modify (group {}, account {})",
+                    modifier,
+                    group + 1,
+                    account + 1
+                )
+            }
+        }
+    }
+}
+impl error::Error for BuildError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            BuildError::Convert(ref e) => Some(e),
+            BuildError::NegativeAmount { .. } | BuildError::Misplaced { .. } => None,
+        }
+    }
+}
+
+/// An error building a file with `FileBuilder::write_to`, or writing it.
+#[derive(Debug)]
+pub enum WriteError {
+    Build(BuildError),
+    Io(io::Error),
+}
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WriteError::Build(ref e) => e.fmt(f),
+            WriteError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for WriteError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            WriteError::Build(ref e) => Some(e),
+            WriteError::Io(ref e) => Some(e),
+        }
+    }
+}
+impl From<BuildError> for WriteError {
+    fn from(e: BuildError) -> Self {
+        WriteError::Build(e)
+    }
+}
+impl From<io::Error> for WriteError {
+    fn from(e: io::Error) -> Self {
+        WriteError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileBuilder {
+    sender: String,
+    receiver: String,
+    creation: BaiDateTime,
+    ident: u32,
+    version: Version,
+    groups: Vec<GroupBuilder>,
+}
+
+impl FileBuilder {
+    pub fn new<T>(sender: &str, receiver: &str, creation: T, ident: u32) -> Self
+    where
+        T: Into<BaiDateTime>,
+    {
+        FileBuilder {
+            sender: sender.to_owned(),
+            receiver: receiver.to_owned(),
+            creation: creation.into(),
+            ident,
+            version: Version::default(),
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn group(mut self, group: GroupBuilder) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    pub fn build(self) -> Result<data::File, BuildError> {
+        let version = self.version;
+        let groups = self.groups
+            .into_iter()
+            .enumerate()
+            .map(|(i, group)| group.build(i, version))
+            .collect::<Result<_, _>>()?;
+        Ok(data::File {
+            sender: data::Party(self.sender),
+            receiver: data::Party(self.receiver),
+            creation: self.creation,
+            ident: data::FileIdent(self.ident),
+            version,
+            groups,
+        })
+    }
+
+    /// Builds the file and writes it as BAI2, with its trailers.
+    pub fn write_to<W: io::Write>(self, w: &mut W) -> Result<(), WriteError> {
+        Ok(self.build()?.write_to(w)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupBuilder {
+    ultimate_receiver: Option<String>,
+    originator: Option<String>,
+    status: GroupStatus,
+    as_of: BaiDateOrTime,
+    currency: Option<Currency>,
+    as_of_date_mod: Option<AsOfDateModifier>,
+    accounts: Vec<AccountBuilder>,
+}
+
+impl GroupBuilder {
+    pub fn new<T: Into<BaiDateOrTime>>(status: GroupStatus, as_of: T) -> Self {
+        GroupBuilder {
+            ultimate_receiver: None,
+            originator: None,
+            status,
+            as_of: as_of.into(),
+            currency: None,
+            as_of_date_mod: None,
+            accounts: Vec::new(),
+        }
+    }
+
+    pub fn ultimate_receiver(mut self, ultimate_receiver: &str) -> Self {
+        self.ultimate_receiver = Some(ultimate_receiver.to_owned());
+        self
+    }
+
+    pub fn originator(mut self, originator: &str) -> Self {
+        self.originator = Some(originator.to_owned());
+        self
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    pub fn as_of_date_mod(mut self, as_of_date_mod: AsOfDateModifier) -> Self {
+        self.as_of_date_mod = Some(as_of_date_mod);
+        self
+    }
+
+    pub fn account(mut self, account: AccountBuilder) -> Self {
+        self.accounts.push(account);
+        self
+    }
+
+    fn build(self, group_num: usize, version: Version) -> Result<data::Group, BuildError> {
+        let group_err = |err| {
+            BuildError::Convert(ConvertError::Group {
+                group: group_num,
+                err,
+                position: None,
+            })
+        };
+        if version == Version::Btrs3 {
+            match self.status {
+                GroupStatus::Update => {}
+                status => return Err(group_err(GroupConvError::RetiredStatus(status))),
+            }
+            if let Some(m @ AsOfDateModifier::InterimPrevious) = self.as_of_date_mod {
+                return Err(group_err(GroupConvError::RetiredAsOfDateMod(m)));
+            }
+        }
+        let group_cur = self.currency.unwrap_or(Currency::USD);
+        let accounts = self.accounts
+            .into_iter()
+            .enumerate()
+            .map(|(i, account)| account.build(group_cur, version, group_num, i))
+            .collect::<Result<_, _>>()?;
+        Ok(data::Group {
+            ultimate_receiver: self.ultimate_receiver.map(data::Party),
+            originator: self.originator.map(data::Party),
+            status: self.status,
+            as_of: self.as_of,
+            currency: self.currency,
+            as_of_date_mod: self.as_of_date_mod,
+            accounts,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InfoCode {
+    Status(StatusCode),
+    Summary(SummaryCode),
+}
+
+#[derive(Debug, Clone)]
+struct PendingInfo {
+    code: InfoCode,
+    amount: i64,
+    item_count: Option<u32>,
+    funds: Option<FundsType>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingDetail {
+    code: DetailCode,
+    amount: i64,
+    funds: Option<FundsType>,
+    bank_ref_num: Option<String>,
+    customer_ref_num: Option<String>,
+    text: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Last {
+    Info,
+    Detail,
+}
+
+/// Builds an account's infos and transaction details. Modifiers, like `item_count` and `funds`,
+/// apply to whichever was added last.
+#[derive(Debug, Clone)]
+pub struct AccountBuilder {
+    customer_account: String,
+    currency: Option<Currency>,
+    infos: Vec<PendingInfo>,
+    details: Vec<PendingDetail>,
+    last: Option<Last>,
+    misplaced: Option<&'static str>,
+}
+
+impl AccountBuilder {
+    pub fn new(customer_account: &str) -> Self {
+        AccountBuilder {
+            customer_account: customer_account.to_owned(),
+            currency: None,
+            infos: Vec::new(),
+            details: Vec::new(),
+            last: None,
+            misplaced: None,
+        }
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    pub fn status(self, code: StatusCode, amount: i64) -> Self {
+        self.info(InfoCode::Status(code), amount)
+    }
+
+    pub fn summary(self, code: SummaryCode, amount: i64) -> Self {
+        self.info(InfoCode::Summary(code), amount)
+    }
+
+    fn info(mut self, code: InfoCode, amount: i64) -> Self {
+        self.infos.push(PendingInfo {
+            code,
+            amount,
+            item_count: None,
+            funds: None,
+        });
+        self.last = Some(Last::Info);
+        self
+    }
+
+    pub fn detail(mut self, code: DetailCode, amount: i64) -> Self {
+        self.details.push(PendingDetail {
+            code,
+            amount,
+            funds: None,
+            bank_ref_num: None,
+            customer_ref_num: None,
+            text: Vec::new(),
+        });
+        self.last = Some(Last::Detail);
+        self
+    }
+
+    /// Sets the item count of the last account info.
+    pub fn item_count(mut self, item_count: u32) -> Self {
+        match (self.last, self.infos.last_mut()) {
+            (Some(Last::Info), Some(info)) => info.item_count = Some(item_count),
+            _ => self.misplace("item_count"),
+        }
+        self
+    }
+
+    /// Sets the funds type of the last account info or transaction detail.
+    pub fn funds(mut self, funds: FundsType) -> Self {
+        match self.last {
+            Some(Last::Info) => self.infos.last_mut().unwrap().funds = Some(funds),
+            Some(Last::Detail) => self.details.last_mut().unwrap().funds = Some(funds),
+            None => self.misplace("funds"),
+        }
+        self
+    }
+
+    pub fn bank_ref(mut self, bank_ref_num: &str) -> Self {
+        if let Some(detail) = self.last_detail("bank_ref") {
+            detail.bank_ref_num = Some(bank_ref_num.to_owned());
+        }
+        self
+    }
+
+    pub fn customer_ref(mut self, customer_ref_num: &str) -> Self {
+        if let Some(detail) = self.last_detail("customer_ref") {
+            detail.customer_ref_num = Some(customer_ref_num.to_owned());
+        }
+        self
+    }
+
+    /// Adds a line of text to the last transaction detail.
+    pub fn text(mut self, text: &str) -> Self {
+        if let Some(detail) = self.last_detail("text") {
+            detail.text.push(text.to_owned());
+        }
+        self
+    }
+
+    fn last_detail(&mut self, modifier: &'static str) -> Option<&mut PendingDetail> {
+        if self.last == Some(Last::Detail) {
+            self.details.last_mut()
+        } else {
+            self.misplace(modifier);
+            None
+        }
+    }
+
+    fn misplace(&mut self, modifier: &'static str) {
+        if self.misplaced.is_none() {
+            self.misplaced = Some(modifier);
+        }
+    }
+
+    fn build(
+        self,
+        group_cur: Currency,
+        version: Version,
+        group: usize,
+        account: usize,
+    ) -> Result<data::Account, BuildError> {
+        if let Some(modifier) = self.misplaced {
+            return Err(BuildError::Misplaced {
+                group,
+                account,
+                modifier,
+            });
+        }
+        let currency = self.currency.unwrap_or(group_cur);
+        let amount = |a| data::Amount::new(a, currency);
+
+        let mut infos = Vec::with_capacity(self.infos.len());
+        for (i, info) in self.infos.into_iter().enumerate() {
+            let info_err = |err| {
+                BuildError::Convert(ConvertError::Account {
+                    group,
+                    account,
+                    err: AccountConvError::AccountInfo(i, err),
+                    position: None,
+                })
+            };
+            infos.push(match info.code {
+                InfoCode::Status(code) => {
+                    if info.item_count.is_some() {
+                        return Err(info_err(AccountInfoConvError::StatusItemCount));
+                    }
+                    if info.funds.is_some() {
+                        return Err(info_err(AccountInfoConvError::StatusFunds));
+                    }
+                    AccountInfo::Status {
+                        code,
+                        amount: Some(amount(info.amount)),
+                    }
+                }
+                InfoCode::Summary(code) => {
+                    if SummaryCode::from_version(code.into(), version).is_err() {
+                        return Err(info_err(AccountInfoConvError::InvalidCode));
+                    }
+                    if info.amount < 0 {
+                        return Err(info_err(AccountInfoConvError::SummaryNegativeAmount));
+                    }
+                    if let Err(e) = check_funds(info.funds.as_ref(), version) {
+                        return Err(info_err(AccountInfoConvError::Funds(e)));
+                    }
+                    AccountInfo::Summary {
+                        code,
+                        amount: Some(amount(info.amount)),
+                        item_count: info.item_count,
+                        funds: info.funds,
+                    }
+                }
+            });
+        }
+
+        let mut transaction_details = Vec::with_capacity(self.details.len());
+        for (i, detail) in self.details.into_iter().enumerate() {
+            let detail_err = |err| {
+                BuildError::Convert(ConvertError::TransactionDetail {
+                    group,
+                    account,
+                    transaction: i,
+                    err,
+                    position: None,
+                })
+            };
+            let code = u16::from(detail.code);
+            if DetailCode::from_version(code, version).is_err() {
+                return Err(detail_err(TransactionDetailConvError::DetailCode(code)));
+            }
+            if detail.amount < 0 {
+                return Err(BuildError::NegativeAmount {
+                    group,
+                    account,
+                    transaction: i,
+                });
+            }
+            if let Err(e) = check_funds(detail.funds.as_ref(), version) {
+                return Err(detail_err(TransactionDetailConvError::Funds(e)));
+            }
+            transaction_details.push(TransactionDetail {
+                code: detail.code,
+                amount: Some(amount(detail.amount)),
+                funds: detail.funds,
+                bank_ref_num: detail.bank_ref_num.map(data::ReferenceNum),
+                customer_ref_num: detail.customer_ref_num.map(data::ReferenceNum),
+                text: if detail.text.is_empty() {
+                    None
+                } else {
+                    Some(detail.text)
+                },
+            });
+        }
+
+        Ok(data::Account {
+            customer_account: data::AccountNumber(self.customer_account),
+            currency: self.currency,
+            infos,
+            transaction_details,
+        })
+    }
+}
+
+fn check_funds(funds: Option<&FundsType>, version: Version) -> Result<(), FundsTypeConvError> {
+    match funds {
+        Some(&FundsType::DistributedAvailD(_)) if version == Version::Btrs3 => {
+            Err(FundsTypeConvError::RetiredDistributedAvailD)
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,365 @@
+//! Flattened CSV tables for spreadsheets: one row per transaction detail, or one per account
+//! status and summary, each carrying the file, group and account it came from.
+
+use std::io;
+use std::str::FromStr;
+
+use penny::Currency;
+
+use data::{self, Direction};
+
+/// A column of a table. Columns that don't apply to a row, like `ItemCount` for a transaction
+/// detail, are left empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Sender,
+    Receiver,
+    FileIdent,
+    Originator,
+    GroupStatus,
+    AsOfDate,
+    AsOfDateMod,
+    AccountNum,
+    /// The account's currency, or its group's when it has none.
+    Currency,
+    /// "status", "summary" or "detail".
+    Level,
+    TypeCode,
+    /// The description of the type code, from the spec or a bank's registry.
+    Description,
+    /// "credit", "debit" or "non_monetary". Empty for statuses.
+    Direction,
+    Amount,
+    /// The amount, negative for debits. Status amounts are already signed.
+    SignedAmount,
+    ItemCount,
+    FundsType,
+    BankRefNum,
+    CustomerRefNum,
+    Text,
+}
+
+impl Column {
+    /// The column's name, used as its header.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Column::Sender => "sender",
+            Column::Receiver => "receiver",
+            Column::FileIdent => "file_ident",
+            Column::Originator => "originator",
+            Column::GroupStatus => "group_status",
+            Column::AsOfDate => "as_of_date",
+            Column::AsOfDateMod => "as_of_date_mod",
+            Column::AccountNum => "account_num",
+            Column::Currency => "currency",
+            Column::Level => "level",
+            Column::TypeCode => "type_code",
+            Column::Description => "description",
+            Column::Direction => "direction",
+            Column::Amount => "amount",
+            Column::SignedAmount => "signed_amount",
+            Column::ItemCount => "item_count",
+            Column::FundsType => "funds_type",
+            Column::BankRefNum => "bank_ref_num",
+            Column::CustomerRefNum => "customer_ref_num",
+            Column::Text => "text",
+        }
+    }
+
+    /// The columns of the transaction table by default.
+    pub fn transaction_defaults() -> Vec<Column> {
+        vec![
+            Column::Sender,
+            Column::AsOfDate,
+            Column::GroupStatus,
+            Column::AccountNum,
+            Column::Currency,
+            Column::TypeCode,
+            Column::Description,
+            Column::SignedAmount,
+            Column::FundsType,
+            Column::BankRefNum,
+            Column::CustomerRefNum,
+            Column::Text,
+        ]
+    }
+
+    /// The columns of the account info table by default.
+    pub fn info_defaults() -> Vec<Column> {
+        vec![
+            Column::Sender,
+            Column::AsOfDate,
+            Column::GroupStatus,
+            Column::AccountNum,
+            Column::Currency,
+            Column::Level,
+            Column::TypeCode,
+            Column::Description,
+            Column::SignedAmount,
+            Column::ItemCount,
+            Column::FundsType,
+        ]
+    }
+}
+
+const COLUMNS: &[Column] = &[
+    Column::Sender,
+    Column::Receiver,
+    Column::FileIdent,
+    Column::Originator,
+    Column::GroupStatus,
+    Column::AsOfDate,
+    Column::AsOfDateMod,
+    Column::AccountNum,
+    Column::Currency,
+    Column::Level,
+    Column::TypeCode,
+    Column::Description,
+    Column::Direction,
+    Column::Amount,
+    Column::SignedAmount,
+    Column::ItemCount,
+    Column::FundsType,
+    Column::BankRefNum,
+    Column::CustomerRefNum,
+    Column::Text,
+];
+
+impl FromStr for Column {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Column, ()> {
+        let s = s.trim().to_ascii_lowercase();
+        COLUMNS.iter().cloned().find(|c| c.name() == s).ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub transaction_columns: Vec<Column>,
+    pub info_columns: Vec<Column>,
+    /// Joins the lines of a transaction detail's text.
+    pub text_separator: String,
+    /// Whether to start each table with a row of column names.
+    pub headers: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            transaction_columns: Column::transaction_defaults(),
+            info_columns: Column::info_defaults(),
+            text_separator: " ".to_owned(),
+            headers: true,
+        }
+    }
+}
+
+/// Writes a row for every transaction detail in `file`.
+pub fn write_transactions<W: io::Write>(
+    file: &data::File,
+    options: &CsvOptions,
+    mut w: W,
+) -> io::Result<()> {
+    let columns = &options.transaction_columns;
+    if options.headers {
+        write_header(&mut w, columns)?;
+    }
+    for group in &file.groups {
+        for account in &group.accounts {
+            let row = Row::new(file, group, account);
+            for detail in &account.transaction_details {
+                let cells = columns
+                    .iter()
+                    .map(|&c| row.detail(c, detail, options))
+                    .collect::<io::Result<Vec<_>>>()?;
+                write_row(&mut w, cells.into_iter())?;
+            }
+        }
+    }
+    w.flush()
+}
+
+/// Writes a row for every account status and summary in `file`.
+pub fn write_infos<W: io::Write>(
+    file: &data::File,
+    options: &CsvOptions,
+    mut w: W,
+) -> io::Result<()> {
+    let columns = &options.info_columns;
+    if options.headers {
+        write_header(&mut w, columns)?;
+    }
+    for group in &file.groups {
+        for account in &group.accounts {
+            let row = Row::new(file, group, account);
+            for info in &account.infos {
+                let cells = columns
+                    .iter()
+                    .map(|&c| row.info(c, info))
+                    .collect::<io::Result<Vec<_>>>()?;
+                write_row(&mut w, cells.into_iter())?;
+            }
+        }
+    }
+    w.flush()
+}
+
+/// The file, group and account a row inherits.
+struct Row<'a> {
+    file: &'a data::File,
+    group: &'a data::Group,
+    account: &'a data::Account,
+    currency: Currency,
+}
+
+impl<'a> Row<'a> {
+    fn new(file: &'a data::File, group: &'a data::Group, account: &'a data::Account) -> Self {
+        Row {
+            file,
+            group,
+            account,
+            currency: account.currency_def(group.currency_def()),
+        }
+    }
+
+    /// Cells inherited from the file, group or account, or `None` for the row's own.
+    fn inherited(&self, column: Column) -> Option<String> {
+        let cell = match column {
+            Column::Sender => self.file.sender.0.clone(),
+            Column::Receiver => self.file.receiver.0.clone(),
+            Column::FileIdent => self.file.ident.0.to_string(),
+            Column::Originator => self.group.originator.as_ref().map_or_else(
+                String::new,
+                |o| o.0.clone(),
+            ),
+            Column::GroupStatus => self.group.status.to_string(),
+            Column::AsOfDate => self.group.as_of.clone().date().to_string(),
+            Column::AsOfDateMod => self.group.as_of_date_mod.map_or_else(
+                String::new,
+                |m| m.to_string(),
+            ),
+            Column::AccountNum => self.account.customer_account.0.clone(),
+            Column::Currency => self.currency.to_string(),
+            _ => return None,
+        };
+        Some(cell)
+    }
+
+    fn detail(
+        &self,
+        column: Column,
+        detail: &data::TransactionDetail,
+        options: &CsvOptions,
+    ) -> io::Result<String> {
+        if let Some(cell) = self.inherited(column) {
+            return Ok(cell);
+        }
+        Ok(match column {
+            Column::Level => "detail".to_owned(),
+            Column::TypeCode => format!("{:03}", u16::from(detail.code)),
+            Column::Description => description(data::TypeCode::Detail(detail.code)),
+            Column::Direction => direction(detail.code.direction()).to_owned(),
+            Column::Amount => amount(detail.amount),
+            Column::SignedAmount => amount(detail.signed_amount()?),
+            Column::FundsType => funds(detail.funds.as_ref()).to_owned(),
+            Column::BankRefNum => detail.bank_ref_num.as_ref().map_or_else(String::new, |r| {
+                r.0.clone()
+            }),
+            Column::CustomerRefNum => {
+                detail.customer_ref_num.as_ref().map_or_else(
+                    String::new,
+                    |r| r.0.clone(),
+                )
+            }
+            Column::Text => {
+                detail.text.as_ref().map_or_else(String::new, |text| {
+                    text.join(&options.text_separator)
+                })
+            }
+            _ => String::new(),
+        })
+    }
+
+    fn info(&self, column: Column, info: &data::AccountInfo) -> io::Result<String> {
+        use data::AccountInfo as AI;
+
+        if let Some(cell) = self.inherited(column) {
+            return Ok(cell);
+        }
+        Ok(match (column, info) {
+            (Column::Level, &AI::Status { .. }) => "status".to_owned(),
+            (Column::Level, &AI::Summary { .. }) => "summary".to_owned(),
+            (Column::TypeCode, &AI::Status { code, .. }) => format!("{:03}", u16::from(code)),
+            (Column::TypeCode, &AI::Summary { code, .. }) => format!("{:03}", u16::from(code)),
+            (Column::Description, &AI::Status { code, .. }) => {
+                description(data::TypeCode::Status(code))
+            }
+            (Column::Description, &AI::Summary { code, .. }) => {
+                description(data::TypeCode::Summary(code))
+            }
+            (Column::Direction, &AI::Summary { code, .. }) => {
+                direction(code.direction()).to_owned()
+            }
+            (Column::Amount, _) => amount(info.amount()),
+            (Column::SignedAmount, _) => amount(info.signed_amount()?),
+            (Column::ItemCount, &AI::Summary { item_count, .. }) => {
+                item_count.map_or_else(String::new, |n| n.to_string())
+            }
+            (Column::FundsType, AI::Summary { funds: f, .. }) => funds(f.as_ref()).to_owned(),
+            _ => String::new(),
+        })
+    }
+}
+
+fn description(code: data::TypeCode) -> String {
+    code.info().map_or_else(String::new, |info| info.description.to_owned())
+}
+
+fn direction(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Credit => "credit",
+        Direction::Debit => "debit",
+        Direction::NonMonetary => "non_monetary",
+    }
+}
+
+fn amount(amount: Option<data::Amount>) -> String {
+    amount.map_or_else(String::new, |a| a.to_decimal())
+}
+
+/// The funds type's name, as in the export schema.
+fn funds(funds: Option<&data::FundsType>) -> &'static str {
+    use data::FundsType as FT;
+    match funds {
+        None => "",
+        Some(&FT::Unknown) => "unknown",
+        Some(&FT::ImmediateAvail) => "immediate",
+        Some(&FT::OneDayAvail) => "one_day",
+        Some(&FT::TwoOrMoreDaysAvail) => "two_or_more_days",
+        Some(&FT::DistributedAvailS { .. }) => "distributed",
+        Some(&FT::ValueDated(_)) => "value_dated",
+        Some(&FT::DistributedAvailD(_)) => "distributed_by_days",
+    }
+}
+
+fn write_header<W: io::Write>(w: &mut W, columns: &[Column]) -> io::Result<()> {
+    write_row(w, columns.iter().map(|c| c.name().to_owned()))
+}
+
+fn write_row<W, I>(w: &mut W, cells: I) -> io::Result<()>
+where
+    W: io::Write,
+    I: Iterator<Item = String>,
+{
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        if cell.contains(&[',', '"', '\n', '\r'][..]) {
+            write!(w, "\"{}\"", cell.replace('"', "\"\""))?;
+        } else {
+            w.write_all(cell.as_bytes())?;
+        }
+    }
+    w.write_all(b"\r\n")
+}
@@ -0,0 +1,137 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use penny::{Currency, Money};
+
+/// An amount of money as written in a file, in the minor units of its currency (cents for USD,
+/// whole yen for JPY, fils for BHD).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Amount {
+    pub value: i64,
+    pub currency: Currency,
+}
+
+impl Amount {
+    pub fn new(value: i64, currency: Currency) -> Self {
+        Amount { value, currency }
+    }
+
+    /// The number of digits after the decimal point.
+    pub fn decimals(&self) -> u32 {
+        currency_decimals(self.currency)
+    }
+
+    /// The amount in whole units of its currency, with any fraction dropped.
+    pub fn whole(&self) -> i64 {
+        self.value / 10i64.pow(self.decimals())
+    }
+
+    /// The amount as a plain decimal number, as in "43500.00" or "-0.05".
+    pub fn to_decimal(&self) -> String {
+        let decimals = self.decimals();
+        let scale = 10u64.pow(decimals);
+        let abs = self.value.wrapping_abs() as u64;
+        let sign = if self.value < 0 { "-" } else { "" };
+        if decimals == 0 {
+            format!("{}{}", sign, abs)
+        } else {
+            format!("{}{}.{:03$}", sign, abs / scale, abs % scale, decimals as usize)
+        }
+    }
+
+    /// Parses a plain decimal number like `to_decimal` writes, with at most as many digits after
+    /// the decimal point as `currency` has and none needed.
+    pub fn from_decimal(s: &str, currency: Currency) -> Option<Amount> {
+        let decimals = currency_decimals(currency) as usize;
+        let negative = s.starts_with('-');
+        let s = if negative { &s[1..] } else { s };
+        let (whole, fraction) = match s.find('.') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let digits = |s: &str| s.bytes().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !digits(whole) || !digits(fraction) || fraction.len() > decimals {
+            return None;
+        }
+        let value = format!("{}{:0<2$}", whole, fraction, decimals).parse::<i64>().ok()?;
+        Some(Amount::new(if negative { -value } else { value }, currency))
+    }
+
+    /// The amount with its sign flipped. `i64::MIN` has no positive counterpart, so it's an
+    /// error.
+    pub fn negate(self) -> Result<Amount, AmountOverflow> {
+        match self.value.checked_neg() {
+            Some(value) => Ok(Amount { value, ..self }),
+            None => Err(AmountOverflow(self)),
+        }
+    }
+
+    /// The amount without its sign, with an error for `i64::MIN` like `negate`.
+    pub fn abs(self) -> Result<Amount, AmountOverflow> {
+        match self.value.checked_abs() {
+            Some(value) => Ok(Amount { value, ..self }),
+            None => Err(AmountOverflow(self)),
+        }
+    }
+
+    pub fn money(&self) -> Money {
+        Money::new(self.value, self.currency)
+    }
+}
+
+/// An amount whose sign couldn't be flipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountOverflow(pub Amount);
+impl fmt::Display for AmountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "amount {} can't be negated", self.0)
+    }
+}
+impl error::Error for AmountOverflow {}
+impl From<AmountOverflow> for io::Error {
+    fn from(e: AmountOverflow) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+impl From<Amount> for Money {
+    fn from(amount: Amount) -> Money {
+        amount.money()
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimals = self.decimals();
+        let scale = 10u64.pow(decimals);
+        // Wrapping keeps i64::MIN's magnitude right once it's unsigned.
+        let abs = self.value.wrapping_abs() as u64;
+        if self.value < 0 {
+            f.write_str("-")?;
+        }
+        let whole = (abs / scale).to_string();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", digit)?;
+        }
+        if decimals > 0 {
+            write!(f, ".{:01$}", abs % scale, decimals as usize)?;
+        }
+        write!(f, " {}", self.currency)
+    }
+}
+
+/// The number of digits after the decimal point in amounts of `currency`, per ISO 4217.
+pub fn currency_decimals(currency: Currency) -> u32 {
+    match &*currency.to_string() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" |
+        "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        _ => 2,
+    }
+}
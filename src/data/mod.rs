@@ -1,5 +1,6 @@
+use std::error;
 use std::fmt::{self, Write};
-use std::io::Read;
+use std::io::{self, Read};
 
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use nom;
@@ -7,9 +8,17 @@ use penny::{Currency, Money};
 
 use ast;
 use ast::parse::Parsed;
+use dialect::Dialects;
 use parse;
+use read;
+use write;
 
+mod amount;
+mod type_code_list;
+mod type_code_registry;
 mod type_codes;
+pub use self::amount::*;
+pub use self::type_code_registry::*;
 pub use self::type_codes::*;
 
 // From std::fmt::builders (MIT/Apache-2.0)
@@ -117,6 +126,11 @@ impl From<NaiveDate> for BaiDateOrTime {
         BaiDateOrTime::Date(date)
     }
 }
+impl From<NaiveDateTime> for BaiDateOrTime {
+    fn from(datetime: NaiveDateTime) -> Self {
+        BaiDateOrTime::DateTime(datetime)
+    }
+}
 impl From<BaiDateTime> for BaiDateOrTime {
     fn from(datetime: BaiDateTime) -> Self {
         match datetime {
@@ -150,18 +164,26 @@ pub struct File {
     pub receiver: Party,
     pub creation: BaiDateTime,
     pub ident: FileIdent,
+    pub version: Version,
     pub groups: Vec<Group>,
 }
 
+impl File {
+    pub fn control_total(&self) -> i64 {
+        self.groups.iter().map(Group::control_total).sum()
+    }
+}
+
 impl fmt::Display for File {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "File: {sender} to {receiver} at {creation} ({ident}) {{\n",
+            "File: {sender} to {receiver} at {creation} ({ident}, {version}) {{\n",
             sender = self.sender,
             receiver = self.receiver,
             creation = self.creation,
-            ident = self.ident
+            ident = self.ident,
+            version = self.version
         ).and_then(|()| {
             let mut f = PadAdapter::new(f);
             for group in &self.groups {
@@ -172,6 +194,52 @@ impl fmt::Display for File {
     }
 }
 
+/// The revision of the format a file is written in, from the version number in its file header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Version {
+    /// BAI Cash Management Balance Reporting Specifications Version 2.
+    Bai2,
+    /// ANSI X9.121 Balance and Transaction Reporting Standard Version 3.
+    Btrs3,
+}
+
+/// The name `Version` had before `dialect::Dialect` took it for bank deviations.
+#[deprecated(note = "renamed to `Version`")]
+pub type Dialect = Version;
+
+impl Version {
+    pub fn from_version_number(version_number: u8) -> Option<Version> {
+        match version_number {
+            2 => Some(Version::Bai2),
+            3 => Some(Version::Btrs3),
+            _ => None,
+        }
+    }
+
+    pub fn version_number(self) -> u8 {
+        match self {
+            Version::Bai2 => 2,
+            Version::Btrs3 => 3,
+        }
+    }
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::Bai2
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Version::Bai2 => write!(f, "BAI2"),
+            Version::Btrs3 => write!(f, "BTRS Version 3"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct Party(pub String);
@@ -207,6 +275,10 @@ impl Group {
     pub fn currency_def(&self) -> Currency {
         self.currency.unwrap_or(Currency::USD)
     }
+
+    pub fn control_total(&self) -> i64 {
+        self.accounts.iter().map(Account::control_total).sum()
+    }
 }
 
 impl fmt::Display for Group {
@@ -285,6 +357,16 @@ impl fmt::Display for AsOfDateModifier {
         }
     }
 }
+impl AsOfDateModifier {
+    /// Whether the group is an intraday report, rather than a final statement.
+    pub fn is_interim(self) -> bool {
+        use self::AsOfDateModifier as AODM;
+        match self {
+            AODM::InterimPrevious | AODM::InterimSame => true,
+            AODM::FinalPrevious | AODM::FinalSame => false,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -299,6 +381,14 @@ impl Account {
     pub fn currency_def(&self, group_cur: Currency) -> Currency {
         self.currency.unwrap_or(group_cur)
     }
+
+    pub fn control_total(&self) -> i64 {
+        self.infos.iter().map(AccountInfo::control_total).sum::<i64>() +
+            self.transaction_details
+                .iter()
+                .filter_map(|t| t.amount.map(|a| a.value))
+                .sum::<i64>()
+    }
 }
 
 impl fmt::Display for Account {
@@ -335,31 +425,48 @@ impl fmt::Display for Account {
 pub enum AccountInfo {
     Summary {
         code: SummaryCode,
-        amount: Option<u64>,
+        amount: Option<Amount>,
         item_count: Option<u32>,
         funds: Option<FundsType>,
     },
     Status {
         code: StatusCode,
-        amount: Option<i64>,
+        amount: Option<Amount>,
     },
 }
 
 impl AccountInfo {
-    pub fn amount_money(&self, account_cur: Currency) -> Option<Money> {
+    pub fn control_total(&self) -> i64 {
         use self::AccountInfo as AI;
         match *self {
-            AI::Summary {
-                amount: Some(amount),
-                ..
-            } => Some(Money::new(amount as i64, account_cur)),
-            AI::Status {
-                amount: Some(amount),
-                ..
-            } => Some(Money::new(amount, account_cur)),
-            _ => None,
+            AI::Summary { amount, .. } |
+            AI::Status { amount, .. } => amount.map_or(0, |a| a.value),
+        }
+    }
+
+    pub fn amount(&self) -> Option<Amount> {
+        match *self {
+            AccountInfo::Summary { amount, .. } |
+            AccountInfo::Status { amount, .. } => amount,
+        }
+    }
+
+    /// The amount, negative for debit summaries. Status amounts are already signed, and
+    /// summaries that are neither credits nor debits have none.
+    pub fn signed_amount(&self) -> Result<Option<Amount>, AmountOverflow> {
+        match *self {
+            AccountInfo::Summary { code, amount: Some(amount), .. } => {
+                code.direction().sign(amount)
+            }
+            AccountInfo::Summary { amount: None, .. } => Ok(None),
+            AccountInfo::Status { amount, .. } => Ok(amount),
         }
     }
+
+    #[deprecated(note = "amounts carry their currency; use `Amount::money`")]
+    pub fn amount_money(&self, _account_cur: Currency) -> Option<Money> {
+        self.amount().map(|a| a.money())
+    }
 }
 
 impl fmt::Display for AccountInfo {
@@ -415,9 +522,9 @@ pub enum FundsType {
     DistributedAvailS {
         // S
         // These are optional because the example given treats them as such.
-        immediate: Option<i64>,
-        one_day: Option<i64>,
-        more_than_one_day: Option<i64>,
+        immediate: Option<Amount>,
+        one_day: Option<Amount>,
+        more_than_one_day: Option<Amount>,
     },
     ValueDated(BaiDateOrTime), // V
     DistributedAvailD(Vec<DistributedAvailDistribution>), // D
@@ -470,12 +577,13 @@ impl fmt::Display for FundsType {
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct DistributedAvailDistribution {
     pub days: u32,
-    pub amount: i64,
+    pub amount: Amount,
 }
 
 impl DistributedAvailDistribution {
-    pub fn amount_money(&self, funds_cur: Currency) -> Money {
-        Money::new(self.amount, funds_cur)
+    #[deprecated(note = "amounts carry their currency; use `Amount::money`")]
+    pub fn amount_money(&self, _funds_cur: Currency) -> Money {
+        self.amount.money()
     }
 }
 
@@ -483,7 +591,7 @@ impl DistributedAvailDistribution {
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct TransactionDetail {
     pub code: DetailCode,
-    pub amount: Option<i64>,
+    pub amount: Option<Amount>,
     pub funds: Option<FundsType>,
     pub bank_ref_num: Option<ReferenceNum>,
     pub customer_ref_num: Option<ReferenceNum>,
@@ -491,10 +599,21 @@ pub struct TransactionDetail {
 }
 
 impl TransactionDetail {
-    pub fn amount_money(&self, account_cur: Currency) -> Option<Money> {
-        self.amount.map(|amount| Money::new(amount, account_cur))
+    /// The amount, negative for debits. Details that are neither credits nor debits, like 890 or
+    /// unknown codes, have none.
+    pub fn signed_amount(&self) -> Result<Option<Amount>, AmountOverflow> {
+        match self.amount {
+            Some(amount) => self.code.direction().sign(amount),
+            None => Ok(None),
+        }
+    }
+
+    #[deprecated(note = "amounts carry their currency; use `Amount::money`")]
+    pub fn amount_money(&self, _account_cur: Currency) -> Option<Money> {
+        self.amount.map(|a| a.money())
     }
 }
+
 impl fmt::Display for TransactionDetail {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Transaction: {}", self.code)?;
@@ -530,35 +649,296 @@ impl fmt::Display for ReferenceNum {
     }
 }
 
+/// Errors from processing a file, each with the position in the input where it happened.
 #[derive(Debug, Clone)]
-pub enum FileProcessError<'a> {
-    Parse(nom::ErrorKind),
-    FieldParse(ast::parse::ParseError<ast::Record<'a>>),
-    UnfinishedConversion,
-    Conversion(ast::convert::ConvertError),
+pub enum FileProcessError {
+    Parse(parse::Position, nom::ErrorKind),
+    FieldParse(parse::Position, ast::parse::ParseError<ast::RecordField>),
+    UnfinishedConversion(parse::Position),
+    Conversion(parse::Position, ast::convert::ConvertError),
+    Layout(parse::LayoutError),
+    MissingRecordSep(parse::Position),
+}
+impl FileProcessError {
+    pub fn position(&self) -> parse::Position {
+        match *self {
+            FileProcessError::Parse(p, _) |
+            FileProcessError::FieldParse(p, _) |
+            FileProcessError::UnfinishedConversion(p) |
+            FileProcessError::Conversion(p, _) |
+            FileProcessError::MissingRecordSep(p) => p,
+            FileProcessError::Layout(ref e) => e.position(),
+        }
+    }
+}
+impl fmt::Display for FileProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileProcessError::Parse(p, ref kind) => {
+                write!(f, "{}: {}", p, parse::describe_error(kind))
+            }
+            FileProcessError::FieldParse(p, ref e) => write!(f, "{}: {}", p, e),
+            FileProcessError::UnfinishedConversion(p) => {
+                write!(f, "{}: file ended before its trailer", p)
+            }
+            FileProcessError::Conversion(p, ref e) => write!(f, "{}: {}", p, e),
+            FileProcessError::Layout(ref e) => write!(f, "{}: {}", e.position(), e),
+            FileProcessError::MissingRecordSep(p) => write!(f, "{}: missing record separator", p),
+        }
+    }
+}
+impl error::Error for FileProcessError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            FileProcessError::FieldParse(_, ref e) => Some(e),
+            FileProcessError::Conversion(_, ref e) => Some(e),
+            FileProcessError::Layout(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Something that was tolerated while processing a file leniently, as the error it would've
+/// been otherwise.
+#[derive(Debug, Clone)]
+pub struct Warning(pub FileProcessError);
+impl Warning {
+    pub fn position(&self) -> parse::Position {
+        self.0.position()
+    }
+}
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Collects `err` when recovering from errors, or fails with it otherwise.
+fn recover(
+    errors: &mut Option<&mut Vec<FileProcessError>>,
+    err: FileProcessError,
+) -> Result<(), FileProcessError> {
+    match *errors {
+        Some(ref mut errors) => {
+            errors.push(err);
+            Ok(())
+        }
+        None => Err(err),
+    }
+}
+
+/// Skips the record at `position` that couldn't be parsed when recovering from errors, returning
+/// the file if that ended it.
+fn skip_unparsed<V: ast::convert::Visitor>(
+    converter: &mut ast::convert::Converter<V>,
+    errors: &mut Option<&mut Vec<FileProcessError>>,
+    err: FileProcessError,
+    position: parse::Position,
+) -> Result<Option<File>, FileProcessError> {
+    use ast::convert::ConverterOutput;
+
+    if errors.is_none() {
+        return Err(err);
+    }
+    let file = match converter.skip_unparsed(position.record_code) {
+        ConverterOutput::Active => None,
+        ConverterOutput::Ok(file) => Some(file),
+        _ => return Err(err),
+    };
+    recover(errors, err)?;
+    Ok(file)
+}
+
+/// A file processed with error recovery, along with everything that went wrong in it.
+#[derive(Debug, Clone)]
+pub struct Recovered {
+    /// What could be converted, or `None` if the error at the end of `errors` lost the whole
+    /// file.
+    pub file: Option<File>,
+    pub errors: Vec<FileProcessError>,
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Strictness {
+    /// Every spec violation is an error.
+    Strict,
+    /// Spec violations that still leave a usable file are returned as warnings.
+    Lenient,
+}
+impl Default for Strictness {
+    fn default() -> Self {
+        Strictness::Strict
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct ParseOptions {
+    pub strictness: Strictness,
+    /// Whether to check that every physical record fits within the physical record length
+    /// declared in the file header.
+    pub check_layout: bool,
 }
 
 impl File {
-    pub fn process<'a>(file: &'a [u8]) -> Result<File, FileProcessError<'a>> {
-        parse::file(file)
-            .to_result()
-            .map_err(FileProcessError::Parse)
-            .and_then(|raw_records| {
-                let mut parsed_records = raw_records.iter().map(|r| {
-                    ast::Record::parse(r).map_err(FileProcessError::FieldParse)
-                });
-                ast::convert::Converter::fold_results(&mut parsed_records, |e| match e {
-                    Some(e) => FileProcessError::Conversion(e),
-                    None => FileProcessError::UnfinishedConversion,
-                })
-            })
-    }
-
-    pub fn from_source<T: Read>(source: &mut T) -> Result<File, String> {
-        let mut file = Vec::new();
-        source
-            .read_to_end(&mut file)
-            .map_err(|e| format!("{:?}", e))?;
-        File::process(&file).map_err(|e| format!("{:?}", e))
+    pub fn process(file: &[u8]) -> Result<File, FileProcessError> {
+        File::process_with(file, &ParseOptions::default()).map(|(file, _)| file)
+    }
+
+    /// Like `process`, but also checks that every physical record fits within the physical
+    /// record length declared in the file header.
+    pub fn process_checked(file: &[u8]) -> Result<File, FileProcessError> {
+        let options = ParseOptions {
+            check_layout: true,
+            ..ParseOptions::default()
+        };
+        File::process_with(file, &options).map(|(file, _)| file)
+    }
+
+    /// Processes a file with the given options, returning the warnings that were tolerated
+    /// along with it. Warnings are only returned when processing leniently.
+    pub fn process_with(
+        file: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(File, Vec<Warning>), FileProcessError> {
+        File::process_with_dialects(file, options, &Dialects::default())
+    }
+
+    /// Like `process_with`, but expecting the deviations from the spec of the dialect registered
+    /// for the file's sender.
+    pub fn process_with_dialects(
+        file: &[u8],
+        options: &ParseOptions,
+        dialects: &Dialects,
+    ) -> Result<(File, Vec<Warning>), FileProcessError> {
+        let mut warnings = Vec::new();
+        File::process_records(file, options, dialects, &mut warnings, None)
+            .map(|file| (file, warnings))
+    }
+
+    /// Processes a file, carrying on past errors instead of stopping at the first one. Accounts
+    /// and groups with errors in them are skipped to their trailers and left out of the file.
+    /// Pass `&Dialects::default()` for files as the spec describes them.
+    pub fn process_recovering(
+        file: &[u8],
+        options: &ParseOptions,
+        dialects: &Dialects,
+    ) -> Recovered {
+        let mut warnings = Vec::new();
+        let mut errors = Vec::new();
+        let processed =
+            File::process_records(file, options, dialects, &mut warnings, Some(&mut errors));
+        let file = match processed {
+            Ok(file) => Some(file),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+        Recovered {
+            file,
+            errors,
+            warnings,
+        }
+    }
+
+    fn process_records(
+        file: &[u8],
+        options: &ParseOptions,
+        dialects: &Dialects,
+        warnings: &mut Vec<Warning>,
+        mut errors: Option<&mut Vec<FileProcessError>>,
+    ) -> Result<File, FileProcessError> {
+        use ast::convert::{Converter, ConverterOutput};
+
+        let dialect = dialects.for_file(file);
+        let mut records = if errors.is_some() {
+            parse::Records::recovering(file)
+        } else {
+            parse::Records::new(file)
+        };
+        // Missing record separators are reported below, as errors or warnings.
+        records.set_lenient(true);
+        records.set_dialect(&*dialect);
+        let mut converter = Converter::default();
+        converter.set_strictness(options.strictness);
+        converter.set_dialect(dialect.clone());
+        converter.set_recovery(errors.is_some());
+        let mut layout = parse::Layout::default();
+        while let Some(record) = records.next() {
+            let (position, raw, continuations) = match record {
+                Ok(record) => record,
+                Err((position, kind)) => {
+                    let err = FileProcessError::Parse(position, kind);
+                    match skip_unparsed(&mut converter, &mut errors, err, position)? {
+                        Some(file) => return Ok(file),
+                        None => continue,
+                    }
+                }
+            };
+            let consumed = &file[position.offset..records.position().offset];
+            let missing = if dialect.missing_record_sep() {
+                None
+            } else {
+                parse::missing_record_sep(consumed, &raw)
+            };
+            if let Some(end) = missing {
+                let err = FileProcessError::MissingRecordSep(position.advance(&consumed[..end]));
+                match options.strictness {
+                    Strictness::Strict => recover(&mut errors, err)?,
+                    Strictness::Lenient => warnings.push(Warning(err)),
+                }
+            }
+            let parsed = match ast::Record::parse_with(&raw, &*dialect) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    let field_position = ast::Record::locate(&raw, &e).map_or(position, |field| {
+                        position.locate(&file[position.offset..], field)
+                    });
+                    let err = FileProcessError::FieldParse(field_position, e.at(field_position));
+                    match skip_unparsed(&mut converter, &mut errors, err, position)? {
+                        Some(file) => return Ok(file),
+                        None => continue,
+                    }
+                }
+            };
+            if options.check_layout {
+                if let ast::ParsedRecord::FileHeader(ref header) = parsed {
+                    layout = parse::Layout::from_header(header);
+                }
+                if let Err(e) = layout.check_lines(position, consumed) {
+                    recover(&mut errors, FileProcessError::Layout(e))?;
+                }
+            }
+            let output = converter.process_continued(parsed, continuations);
+            warnings.extend(converter.take_warnings().into_iter().map(|e| {
+                Warning(FileProcessError::Conversion(position, e.at(position)))
+            }));
+            if let Some(ref mut errors) = errors {
+                errors.extend(converter.take_errors().into_iter().map(|e| {
+                    FileProcessError::Conversion(position, e.at(position))
+                }));
+            }
+            match output {
+                ConverterOutput::Active => {}
+                ConverterOutput::Ok(file) => return Ok(file),
+                ConverterOutput::Err(e) => {
+                    return Err(FileProcessError::Conversion(position, e.at(position)))
+                }
+                ConverterOutput::Done => unreachable!(),
+            }
+        }
+        Err(FileProcessError::UnfinishedConversion(records.position()))
+    }
+
+    pub fn from_source<T: Read>(source: &mut T) -> Result<File, read::ReadError> {
+        read::RecordReader::new(io::BufReader::new(source)).read_file()
+    }
+
+    /// Writes the file as BAI2, computing trailer control totals and record counts.
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write::Writer::new(w).write_file(self)
     }
 }
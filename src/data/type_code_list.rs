@@ -0,0 +1,552 @@
+//! The type codes listed in Appendix A of the spec, along with the ones X9 added for BTRS.
+
+use super::Version;
+use super::type_codes::{Direction, Level, TypeCodeInfo};
+
+macro_rules! type_code_version {
+    () => { Version::Bai2 };
+    ($version:ident) => { Version::$version };
+}
+
+macro_rules! type_codes {
+    ($($section:expr => {
+        $($code:expr, $level:ident, $direction:ident, $description:expr $(, $version:ident)*;)+
+    })+) => {
+        /// Every code, in order.
+        pub static TYPE_CODES: &[TypeCodeInfo] = &[$($(
+            TypeCodeInfo {
+                code: $code,
+                description: $description,
+                level: Level::$level,
+                direction: Direction::$direction,
+                loan: $code >= 700 && $code <= 799,
+                section: $section,
+                version: type_code_version!($($version)*),
+            },
+        )+)+];
+    };
+}
+
+type_codes! {
+    "Account Status" => {
+        10, Status, NonMonetary, "Opening Ledger";
+        11, Status, NonMonetary, "Average Opening Ledger MTD";
+        12, Status, NonMonetary, "Average Opening Ledger YTD";
+        15, Status, NonMonetary, "Closing Ledger";
+        20, Status, NonMonetary, "Average Closing Ledger MTD";
+        21, Status, NonMonetary, "Average Closing Ledger - Previous Month";
+        22, Status, NonMonetary, "Aggregate Balance Adjustments";
+        24, Status, NonMonetary, "Average Closing Ledger YTD - Previous Month";
+        25, Status, NonMonetary, "Average Closing Ledger YTD";
+        30, Status, NonMonetary, "Current Ledger";
+        37, Status, NonMonetary, "ACH Net Position";
+        39, Status, NonMonetary, "Opening Available + Total Same-Day ACH DTC Deposit";
+        40, Status, NonMonetary, "Opening Available";
+        41, Status, NonMonetary, "Average Opening Available MTD";
+        42, Status, NonMonetary, "Average Opening Available YTD";
+        43, Status, NonMonetary, "Average Available - Previous Month";
+        44, Status, NonMonetary, "Disbursing Opening Available Balance";
+        45, Status, NonMonetary, "Closing Available";
+        50, Status, NonMonetary, "Average Closing Available MTD";
+        51, Status, NonMonetary, "Average Closing Available - Last Month";
+        54, Status, NonMonetary, "Average Closing Available YTD - Last Month";
+        55, Status, NonMonetary, "Average Closing Available YTD";
+        56, Status, NonMonetary, "Loan Balance";
+        57, Status, NonMonetary, "Total Investment Position";
+        59, Status, NonMonetary, "Current Available (CRS Supressed)";
+        60, Status, NonMonetary, "Current Available";
+        61, Status, NonMonetary, "Average Current Available MTD";
+        62, Status, NonMonetary, "Average Current Available YTD";
+        63, Status, NonMonetary, "Total Float";
+        65, Status, NonMonetary, "Target Balance";
+        66, Status, NonMonetary, "Adjusted Balance";
+        67, Status, NonMonetary, "Adjusted Balance MTD";
+        68, Status, NonMonetary, "Adjusted Balance YTD";
+        70, Status, NonMonetary, "0-Day Float";
+        72, Status, NonMonetary, "1-Day Float";
+        73, Status, NonMonetary, "Float Adjustment";
+        74, Status, NonMonetary, "2 or More Days Float";
+        75, Status, NonMonetary, "3 or More Days Float";
+        76, Status, NonMonetary, "Adjustment to Balances";
+        77, Status, NonMonetary, "Average Adjustment to Balances MTD";
+        78, Status, NonMonetary, "Average Adjustment to Balances YTD";
+        79, Status, NonMonetary, "4-Day Float";
+        80, Status, NonMonetary, "5-Day Float";
+        81, Status, NonMonetary, "6-Day Float";
+        82, Status, NonMonetary, "Average 1-Day Float MTD";
+        83, Status, NonMonetary, "Average 1-Day Float YTD";
+        84, Status, NonMonetary, "Average 2-Day Float MTD";
+        85, Status, NonMonetary, "Average 2-Day Float YTD";
+        86, Status, NonMonetary, "Transfer Calculation";
+    }
+    "Summary and Detail Credits" => {
+        100, Summary, Credit, "Total Credits";
+        101, Summary, Credit, "Total Credit Amount MTD";
+        105, Summary, Credit, "Credits Not Detailed";
+        106, Summary, Credit, "Deposits Subject to Float";
+        107, Summary, Credit, "Total Adjustment Credits YTD";
+        108, Detail, Credit, "Credit (Any Type)";
+        109, Summary, Credit, "Current Day Total Lockbox Deposits";
+    }
+    "Lockbox" => {
+        110, Summary, Credit, "Total Lockbox Deposits";
+        115, Detail, Credit, "Lockbox Deposit";
+        116, Detail, Credit, "Item in Lockbox Deposit";
+        118, Detail, Credit, "Lockbox Adjustment Credit";
+        120, Summary, Credit, "EDI Transaction Credit";
+        121, Detail, Credit, "EDI Transaction Credit";
+        122, Detail, Credit, "EDIBANX Credit Received";
+        123, Detail, Credit, "EDIBANX Credit Return";
+    }
+    "Concentration" => {
+        130, Summary, Credit, "Total Concentration Credits";
+        131, Summary, Credit, "Total DTC Credits";
+        135, Detail, Credit, "DTC Concentration Credit";
+        136, Detail, Credit, "Item in DTC Deposit";
+        140, Summary, Credit, "Total ACH Credits";
+        142, Detail, Credit, "ACH Credit Received";
+        143, Detail, Credit, "Item in ACH Deposit";
+        145, Detail, Credit, "ACH Concentration Credit";
+        146, Summary, Credit, "Total Bank Card Deposits";
+        147, Detail, Credit, "Individual Bank Card Deposit";
+    }
+    "Preauthorized and ACH" => {
+        150, Summary, Credit, "Total Preauthorized Payment Credits";
+        155, Detail, Credit, "Preauthorized Draft Credit";
+        156, Detail, Credit, "Item in PAC Deposit";
+        158, Detail, Credit, "Real Time Payment Credit", Btrs3;
+        160, Summary, Credit, "Total ACH Disbursing Funding Credits";
+        162, Summary, Credit, "Corporate Trade Payment Settlement";
+        163, Summary, Credit, "Corporate Trade Payment Credits";
+        164, Detail, Credit, "Corporate Trade Payment Credit";
+        165, Detail, Credit, "Preauthorized ACH Credit";
+        166, Detail, Credit, "ACH Settlement";
+        167, Summary, Credit, "ACH Settlement Credits";
+        168, Detail, Credit, "ACH Return Item or Adjustment Settlement";
+        169, Detail, Credit, "Miscellaneous ACH Credit";
+    }
+    "Other Deposits" => {
+        170, Summary, Credit, "Total Other Check Deposits";
+        171, Detail, Credit, "Individual Loan Deposit";
+        172, Detail, Credit, "Deposit Correction";
+        173, Detail, Credit, "Bank-Prepared Deposit";
+        174, Detail, Credit, "Other Deposit";
+        175, Detail, Credit, "Check Deposit Package";
+        176, Detail, Credit, "Re-presented Check Deposit";
+        178, Summary, Credit, "List Post Credits";
+        180, Summary, Credit, "Total Loan Proceeds";
+        182, Summary, Credit, "Total Bank-Prepared Deposits";
+        184, Detail, Credit, "Draft Deposit";
+        185, Summary, Credit, "Total Miscellaneous Deposits";
+        186, Summary, Credit, "Total Cash Letter Credits";
+        187, Detail, Credit, "Cash Letter Credit";
+        188, Summary, Credit, "Total Cash Letter Adjustments";
+        189, Detail, Credit, "Cash Letter Adjustment";
+    }
+    "Money Transfer" => {
+        190, Summary, Credit, "Total Incoming Money Transfers";
+        191, Detail, Credit, "Individual Incoming Internal Money Transfer";
+        195, Detail, Credit, "Incoming Money Transfer";
+        196, Detail, Credit, "Money Transfer Adjustment";
+        198, Detail, Credit, "Compensation";
+        200, Summary, Credit, "Total Automatic Transfer Credits";
+        201, Detail, Credit, "Individual Automatic Transfer Credit";
+        202, Detail, Credit, "Bond Operations Credit";
+        205, Summary, Credit, "Total Book Transfer Credits";
+        206, Detail, Credit, "Book Transfer Credit";
+        207, Summary, Credit, "Total International Money Transfer Credits";
+        208, Detail, Credit, "Individual International Money Transfer Credit";
+        210, Summary, Credit, "Total International Credits";
+        212, Detail, Credit, "Foreign Letter of Credit";
+        213, Detail, Credit, "Letter of Credit";
+        214, Detail, Credit, "Foreign Exchange of Credit";
+        215, Summary, Credit, "Total Letters of Credit";
+        216, Detail, Credit, "Foreign Remittance Credit";
+        218, Detail, Credit, "Foreign Collection Credit";
+        221, Detail, Credit, "Foreign Check Purchase";
+        222, Detail, Credit, "Foreign Checks Deposited";
+        224, Detail, Credit, "Commission";
+        226, Detail, Credit, "International Money Market Trading";
+        227, Detail, Credit, "Standing Order";
+        229, Detail, Credit, "Miscellaneous International Credit";
+    }
+    "Security" => {
+        230, Summary, Credit, "Total Security Credits";
+        231, Summary, Credit, "Total Collection Credits";
+        232, Detail, Credit, "Sale of Debt Security";
+        233, Detail, Credit, "Securities Sold";
+        234, Detail, Credit, "Sale of Equity Security";
+        235, Detail, Credit, "Matured Reverse Repurchase Order";
+        236, Detail, Credit, "Maturity of Debt Security";
+        237, Detail, Credit, "Individual Collection Credit";
+        238, Detail, Credit, "Collection of Dividends";
+        239, Summary, Credit, "Total Bankers' Acceptance Credits";
+        240, Detail, Credit, "Coupon Collections - Banks";
+        241, Detail, Credit, "Bankers' Acceptances";
+        242, Detail, Credit, "Collection of Interest Income";
+        243, Detail, Credit, "Matured Fed Funds Purchased";
+        244, Detail, Credit, "Interest/Matured Principal Payment";
+        245, Summary, Credit, "Monthly Dividends";
+        246, Detail, Credit, "Commercial Paper";
+        247, Detail, Credit, "Capital Change";
+        248, Detail, Credit, "Savings Bonds Sales Adjustment";
+        249, Detail, Credit, "Miscellaneous Security Credit";
+        250, Summary, Credit, "Total Checks Posted and Returned";
+        251, Summary, Credit, "Total Debit Reversals";
+        252, Detail, Credit, "Debit Reversal";
+        254, Detail, Credit, "Posting Error Correction Credit";
+        255, Detail, Credit, "Check Posted and Returned";
+        256, Summary, Credit, "Total ACH Return Items";
+        257, Detail, Credit, "Individual ACH Return Item";
+        258, Detail, Credit, "ACH Reversal Credit";
+        260, Summary, Credit, "Total Rejected Credits";
+        261, Detail, Credit, "Individual Rejected Credit";
+        263, Detail, Credit, "Overdraft";
+        266, Detail, Credit, "Return Item";
+        268, Detail, Credit, "Return Item Adjustment";
+    }
+    "ZBA and Disbursing" => {
+        270, Summary, Credit, "Total ZBA Credits";
+        271, Summary, Credit, "Net Zero-Balance Amount";
+        274, Detail, Credit, "Cumulative ZBA or Disbursement Credits";
+        275, Detail, Credit, "ZBA Credit";
+        276, Detail, Credit, "ZBA Float Adjustment";
+        277, Detail, Credit, "ZBA Credit Transfer";
+        278, Detail, Credit, "ZBA Credit Adjustment";
+        280, Summary, Credit, "Total Controlled Disbursing Credits";
+        281, Detail, Credit, "Individual Controlled Disbursing Credit";
+        285, Summary, Credit, "Total DTC Disbursing Credits";
+        286, Detail, Credit, "Individual DTC Disbursing Credit";
+    }
+    "Other (Expansion)" => {
+        294, Summary, Credit, "Total ATM Credits";
+        295, Detail, Credit, "ATM Credit";
+        301, Detail, Credit, "Commercial Deposit";
+        302, Summary, Credit, "Correspondent Bank Deposit";
+        303, Summary, Credit, "Total Wire Transfers In - FF";
+        304, Summary, Credit, "Total Wire Transfers In - CHF";
+        305, Summary, Credit, "Total Fed Funds Sold";
+        306, Detail, Credit, "Fed Funds Sold";
+        307, Summary, Credit, "Total Trust Credits";
+        308, Detail, Credit, "Trust Credit";
+        309, Summary, Credit, "Total Value - Dated Funds";
+        310, Summary, Credit, "Total Commercial Deposits";
+        315, Summary, Credit, "Total International Credits - FF";
+        316, Summary, Credit, "Total International Credits - CHF";
+        318, Summary, Credit, "Total Foreign Check Purchased";
+        319, Summary, Credit, "Late Deposit";
+        320, Summary, Credit, "Total Securities Sold - FF";
+        321, Summary, Credit, "Total Securities Sold - CHF";
+        324, Summary, Credit, "Total Securities Matured - FF";
+        325, Summary, Credit, "Total Securities Matured - CHF";
+        326, Summary, Credit, "Total Securities Interest";
+        327, Summary, Credit, "Total Securities Matured";
+        328, Summary, Credit, "Total Securities Interest - FF";
+        329, Summary, Credit, "Total Securities Interest - CHF";
+        330, Summary, Credit, "Total Escrow Credits";
+        331, Detail, Credit, "Individual Escrow Credit";
+        332, Summary, Credit, "Total Miscellaneous Securities Credits - FF";
+        336, Summary, Credit, "Total Miscellaneous Securities Credits - CHF";
+        338, Summary, Credit, "Total Securities Sold";
+        340, Summary, Credit, "Total Broker Deposits";
+        341, Summary, Credit, "Total Broker Deposits - FF";
+        342, Detail, Credit, "Broker Deposit";
+        343, Summary, Credit, "Total Broker Deposits - CHF";
+        344, Detail, Credit, "Individual Back Value Credit";
+        345, Detail, Credit, "Item in Brokers Deposit";
+        346, Detail, Credit, "Sweep Interest Income";
+        347, Detail, Credit, "Sweep Principal Sell";
+        348, Detail, Credit, "Futures Credit";
+        349, Detail, Credit, "Principal Payments Credit";
+        350, Summary, Credit, "Investment Sold";
+        351, Detail, Credit, "Individual Investment Sold";
+        352, Summary, Credit, "Total Cash Center Credits";
+        353, Detail, Credit, "Cash Center Credit";
+        354, Detail, Credit, "Interest Credit";
+        355, Summary, Credit, "Investment Interest";
+        356, Summary, Credit, "Total Credit Adjustment";
+        357, Detail, Credit, "Credit Adjustment";
+        358, Detail, Credit, "YTD Adjustment Credit";
+        359, Detail, Credit, "Interest Adjustment Credit";
+        360, Summary, Credit, "Total Credits Less Wire Transfer and Returned Checks";
+        361, Summary, Credit, "Grand Total Credits Less Grand Total Debits";
+    }
+    "Correspondent Bank and Federal Reserve" => {
+        362, Detail, Credit, "Correspondent Collection";
+        363, Detail, Credit, "Correspondent Collection Adjustment";
+        364, Detail, Credit, "Loan Participation";
+        366, Detail, Credit, "Currency and Coin Deposited";
+        367, Detail, Credit, "Food Stamp Letter";
+        368, Detail, Credit, "Food Stamp Adjustment";
+        369, Detail, Credit, "Clearing Settlement Credit";
+        370, Summary, Credit, "Total Back Value Credits";
+        372, Detail, Credit, "Back Value Adjustment";
+        373, Detail, Credit, "Customer Payroll";
+        374, Detail, Credit, "FRB Statement Recap";
+        376, Detail, Credit, "Savings Bond Letter or Adjustment";
+        377, Detail, Credit, "Treasury Tax and Loan Credit";
+        378, Detail, Credit, "Transfer of Treasury Credit";
+        379, Detail, Credit, "FRB Government Checks Cash Letter Credit";
+        381, Detail, Credit, "FRB Government Check Adjustment";
+        382, Detail, Credit, "FRB Postal Money Order Credit";
+        383, Detail, Credit, "FRB Postal Money Order Adjustment";
+        384, Detail, Credit, "FRB Cash Letter Auto Charge Credit";
+        385, Summary, Credit, "Total Universal Credits";
+        386, Detail, Credit, "FRB Cash Letter Auto Charge Adjustment";
+        387, Detail, Credit, "FRB Fine-Sort Cash Letter Credit";
+        388, Detail, Credit, "FRB Fine-Sort Adjustment";
+        389, Summary, Credit, "Total Freight Payment Credits";
+    }
+    "Miscellaneous" => {
+        390, Summary, Credit, "Total Miscellaneous Credits";
+        391, Detail, Credit, "Universal Credit";
+        392, Detail, Credit, "Freight Payment Credit";
+        393, Detail, Credit, "Itemized Credit Over $10,000";
+        394, Detail, Credit, "Cumulative Credits";
+        395, Detail, Credit, "Check Reversal";
+        397, Detail, Credit, "Float Adjustment";
+        398, Detail, Credit, "Miscellaneous Fee Refund";
+        399, Detail, Credit, "Miscellaneous Credit";
+    }
+    "Summary and Detail Debits" => {
+        400, Summary, Debit, "Total Debits";
+        401, Summary, Debit, "Total Debit Amount MTD";
+        403, Summary, Debit, "Today's Total Debits";
+        405, Summary, Debit, "Total Debit Less Wire Transfers and Charge-Backs";
+        406, Summary, Debit, "Debits not Detailed";
+        408, Detail, Debit, "Float Adjustment";
+        409, Detail, Debit, "Debit (Any Type)";
+        410, Summary, Debit, "Total YTD Adjustment";
+        412, Summary, Debit, "Total Debits (Excluding Returned Items)";
+    }
+    "Lockbox" => {
+        415, Detail, Debit, "Lockbox Debit";
+        416, Summary, Debit, "Total Lockbox Debits";
+        420, Summary, Debit, "EDI Transaction Debits";
+        421, Detail, Debit, "EDI Transaction Debit";
+        422, Detail, Debit, "EDIBANX Settlement Debit";
+        423, Detail, Debit, "EDIBANX Return Item Debit";
+    }
+    "Payable-Through Draft" => {
+        430, Summary, Debit, "Total Payable-Through Drafts";
+        435, Detail, Debit, "Payable-Through Draft";
+    }
+    "ACH" => {
+        445, Detail, Debit, "ACH Concentration Debit";
+        446, Summary, Debit, "Total ACH Disbursement Funding Debits";
+        447, Detail, Debit, "ACH Disbursement Funding Debit";
+        450, Summary, Debit, "Total ACH Debits";
+        451, Detail, Debit, "ACH Debit Received";
+        452, Detail, Debit, "Item in ACH Disbursement or Debit";
+        455, Detail, Debit, "Preauthorized ACH Debit";
+        458, Detail, Debit, "Real Time Payment Debit", Btrs3;
+        462, Detail, Debit, "Account Holder Initiated ACH Debit";
+        463, Summary, Debit, "Corporate Trade Payment Debits";
+        464, Detail, Debit, "Corporate Trade Payment Debit";
+        465, Summary, Debit, "Corporate Trade Payment Settlement";
+        466, Detail, Debit, "ACH Settlement";
+        467, Summary, Debit, "ACH Settlement Debits";
+        468, Detail, Debit, "ACH Return Item or Adjustment Settlement";
+        469, Detail, Debit, "Miscellaneous ACH Debit";
+    }
+    "Checks Paid" => {
+        470, Summary, Debit, "Total Check Paid";
+        471, Summary, Debit, "Total Check Paid - Cumulative MTD";
+        472, Detail, Debit, "Cumulative Checks Paid";
+        474, Detail, Debit, "Certified Check Debit";
+        475, Detail, Debit, "Check Paid";
+        476, Detail, Debit, "Federal Reserve Bank Letter Debit";
+        477, Detail, Debit, "Bank Originated Debit";
+        478, Summary, Debit, "List Post Debits";
+        479, Detail, Debit, "List Post Debit";
+        480, Summary, Debit, "Total Loan Payments";
+        481, Detail, Debit, "Individual Loan Payment";
+        482, Summary, Debit, "Total Bank-Originated Debits";
+        484, Detail, Debit, "Draft";
+        485, Detail, Debit, "DTC Debit";
+        486, Summary, Debit, "Total Cash Letter Debits";
+        487, Detail, Debit, "Cash Letter Debit";
+        489, Detail, Debit, "Cash Letter Adjustment";
+    }
+    "Money Transfer" => {
+        490, Summary, Debit, "Total Outgoing Money Transfers";
+        491, Detail, Debit, "Individual Outgoing Internal Money Transfer";
+        493, Detail, Debit, "Customer Terminal Initiated Money Transfer";
+        495, Detail, Debit, "Outgoing Money Transfer";
+        496, Detail, Debit, "Money Transfer Adjustment";
+        498, Detail, Debit, "Compensation";
+        500, Summary, Debit, "Total Automatic Transfer Debits";
+        501, Detail, Debit, "Individual Automatic Transfer Debit";
+        502, Detail, Debit, "Bond Operations Debit";
+        505, Summary, Debit, "Total Book Transfer Debits";
+        506, Detail, Debit, "Book Transfer Debit";
+        507, Summary, Debit, "Total International Money Transfer Debits";
+        508, Detail, Debit, "Individual International Money Transfer Debits";
+        510, Summary, Debit, "Total International Debits";
+        512, Detail, Debit, "Letter of Credit Debit";
+        513, Detail, Debit, "Letter of Credit";
+        514, Detail, Debit, "Foreign Exchange Debit";
+        515, Summary, Debit, "Total Letters of Credit";
+        516, Detail, Debit, "Foreign Remittance Debit";
+        518, Detail, Debit, "Foreign Collection Debit";
+        522, Detail, Debit, "Foreign Checks Paid";
+        524, Detail, Debit, "Commission";
+        526, Detail, Debit, "International Money Market Trading";
+        527, Detail, Debit, "Standing Order";
+        529, Detail, Debit, "Miscellaneous International Debit";
+    }
+    "Security" => {
+        530, Summary, Debit, "Total Security Debits";
+        531, Detail, Debit, "Securities Purchased";
+        532, Summary, Debit, "Total Amount of Securities Purchased";
+        533, Detail, Debit, "Security Collection Debit";
+        534, Summary, Debit, "Total Miscellaneous Securities DB - FF";
+        535, Detail, Debit, "Purchase of Equity Securities";
+        536, Summary, Debit, "Total Miscellaneous Securities Debit - CHF";
+        537, Summary, Debit, "Total Collection Debit";
+        538, Detail, Debit, "Matured Repurchase Order";
+        539, Summary, Debit, "Total Bankers' Acceptances Debit";
+        540, Detail, Debit, "Coupon Collection Debit";
+        541, Detail, Debit, "Bankers' Acceptances";
+        542, Detail, Debit, "Purchase of Debt Securities";
+        543, Detail, Debit, "Domestic Collection";
+        544, Detail, Debit, "Interest/Matured Principal Payment";
+        546, Detail, Debit, "Commercial paper";
+        547, Detail, Debit, "Capital Change";
+        548, Detail, Debit, "Savings Bonds Sales Adjustment";
+        549, Detail, Debit, "Miscellaneous Security Debit";
+    }
+    "Deposited Items Returned" => {
+        550, Summary, Debit, "Total Deposited Items Returned";
+        551, Summary, Debit, "Total Credit Reversals";
+        552, Detail, Debit, "Credit Reversal";
+        554, Detail, Debit, "Posting Error Correction Debit";
+        555, Detail, Debit, "Deposited Item Returned";
+        556, Summary, Debit, "Total ACH Return Items";
+        557, Detail, Debit, "Individual ACH Return Item";
+        558, Detail, Debit, "ACH Reversal Debit";
+        560, Summary, Debit, "Total Rejected Debits";
+        561, Detail, Debit, "Individual Rejected Debit";
+        563, Detail, Debit, "Overdraft";
+        564, Detail, Debit, "Overdraft Fee";
+        566, Detail, Debit, "Return Item";
+        567, Detail, Debit, "Return Item Fee";
+        568, Detail, Debit, "Return Item Adjustment";
+    }
+    "ZBA and Disbursing" => {
+        570, Summary, Debit, "Total ZBA Debits";
+        574, Detail, Debit, "Cumulative ZBA Debits";
+        575, Detail, Debit, "ZBA Debit";
+        577, Detail, Debit, "ZBA Debit Transfer";
+        578, Detail, Debit, "ZBA Debit Adjustment";
+        580, Summary, Debit, "Total Controlled Disbursing Debits";
+        581, Detail, Debit, "Individual Controlled Disbursing Debit";
+        583, Summary, Debit, "Total Disbursing Checks Paid - Early Amount";
+        584, Summary, Debit, "Total Disbursing Checks Paid - Later Amount";
+        585, Summary, Debit, "Disbursing Funding Requirement";
+        586, Summary, Debit, "FRB Presentment Estimate (Fed Estimate)";
+        587, Summary, Debit, "Late Debits (After Notification)";
+        588, Summary, Debit, "Total Disbursing Checks Paid-Last Amount";
+    }
+    "Other (Expansion)" => {
+        590, Summary, Debit, "Total DTC Debits";
+        594, Summary, Debit, "Total ATM Debits";
+        595, Detail, Debit, "ATM Debit";
+        596, Summary, Debit, "Total APR Debits";
+        597, Detail, Debit, "ARP Debit";
+        601, Summary, Debit, "Estimated Total Disbursement";
+        602, Summary, Debit, "Adjusted Total Disbursement";
+        610, Summary, Debit, "Total Funds Required";
+        611, Summary, Debit, "Total Wire Transfers Out- CHF";
+        612, Summary, Debit, "Total Wire Transfers Out - FF";
+        613, Summary, Debit, "Total International Debit - CHF";
+        614, Summary, Debit, "Total International Debit - FF";
+        615, Summary, Debit, "Total Federal Reserve Bank - Commercial Bank Debit";
+        616, Detail, Debit, "Federal Reserve Bank - Commercial Bank Debit";
+        617, Summary, Debit, "Total Securities Purchased - CHF";
+        618, Summary, Debit, "Total Securities Purchased - FF";
+        621, Summary, Debit, "Total Broker Debits - CHF";
+        622, Detail, Debit, "Broker Debit";
+        623, Summary, Debit, "Total Broker Debits - FF";
+        625, Summary, Debit, "Total Broker Debits";
+        626, Summary, Debit, "Total Fed Funds Purchased";
+        627, Detail, Debit, "Fed Funds Purchased";
+        628, Summary, Debit, "Total Cash Center Debits";
+        629, Detail, Debit, "Cash Center Debit";
+        630, Summary, Debit, "Total Debit Adjustments";
+        631, Detail, Debit, "Debit Adjustment";
+        632, Summary, Debit, "Total Trust Debits";
+        633, Detail, Debit, "Trust Debit";
+        634, Detail, Debit, "YTD Adjustment Debit";
+        640, Summary, Debit, "Total Escrow Debits";
+        641, Detail, Debit, "Individual Escrow Debit";
+        644, Detail, Debit, "Individual Back Value Debit";
+        646, Summary, Debit, "Transfer Calculation Debit";
+        650, Summary, Debit, "Investments Purchased";
+        651, Detail, Debit, "Individual Investment purchased";
+        654, Detail, Debit, "Interest Debit";
+        655, Summary, Debit, "Total Investment Interest Debits";
+        656, Detail, Debit, "Sweep Principal Buy";
+        657, Detail, Debit, "Futures Debit";
+        658, Detail, Debit, "Principal Payments Debit";
+        659, Detail, Debit, "Interest Adjustment Debit";
+    }
+    "Correspondent Bank and Federal Reserve" => {
+        661, Detail, Debit, "Account Analysis Fee";
+        662, Detail, Debit, "Correspondent Collection Debit";
+        663, Detail, Debit, "Correspondent Collection Adjustment";
+        664, Detail, Debit, "Loan Participation";
+        665, Summary, Debit, "Intercept Debits";
+        666, Detail, Debit, "Currency and Coin Shipped";
+        667, Detail, Debit, "Food Stamp Letter";
+        668, Detail, Debit, "Food Stamp Adjustment";
+        669, Detail, Debit, "Clearing Settlement Debit";
+        670, Summary, Debit, "Total Back Value Debits";
+        672, Detail, Debit, "Back Value Adjustment";
+        673, Detail, Debit, "Customer Payroll";
+        674, Detail, Debit, "FRB Statement Recap";
+        676, Detail, Debit, "Savings Bond Letter or Adjustment";
+        677, Detail, Debit, "Treasury Tax and Loan Debit";
+        678, Detail, Debit, "Transfer of Treasury Debit";
+        679, Detail, Debit, "FRB Government Checks Cash Letter Debit";
+        681, Detail, Debit, "FRB Government Check Adjustment";
+        682, Detail, Debit, "FRB Postal Money Order Debit";
+        683, Detail, Debit, "FRB Postal Money Order Adjustment";
+        684, Detail, Debit, "FRB Cash Letter Auto Charge Debit";
+        685, Summary, Debit, "Total Universal Debits";
+        686, Detail, Debit, "FRB Cash Letter Auto Charge Adjustment";
+        687, Detail, Debit, "FRB Fine-Sort Cash Letter Debit";
+        688, Detail, Debit, "FRB Fine-Sort Adjustment";
+        689, Summary, Debit, "FRB Freight Payment Debits";
+        690, Summary, Debit, "Total Miscellaneous Debits";
+        691, Detail, Debit, "Universal Debit";
+        692, Detail, Debit, "Freight Payment Debit";
+        693, Detail, Debit, "Itemized Debit Over $10,000";
+        694, Detail, Debit, "Deposit Reversal";
+        695, Detail, Debit, "Deposit Correction Debit";
+        696, Detail, Debit, "Regular Collection Debit";
+        697, Detail, Debit, "Cumulative Debits";
+        698, Detail, Debit, "Miscellaneous Fees";
+        699, Detail, Debit, "Miscellaneous Debit";
+    }
+    "Loan Transactions" => {
+        701, Status, NonMonetary, "Principal Loan Balance";
+        703, Status, NonMonetary, "Available Commitment Amount";
+        705, Status, NonMonetary, "Payment Amount Due";
+        707, Status, NonMonetary, "Principal Amount Past Due";
+        709, Status, NonMonetary, "Interest Amount Past Due";
+        720, Summary, Credit, "Total Loan Payment";
+        721, Detail, Credit, "Amount Applied to Interest";
+        722, Detail, Credit, "Amount Applied to Principal";
+        723, Detail, Credit, "Amount Applied to Escrow";
+        724, Detail, Credit, "Amount Applied to Late Charges";
+        725, Detail, Credit, "Amount Applied to Buydown";
+        726, Detail, Credit, "Amount Applied to Misc. Fees";
+        727, Detail, Credit, "Amount Applied to Deferred Interest Detail";
+        728, Detail, Credit, "Amount Applied to Service Charge";
+        760, Summary, Debit, "Loan Disbursement";
+    }
+    "Non-Monetary Information" => {
+        890, Detail, NonMonetary, "Contains Non-monetary Information";
+    }
+}
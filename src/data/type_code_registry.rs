@@ -0,0 +1,331 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "toml-registry")]
+use toml;
+
+use super::{DetailCode, Direction, Level, StatusCode, SummaryCode, TypeCode, TypeCodeInfo,
+            Version};
+
+/// A bank's own meaning for a type code. Levels and directions deserialize as `from_csv` reads
+/// them, in any case and with their abbreviations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-serialize", serde(deny_unknown_fields))]
+pub struct CustomCode {
+    pub code: u16,
+    #[cfg_attr(feature = "serde-serialize", serde(deserialize_with = "deserialize_from_str"))]
+    pub level: Level,
+    #[cfg_attr(feature = "serde-serialize", serde(deserialize_with = "deserialize_from_str"))]
+    pub direction: Direction,
+    #[cfg_attr(feature = "serde-serialize", serde(default))]
+    pub description: String,
+}
+
+/// Type codes a bank defines beyond the spec, such as its meanings for the custom ranges
+/// (900-999) or for codes the spec leaves undefined.
+///
+/// Tables can be loaded with `from_csv`, from lines of `code,level,direction,description`, or
+/// with `from_toml` (behind the `toml-registry` feature), from `[[codes]]` tables with those
+/// same keys. Registries also deserialize from a `codes` list of them. Registered codes take
+/// precedence over the spec when converting, and convert to the `Registered` variants of the
+/// code types, which carry their `TypeCodeInfo`. So that those stay `Copy`, descriptions are
+/// leaked when registered; registries are meant to be loaded once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize))]
+pub struct TypeCodeRegistry {
+    codes: Vec<TypeCodeInfo>,
+}
+
+impl TypeCodeRegistry {
+    pub fn new() -> Self {
+        TypeCodeRegistry::default()
+    }
+
+    /// Registers `code`, replacing any code already registered with the same number and level.
+    /// Codes outside 001-999, status codes that are credits or debits, and summary codes that
+    /// are neither can't be registered.
+    pub fn insert(&mut self, code: CustomCode) -> Result<(), RegistryError> {
+        if check(&code).is_err() {
+            return Err(RegistryError::Unrepresentable(code.code, code.level, code.direction));
+        }
+        self.insert_info(TypeCodeInfo {
+            code: code.code,
+            description: Box::leak(code.description.into_boxed_str()),
+            level: code.level,
+            direction: code.direction,
+            loan: code.code >= 700 && code.code <= 799,
+            section: "Registered",
+            version: Version::Bai2,
+        });
+        Ok(())
+    }
+
+    fn insert_info(&mut self, info: TypeCodeInfo) {
+        match self.codes
+            .iter()
+            .position(|c| c.code == info.code && c.level == info.level)
+        {
+            Some(i) => self.codes[i] = info,
+            None => self.codes.push(info),
+        }
+    }
+
+    /// Registers every code in `other`, replacing any already registered.
+    pub fn merge(&mut self, other: &TypeCodeRegistry) {
+        for &info in &other.codes {
+            self.insert_info(info);
+        }
+    }
+
+    pub fn get(&self, code: u16, level: Level) -> Option<TypeCodeInfo> {
+        self.codes
+            .iter()
+            .find(|c| c.code == code && c.level == level)
+            .cloned()
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<TypeCodeInfo> {
+        self.codes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// `code` as a status code, if it's registered as one.
+    pub fn status_code(&self, code: u16) -> Option<StatusCode> {
+        self.get(code, Level::Status).map(StatusCode::Registered)
+    }
+
+    /// `code` as a summary code, if it's registered as one.
+    pub fn summary_code(&self, code: u16) -> Option<SummaryCode> {
+        self.get(code, Level::Summary).map(SummaryCode::Registered)
+    }
+
+    /// `code` as a detail code, if it's registered as one.
+    pub fn detail_code(&self, code: u16) -> Option<DetailCode> {
+        self.get(code, Level::Detail).map(DetailCode::Registered)
+    }
+
+    /// The description of `code`, from the registry or else from the spec.
+    pub fn description(&self, code: TypeCode) -> Option<&str> {
+        let level = match code {
+            TypeCode::Status(_) => Level::Status,
+            TypeCode::Summary(_) => Level::Summary,
+            TypeCode::Detail(_) => Level::Detail,
+        };
+        self.get(u16::from(code), level)
+            .or_else(|| code.info())
+            .map(|info| info.description)
+    }
+
+    /// Reads lines of `code,level,direction,description`. A first line starting with `code` is
+    /// taken as a header, fields may be quoted with `"`, and lines starting with `#` are
+    /// skipped.
+    pub fn from_csv(table: &str) -> Result<Self, RegistryError> {
+        let mut registry = TypeCodeRegistry::new();
+        for (i, line) in table.lines().enumerate() {
+            let line_num = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("code")) {
+                continue;
+            }
+            let fields = csv_fields(line).ok_or(RegistryError::Syntax(line_num))?;
+            if fields.len() != 4 {
+                return Err(RegistryError::Syntax(line_num));
+            }
+            let code = CustomCode {
+                code: parse_field(&fields[0], line_num, "code")?,
+                level: parse_field(&fields[1], line_num, "level")?,
+                direction: parse_field(&fields[2], line_num, "direction")?,
+                description: fields[3].clone(),
+            };
+            check(&code).map_err(|field| RegistryError::Field(line_num, field))?;
+            registry.insert(code)?;
+        }
+        Ok(registry)
+    }
+
+    /// Reads `[[codes]]` tables with `code`, `level`, `direction` and `description` keys, as in
+    ///
+    /// ```toml
+    /// [[codes]]
+    /// code = 925
+    /// level = "detail"
+    /// direction = "credit"
+    /// description = "Cash Concentration Credit"
+    /// ```
+    ///
+    /// Other keys are errors.
+    #[cfg(feature = "toml-registry")]
+    pub fn from_toml(table: &str) -> Result<Self, RegistryError> {
+        toml::from_str(table).map_err(RegistryError::Toml)
+    }
+}
+
+#[cfg(feature = "serde-serialize")]
+impl<'de> ::serde::Deserialize<'de> for TypeCodeRegistry {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Codes {
+            codes: Vec<CustomCode>,
+        }
+
+        let mut registry = TypeCodeRegistry::new();
+        for code in Codes::deserialize(deserializer)?.codes {
+            registry.insert(code).map_err(::serde::de::Error::custom)?;
+        }
+        Ok(registry)
+    }
+}
+
+/// Checks that `code` can be registered, returning the field that's wrong if it can't.
+fn check(code: &CustomCode) -> Result<(), &'static str> {
+    match (code.code, code.level, code.direction) {
+        (0, _, _) | (1000...0xffff, _, _) => Err("code"),
+        (_, Level::Status, Direction::Credit) |
+        (_, Level::Status, Direction::Debit) |
+        (_, Level::Summary, Direction::NonMonetary) => Err("direction"),
+        _ => Ok(()),
+    }
+}
+
+fn parse_field<T>(s: &str, line: usize, field: &'static str) -> Result<T, RegistryError>
+where
+    T: FromStr,
+{
+    s.trim().parse().map_err(|_| RegistryError::Field(line, field))
+}
+
+fn csv_fields(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return None,
+                }
+            }
+            while chars.peek() == Some(&' ') {
+                chars.next();
+            }
+            match chars.next() {
+                Some(',') => fields.push(field),
+                None => {
+                    fields.push(field);
+                    return Some(fields);
+                }
+                Some(_) => return None,
+            }
+        } else {
+            loop {
+                match chars.next() {
+                    Some(',') => break,
+                    Some(c) => field.push(c),
+                    None => {
+                        fields.push(field.trim().to_owned());
+                        return Some(fields);
+                    }
+                }
+            }
+            fields.push(field.trim().to_owned());
+        }
+    }
+}
+
+/// Deserializes a level or direction with its `FromStr` impl.
+#[cfg(feature = "serde-serialize")]
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: ::serde::Deserializer<'de>,
+    T: FromStr,
+{
+    let s = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+    s.parse().map_err(|_| ::serde::de::Error::custom(format!("invalid value {:?}", s)))
+}
+
+#[derive(Debug, Clone)]
+pub enum RegistryError {
+    /// A line that isn't a valid entry.
+    Syntax(usize),
+    /// An entry on the line with a field that's missing or invalid.
+    Field(usize, &'static str),
+    /// A code that can't be registered with the level and direction it was given.
+    Unrepresentable(u16, Level, Direction),
+    /// A TOML table that isn't valid, or has an entry that isn't.
+    #[cfg(feature = "toml-registry")]
+    Toml(toml::de::Error),
+}
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegistryError::Syntax(line) => write!(f, "line {}: invalid entry", line),
+            RegistryError::Field(line, field) => {
+                write!(f, "line {}: missing or invalid {}", line, field)
+            }
+            RegistryError::Unrepresentable(code, level, direction) => {
+                write!(
+                    f,
+                    "type code {:03} can't be registered as a {:?} {:?} code",
+                    code,
+                    direction,
+                    level
+                )
+            }
+            #[cfg(feature = "toml-registry")]
+            RegistryError::Toml(ref e) => write!(f, "invalid type code table: {}", e),
+        }
+    }
+}
+impl error::Error for RegistryError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            #[cfg(feature = "toml-registry")]
+            RegistryError::Toml(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Level, ()> {
+        match &*s.to_ascii_lowercase() {
+            "status" => Ok(Level::Status),
+            "summary" => Ok(Level::Summary),
+            "detail" => Ok(Level::Detail),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Direction, ()> {
+        match &*s.to_ascii_lowercase() {
+            "credit" | "cr" => Ok(Direction::Credit),
+            "debit" | "db" => Ok(Direction::Debit),
+            "nonmonetary" | "non-monetary" | "na" | "n/a" => Ok(Direction::NonMonetary),
+            _ => Err(()),
+        }
+    }
+}
@@ -3,6 +3,9 @@
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 
+use super::{Amount, AmountOverflow, Version};
+use super::type_code_list::TYPE_CODES;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub enum TypeCode {
@@ -10,13 +13,93 @@ pub enum TypeCode {
     Summary(SummaryCode),
     Detail(DetailCode),
 }
+impl TypeCode {
+    /// What the spec says about `code`, if it's one the spec lists.
+    pub fn lookup(code: u16) -> Option<TypeCodeInfo> {
+        TYPE_CODES
+            .binary_search_by_key(&code, |info| info.code)
+            .ok()
+            .map(|i| TYPE_CODES[i])
+    }
+
+    /// What the spec, or the registry a code was converted with, says about the code.
+    pub fn info(&self) -> Option<TypeCodeInfo> {
+        match *self {
+            TypeCode::Status(c) => c.info(),
+            TypeCode::Summary(c) => c.info(),
+            TypeCode::Detail(c) => c.info(),
+        }
+    }
+}
 impl fmt::Display for TypeCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Type(")?;
+        write_code(f, u16::from(*self), self.info())
+    }
+}
+
+/// Writes `code` along with its description, as in "115 Lockbox Deposit".
+fn write_code(f: &mut fmt::Formatter, code: u16, info: Option<TypeCodeInfo>) -> fmt::Result {
+    match info {
+        Some(info) => write!(f, "{:03} {}", code, info.description),
+        None => write!(f, "{:03}", code),
+    }
+}
+
+/// Whether `code` is defined in `version`, since BTRS adds codes BAI2 doesn't have.
+fn defined_in(code: u16, version: Version) -> bool {
+    match TypeCode::lookup(code) {
+        Some(info) => info.version.version_number() <= version.version_number(),
+        None => true,
+    }
+}
+
+fn table_direction(code: u16) -> Direction {
+    TypeCode::lookup(code).map_or(Direction::NonMonetary, |info| info.direction)
+}
+
+/// What the spec says about a type code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize))]
+pub struct TypeCodeInfo {
+    pub code: u16,
+    pub description: &'static str,
+    pub level: Level,
+    pub direction: Direction,
+    /// Whether the code is one of the loan codes (700-799).
+    pub loan: bool,
+    /// The heading the spec lists the code under, such as "Lockbox", or "Registered" for codes
+    /// from a `TypeCodeRegistry`.
+    pub section: &'static str,
+    /// The first version the code is defined in.
+    pub version: Version,
+}
+
+/// Whether a type code reports a balance, a total of transactions, or a single transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Level {
+    Status,
+    Summary,
+    Detail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Direction {
+    Credit,
+    Debit,
+    /// Neither a credit nor a debit, like status codes and 890.
+    NonMonetary,
+}
+
+impl Direction {
+    /// `amount` signed by the direction: positive for credits, negative for debits, and `None`
+    /// for codes that are neither. Debits of `i64::MIN` can't be negated, and are an error.
+    pub fn sign(&self, amount: Amount) -> Result<Option<Amount>, AmountOverflow> {
         match *self {
-            TypeCode::Status(ref code) => write!(f, "Status, {})", code),
-            TypeCode::Summary(ref code) => write!(f, "Summary, {})", code),
-            TypeCode::Detail(ref code) => write!(f, "Detail, {})", code),
+            Direction::Credit => Ok(Some(amount)),
+            Direction::Debit => amount.negate().map(Some),
+            Direction::NonMonetary => Ok(None),
         }
     }
 }
@@ -38,6 +121,9 @@ pub enum StatusCode {
     Account(AccountStatus),
     // 700-719
     Loan(LoanStatus),
+    // Codes from a `TypeCodeRegistry`, along with what it says about them.
+    #[cfg_attr(feature = "serde-serialize", serde(skip_deserializing))]
+    Registered(TypeCodeInfo),
 }
 
 impl TryFrom<u16> for StatusCode {
@@ -55,18 +141,32 @@ impl From<StatusCode> for u16 {
         match code {
             StatusCode::Account(c) => c.into(),
             StatusCode::Loan(c) => c.into(),
+            StatusCode::Registered(info) => info.code,
         }
     }
 }
-impl fmt::Display for StatusCode {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Status(")?;
+impl StatusCode {
+    /// Like `try_from`, but only with the codes defined in `version`. Both reserve 900-919 for
+    /// custom status codes.
+    pub fn from_version(code: u16, version: Version) -> Result<StatusCode, u16> {
+        match StatusCode::try_from(code) {
+            Ok(_) if !defined_in(code, version) => Err(code),
+            result => result,
+        }
+    }
+
+    pub fn info(&self) -> Option<TypeCodeInfo> {
         match *self {
-            StatusCode::Account(ref code) => write!(f, "Account, {:?})", code),
-            StatusCode::Loan(ref code) => write!(f, "Loan, {:?})", code),
+            StatusCode::Registered(info) => Some(info),
+            _ => TypeCode::lookup(u16::from(*self)),
         }
     }
 }
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_code(f, u16::from(*self), self.info())
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -77,6 +177,9 @@ pub enum SummaryCode {
     Debit(DebitSummary),
     // 700-799
     Loan(LoanSummary),
+    // Codes from a `TypeCodeRegistry`, along with what it says about them.
+    #[cfg_attr(feature = "serde-serialize", serde(skip_deserializing))]
+    Registered(TypeCodeInfo),
 }
 
 impl TryFrom<u16> for SummaryCode {
@@ -84,29 +187,54 @@ impl TryFrom<u16> for SummaryCode {
     fn try_from(code: u16) -> Result<SummaryCode, u16> {
         match code {
             c @ 100...399 | c @ 920...959 => c.try_into().map(SummaryCode::Credit),
-            c @ 400...469 | c @ 960...999 => c.try_into().map(SummaryCode::Debit),
+            c @ 400...699 | c @ 960...999 => c.try_into().map(SummaryCode::Debit),
             c @ 700...799 => c.try_into().map(SummaryCode::Loan),
             _ => Err(code),
         }
     }
 }
+impl SummaryCode {
+    /// Like `try_from`, but with the codes and ranges of `version`. BTRS splits the custom
+    /// ranges BAI2 shares between summary and detail codes.
+    pub fn from_version(code: u16, version: Version) -> Result<SummaryCode, u16> {
+        match (version, code) {
+            (Version::Btrs3, 935...959) | (Version::Btrs3, 975...999) => Err(code),
+            _ if !defined_in(code, version) => Err(code),
+            _ => SummaryCode::try_from(code),
+        }
+    }
+
+    /// Whether the code totals credits or debits. Loan codes go by the spec's tables, and
+    /// registered codes by their registration.
+    pub fn direction(&self) -> Direction {
+        match *self {
+            SummaryCode::Credit(_) => Direction::Credit,
+            SummaryCode::Debit(_) => Direction::Debit,
+            SummaryCode::Loan(c) => table_direction(c.into()),
+            SummaryCode::Registered(info) => info.direction,
+        }
+    }
+
+    pub fn info(&self) -> Option<TypeCodeInfo> {
+        match *self {
+            SummaryCode::Registered(info) => Some(info),
+            _ => TypeCode::lookup(u16::from(*self)),
+        }
+    }
+}
 impl From<SummaryCode> for u16 {
     fn from(code: SummaryCode) -> u16 {
         match code {
             SummaryCode::Credit(c) => c.into(),
             SummaryCode::Debit(c) => c.into(),
             SummaryCode::Loan(c) => c.into(),
+            SummaryCode::Registered(info) => info.code,
         }
     }
 }
 impl fmt::Display for SummaryCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Summary(")?;
-        match *self {
-            SummaryCode::Credit(ref code) => write!(f, "Credit, {:?})", code),
-            SummaryCode::Debit(ref code) => write!(f, "Debit, {:?})", code),
-            SummaryCode::Loan(ref code) => write!(f, "Loan, {:?})", code),
-        }
+        write_code(f, u16::from(*self), self.info())
     }
 }
 
@@ -121,6 +249,11 @@ pub enum DetailCode {
     Loan(LoanDetail),
     // 890
     NonMonetary,
+    // Codes the spec doesn't define, only used when processing leniently.
+    Unknown(u16),
+    // Codes from a `TypeCodeRegistry`, along with what it says about them.
+    #[cfg_attr(feature = "serde-serialize", serde(skip_deserializing))]
+    Registered(TypeCodeInfo),
 }
 
 impl TryFrom<u16> for DetailCode {
@@ -135,6 +268,46 @@ impl TryFrom<u16> for DetailCode {
         }
     }
 }
+impl DetailCode {
+    /// Like `try_from`, but with the codes and ranges of `version`. BTRS splits the custom
+    /// ranges BAI2 shares between summary and detail codes, and adds codes such as 158.
+    pub fn from_version(code: u16, version: Version) -> Result<DetailCode, u16> {
+        match (version, code) {
+            (Version::Btrs3, 920...934) | (Version::Btrs3, 960...974) => Err(code),
+            _ if !defined_in(code, version) => Err(code),
+            _ => DetailCode::try_from(code),
+        }
+    }
+
+    /// Whether the code is a credit or a debit. Loan codes go by the spec's tables, registered
+    /// codes by their registration, and unknown codes are taken as neither.
+    pub fn direction(&self) -> Direction {
+        match *self {
+            DetailCode::Credit(_) => Direction::Credit,
+            DetailCode::Debit(_) => Direction::Debit,
+            DetailCode::Loan(c) => table_direction(c.into()),
+            DetailCode::NonMonetary | DetailCode::Unknown(_) => Direction::NonMonetary,
+            DetailCode::Registered(info) => info.direction,
+        }
+    }
+
+    pub fn info(&self) -> Option<TypeCodeInfo> {
+        match *self {
+            DetailCode::Registered(info) => Some(info),
+            _ => TypeCode::lookup(u16::from(*self)),
+        }
+    }
+
+    /// The custom code for credits (959) or debits (999) that no code in the spec fits, such as
+    /// transactions imported from other formats. Both are in the detail ranges of BAI2 and BTRS.
+    pub fn custom(credit: bool) -> DetailCode {
+        if credit {
+            DetailCode::Credit(CreditDetail::Custom(959))
+        } else {
+            DetailCode::Debit(DebitDetail::Custom(999))
+        }
+    }
+}
 impl From<DetailCode> for u16 {
     fn from(code: DetailCode) -> u16 {
         match code {
@@ -142,18 +315,14 @@ impl From<DetailCode> for u16 {
             DetailCode::Debit(c) => c.into(),
             DetailCode::Loan(c) => c.into(),
             DetailCode::NonMonetary => 890,
+            DetailCode::Unknown(c) => c,
+            DetailCode::Registered(info) => info.code,
         }
     }
 }
 impl fmt::Display for DetailCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Summary(")?;
-        match *self {
-            DetailCode::Credit(ref code) => write!(f, "Credit, {:?})", code),
-            DetailCode::Debit(ref code) => write!(f, "Debit, {:?})", code),
-            DetailCode::Loan(ref code) => write!(f, "Loan, {:?})", code),
-            DetailCode::NonMonetary => write!(f, "NonMonetary)"),
-        }
+        write_code(f, u16::from(*self), self.info())
     }
 }
 
@@ -417,7 +586,9 @@ enum_mapping! {
         InterceptDebits(665),
         TotalBackValueDebits(670),
         TotalUniversalDebits(685),
-        FrbFreightPaymentDebits(689);
+        FrbFreightPaymentDebits(689),
+        // Miscellaneous
+        TotalMiscDebits(690);
 
         Custom {
             from: c @ 960...999 => Ok(DebitSummary::Custom(c));
@@ -457,6 +628,7 @@ enum_mapping! {
         // Preauthorized and ACH
         PreauthorizedDraftCredit(155),
         ItemInPacDeposit(156),
+        RealTimePaymentCredit(158),
         CorporateTradePaymentCredit(164),
         PreauthorizedAchCredit(165),
         AchSettlement(166),
@@ -604,6 +776,7 @@ enum_mapping! {
         AchDebitRecieved(451),
         ItemInAchDisbursementOrDebit(452),
         PreauthorizedAchDebit(455),
+        RealTimePaymentDebit(458),
         AccountHolderInitiatedAchDebit(462),
         CorporateTradePaymentDebit(464),
         AchSettlement(466),
@@ -630,7 +803,7 @@ enum_mapping! {
         IndividualAutomaticTransferDebit(501),
         BondOperationsDebit(502),
         BookTransferDebit(506),
-        IndividualInternationalMoneyTransferDebits(507),
+        IndividualInternationalMoneyTransferDebits(508),
         LetterOfCreditDebit(512),
         LetterOfCredit(513),
         ForeignExchangeDebit(514),
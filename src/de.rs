@@ -0,0 +1,771 @@
+//! Deserializing files straight into your own types with serde, record by record, without
+//! building a `data::File` first.
+//!
+//! A file is a map of its header's fields, then `groups`, then its trailer's fields. A group is
+//! likewise a map of its header's fields, `accounts` and its trailer's fields, and an account a
+//! map of its identifier's fields, `transaction_details` and its trailer's fields. The fields
+//! are named as in `ast`, such as `sender_ident`, `as_of_date` and `customer_ref_num`, so
+//! structs only need the ones they use:
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! struct Statement {
+//!     sender_ident: String,
+//!     groups: Vec<Group>,
+//! }
+//! #[derive(Deserialize)]
+//! struct Group {
+//!     as_of_date: NaiveDate,
+//!     accounts: Vec<Account>,
+//! }
+//! #[derive(Deserialize)]
+//! struct Account {
+//!     customer_account_num: String,
+//!     transaction_details: Vec<Transaction>,
+//! }
+//! #[derive(Deserialize)]
+//! struct Transaction {
+//!     type_code: u16,
+//!     amount: Option<i64>,
+//!     text: Option<Vec<String>>,
+//! }
+//!
+//! let statement: Statement = baimax::from_slice(bytes)?;
+//! ```
+//!
+//! Dates are ISO 8601 strings, with an end-of-day time written as `24:00:00`. Amounts are
+//! integers in the minor units of their currency, and type codes are plain numbers. Funds types
+//! are maps with their letter or digit under `code`, along with their fields. Each record is
+//! checked as `ast::convert` checks it, and control totals and record counts against the trailers
+//! as they're reached.
+
+use std::error;
+use std::fmt;
+use std::vec;
+
+use penny::Currency;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+
+use ast::{self, ParsedRecord};
+use ast::convert::{self, AccountConvError, ConvertError, ConverterProgress, FileConvError,
+                   GroupConvError};
+use ast::parse::Parsed;
+use data::{BaiDateOrTime, FileProcessError, Version};
+use parse::{self, Position};
+
+/// Deserializes a `T` from a whole file, with nothing after its trailer.
+pub fn from_slice<'de, T: de::Deserialize<'de>>(input: &'de [u8]) -> Result<T, Error> {
+    let mut de = Deserializer::from_slice(input);
+    let value = T::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The file couldn't be read, or its trailers didn't match.
+    Process(FileProcessError),
+    /// The file didn't fit the type it was being deserialized into.
+    Message(String),
+    /// There were more records after the file trailer.
+    TrailingRecords(Position),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Process(ref e) => e.fmt(f),
+            Error::Message(ref msg) => f.write_str(msg),
+            Error::TrailingRecords(p) => write!(f, "{}: records after the file trailer", p),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            Error::Process(ref e) => Some(e),
+            Error::Message(_) | Error::TrailingRecords(_) => None,
+        }
+    }
+}
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+pub struct Deserializer<'de> {
+    input: &'de [u8],
+    records: parse::Records<'de>,
+    peeked: Option<(Position, ParsedRecord<'de>, usize)>,
+    position: Position,
+    records_num: usize,
+    version: Version,
+    groups_num: usize,
+    group_records_start: usize,
+    group_control_total: i64,
+    group_currency: Currency,
+    accounts_num: usize,
+    account_records_start: usize,
+    account_control_total: i64,
+    account_currency: Currency,
+    transaction_details_num: usize,
+    file_control_total: i64,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn from_slice(input: &'de [u8]) -> Self {
+        Deserializer {
+            input,
+            records: {
+                // Missing record separators are reported by `peek`, where they're found.
+                let mut records = parse::Records::new(input);
+                records.set_lenient(true);
+                records
+            },
+            peeked: None,
+            position: Position::start(),
+            records_num: 0,
+            version: Version::Bai2,
+            groups_num: 0,
+            group_records_start: 0,
+            group_control_total: 0,
+            group_currency: Currency::USD,
+            accounts_num: 0,
+            account_records_start: 0,
+            account_control_total: 0,
+            account_currency: Currency::USD,
+            transaction_details_num: 0,
+            file_control_total: 0,
+        }
+    }
+
+    /// Checks that the input ends after the file trailer. Call this after deserializing a file
+    /// to reject anything following it.
+    pub fn end(&mut self) -> Result<(), Error> {
+        match self.peek()? {
+            Some(_) => {
+                let position = self.peeked.as_ref().map_or(self.position, |p| p.0);
+                Err(Error::TrailingRecords(position))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&ParsedRecord<'de>>, Error> {
+        if self.peeked.is_none() {
+            let (position, raw, continuations) = match self.records.next() {
+                None => return Ok(None),
+                Some(Err((position, kind))) => {
+                    return Err(Error::Process(FileProcessError::Parse(position, kind)));
+                }
+                Some(Ok(record)) => record,
+            };
+            let consumed = &self.input[position.offset..self.records.position().offset];
+            if let Some(end) = parse::missing_record_sep(consumed, &raw) {
+                let position = position.advance(&consumed[..end]);
+                return Err(Error::Process(FileProcessError::MissingRecordSep(position)));
+            }
+            let parsed = ast::Record::parse(&raw).map_err(|e| {
+                let position = ast::Record::locate(&raw, &e).map_or(position, |field| {
+                    position.locate(&self.input[position.offset..], field)
+                });
+                Error::Process(FileProcessError::FieldParse(position, e.at(position)))
+            })?;
+            self.peeked = Some((position, parsed, continuations));
+        }
+        Ok(self.peeked.as_ref().map(|(_, record, _)| record))
+    }
+
+    fn next(&mut self) -> Result<ParsedRecord<'de>, Error> {
+        self.peek()?;
+        match self.peeked.take() {
+            Some((position, record, continuations)) => {
+                self.position = position;
+                self.records_num += 1 + continuations;
+                Ok(record)
+            }
+            None => {
+                Err(Error::Process(
+                    FileProcessError::UnfinishedConversion(self.records.position()),
+                ))
+            }
+        }
+    }
+
+    fn error(&self, err: ConvertError) -> Error {
+        Error::Process(FileProcessError::Conversion(self.position, err.at(self.position)))
+    }
+
+    fn unexpected(&mut self, progress: ConverterProgress) -> Error {
+        // Report the record that was peeked at, not the last one read.
+        if let Some((position, _, _)) = self.peeked.take() {
+            self.position = position;
+        }
+        let record = self.records_num;
+        self.error(ConvertError::RecordType {
+            record,
+            progress,
+            position: None,
+        })
+    }
+
+    /// Reads the trailer closing `kind`, checking it against what was read since its header.
+    fn trailer(&mut self, kind: Kind) -> Result<Fields<'de>, Error> {
+        let record = self.next()?;
+        match (kind, record) {
+            (Kind::File, ParsedRecord::FileTrailer(t)) => {
+                let file_error = |err| ConvertError::File(err, None);
+                if t.control_total != self.file_control_total {
+                    return Err(self.error(file_error(FileConvError::ControlTotal {
+                        expected: t.control_total,
+                        actual: self.file_control_total,
+                    })));
+                }
+                if t.groups_num != self.groups_num {
+                    return Err(self.error(file_error(FileConvError::GroupsNum {
+                        expected: t.groups_num,
+                        actual: self.groups_num,
+                    })));
+                }
+                if t.records_num != self.records_num {
+                    return Err(self.error(file_error(FileConvError::RecordsNum {
+                        expected: t.records_num,
+                        actual: self.records_num,
+                    })));
+                }
+                Ok(vec![
+                    ("control_total", Value::I64(t.control_total)),
+                    ("groups_num", Value::U64(t.groups_num as u64)),
+                    ("records_num", Value::U64(t.records_num as u64)),
+                ])
+            }
+            (Kind::Group, ParsedRecord::GroupTrailer(t)) => {
+                let group = self.groups_num - 1;
+                let group_error = |err| ConvertError::Group { group, err, position: None };
+                let records_num = self.records_num - self.group_records_start;
+                if t.control_total != self.group_control_total {
+                    return Err(self.error(group_error(GroupConvError::ControlTotal {
+                        expected: t.control_total,
+                        actual: self.group_control_total,
+                    })));
+                }
+                if t.accounts_num != self.accounts_num {
+                    return Err(self.error(group_error(GroupConvError::AccountsNum {
+                        expected: t.accounts_num,
+                        actual: self.accounts_num,
+                    })));
+                }
+                if t.records_num != records_num {
+                    return Err(self.error(group_error(GroupConvError::RecordsNum {
+                        expected: t.records_num,
+                        actual: records_num,
+                    })));
+                }
+                self.file_control_total += self.group_control_total;
+                Ok(vec![
+                    ("control_total", Value::I64(t.control_total)),
+                    ("accounts_num", Value::U64(t.accounts_num as u64)),
+                    ("records_num", Value::U64(t.records_num as u64)),
+                ])
+            }
+            (Kind::Account, ParsedRecord::AccountTrailer(t)) => {
+                let (group, account) = (self.groups_num - 1, self.accounts_num - 1);
+                let account_error = |err| ConvertError::Account {
+                    group,
+                    account,
+                    err,
+                    position: None,
+                };
+                let records_num = self.records_num - self.account_records_start;
+                if t.control_total != self.account_control_total {
+                    return Err(self.error(account_error(AccountConvError::ControlTotal {
+                        expected: t.control_total,
+                        actual: self.account_control_total,
+                    })));
+                }
+                if t.records_num != records_num {
+                    return Err(self.error(account_error(AccountConvError::RecordsNum {
+                        expected: t.records_num,
+                        actual: records_num,
+                    })));
+                }
+                self.group_control_total += self.account_control_total;
+                Ok(vec![
+                    ("control_total", Value::I64(t.control_total)),
+                    ("records_num", Value::U64(t.records_num as u64)),
+                ])
+            }
+            (kind, _) => {
+                let record = self.records_num - 1;
+                let progress = kind.progress();
+                Err(self.error(ConvertError::RecordType {
+                    record,
+                    progress,
+                    position: None,
+                }))
+            }
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.peek()?.is_none() {
+            return Err(Error::Process(FileProcessError::UnfinishedConversion(self.position)));
+        }
+        match self.next()? {
+            ParsedRecord::FileHeader(h) => {
+                let file = h.convert().map_err(|err| self.error(ConvertError::File(err, None)))?;
+                self.version = file.version;
+                let (creation_date, creation_time) = date_time(file.creation.into());
+                let fields = vec![
+                    ("sender_ident", Value::Str(h.sender_ident)),
+                    ("receiver_ident", Value::Str(h.receiver_ident)),
+                    ("creation_date", creation_date),
+                    ("creation_time", creation_time),
+                    ("ident_num", Value::U64(h.ident_num.into())),
+                    ("physical_record_len", opt(h.physical_record_len.map(u64::from))),
+                    ("block_size", opt(h.block_size.map(u64::from))),
+                    ("version_number", Value::U64(h.version_number.into())),
+                ];
+                visitor.visit_map(Level::new(self, Kind::File, fields))
+            }
+            _ => {
+                self.records_num = 0;
+                Err(self.error(ConvertError::RecordType {
+                    record: 0,
+                    progress: ConverterProgress::Fresh,
+                    position: None,
+                }))
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+type Fields<'de> = Vec<(&'de str, Value<'de>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    File,
+    Group,
+    Account,
+}
+
+impl Kind {
+    fn children(self) -> &'static str {
+        match self {
+            Kind::File => "groups",
+            Kind::Group => "accounts",
+            Kind::Account => "transaction_details",
+        }
+    }
+
+    fn progress(self) -> ConverterProgress {
+        match self {
+            Kind::File => ConverterProgress::File,
+            Kind::Group => ConverterProgress::Group,
+            Kind::Account => ConverterProgress::Account,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Children,
+    ChildrenDone,
+    Trailer,
+}
+
+/// A file, group or account, as a map of its header's fields, its children and its trailer's
+/// fields.
+struct Level<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    kind: Kind,
+    fields: vec::IntoIter<(&'de str, Value<'de>)>,
+    value: Option<Value<'de>>,
+    state: State,
+}
+
+impl<'a, 'de> Level<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, kind: Kind, fields: Fields<'de>) -> Self {
+        Level {
+            de,
+            kind,
+            fields: fields.into_iter(),
+            value: None,
+            state: State::Header,
+        }
+    }
+}
+
+impl<'a, 'de> de::Deserializer<'de> for Level<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+impl<'a, 'de> MapAccess<'de> for Level<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        loop {
+            match self.state {
+                State::Header | State::Trailer => {
+                    if let Some((key, value)) = self.fields.next() {
+                        self.value = Some(value);
+                        return seed.deserialize(key.into_deserializer()).map(Some);
+                    }
+                    if self.state == State::Trailer {
+                        return Ok(None);
+                    }
+                    self.state = State::Children;
+                    return seed.deserialize(self.kind.children().into_deserializer()).map(Some);
+                }
+                State::Children => {
+                    return Err(de::Error::custom(
+                        format_args!("{} skipped without being deserialized", self.kind.children()),
+                    ));
+                }
+                State::ChildrenDone => {
+                    self.fields = self.de.trailer(self.kind)?.into_iter();
+                    self.state = State::Trailer;
+                }
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => {
+                self.state = State::ChildrenDone;
+                seed.deserialize(Children {
+                    de: &mut *self.de,
+                    kind: self.kind,
+                })
+            }
+        }
+    }
+}
+
+/// The groups of a file, the accounts of a group or the transaction details of an account, as a
+/// sequence.
+struct Children<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    kind: Kind,
+}
+
+impl<'a, 'de> de::Deserializer<'de> for Children<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+impl<'a, 'de> SeqAccess<'de> for Children<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let field = match self.de.peek()? {
+            Some(record) => record.field(),
+            None => return Err(Error::Process(
+                FileProcessError::UnfinishedConversion(self.de.records.position()),
+            )),
+        };
+        match (self.kind, field) {
+            (Kind::File, ast::RecordField::FileTrailer) |
+            (Kind::Group, ast::RecordField::GroupTrailer) |
+            (Kind::Account, ast::RecordField::AccountTrailer) => Ok(None),
+            (Kind::File, ast::RecordField::GroupHeader) => {
+                let de = &mut *self.de;
+                let records_start = de.records_num;
+                let h = match de.next()? {
+                    ParsedRecord::GroupHeader(h) => h,
+                    _ => unreachable!(),
+                };
+                let group = de.groups_num;
+                let converted = h.convert(de.version, &mut Err).map_err(|err| {
+                    de.error(ConvertError::Group {
+                        group,
+                        err,
+                        position: None,
+                    })
+                })?;
+                de.group_currency = converted.currency_def();
+                let (as_of_date, as_of_time) = date_time(converted.as_of);
+                de.groups_num += 1;
+                de.group_records_start = records_start;
+                de.group_control_total = 0;
+                de.accounts_num = 0;
+                let fields = vec![
+                    ("ultimate_receiver_ident", opt(h.ultimate_receiver_ident.map(Value::Str))),
+                    ("originator_ident", opt(h.originator_ident.map(Value::Str))),
+                    ("status", Value::U64(h.status.into())),
+                    ("as_of_date", as_of_date),
+                    ("as_of_time", as_of_time),
+                    ("currency", opt(h.currency.map(Value::Str))),
+                    ("as_of_date_mod", opt(h.as_of_date_mod.map(u64::from))),
+                ];
+                seed.deserialize(Level::new(de, Kind::Group, fields)).map(Some)
+            }
+            (Kind::Group, ast::RecordField::AccountIdent) => {
+                let de = &mut *self.de;
+                let records_start = de.records_num;
+                let a = match de.next()? {
+                    ParsedRecord::AccountIdent(a) => a,
+                    _ => unreachable!(),
+                };
+                let (group, account) = (de.groups_num - 1, de.accounts_num);
+                let (converted, control_total) = a.convert(
+                    de.group_currency,
+                    de.version,
+                    None,
+                    &mut Err,
+                ).map_err(|err| {
+                    de.error(ConvertError::Account {
+                        group,
+                        account,
+                        err,
+                        position: None,
+                    })
+                })?;
+                de.account_currency = converted.currency_def(de.group_currency);
+                de.accounts_num += 1;
+                de.account_records_start = records_start;
+                de.account_control_total = control_total;
+                de.transaction_details_num = 0;
+                let infos = a.infos
+                    .into_iter()
+                    .filter(|i| {
+                        i.type_code.is_some() || i.amount.is_some() || i.item_count.is_some() ||
+                            i.funds_type.is_some()
+                    })
+                    .map(|i| {
+                        Value::Map(vec![
+                            ("type_code", opt(i.type_code.map(u64::from))),
+                            ("amount", opt(i.amount.map(Value::I64))),
+                            ("item_count", opt(i.item_count.map(u64::from))),
+                            ("funds_type", opt(i.funds_type.map(funds))),
+                        ])
+                    })
+                    .collect();
+                let fields = vec![
+                    ("customer_account_num", Value::Str(a.customer_account_num)),
+                    ("currency", opt(a.currency.map(Value::Str))),
+                    ("infos", Value::Seq(infos)),
+                ];
+                seed.deserialize(Level::new(de, Kind::Account, fields)).map(Some)
+            }
+            (Kind::Account, ast::RecordField::TransactionDetail) => {
+                let de = &mut *self.de;
+                let td = match de.next()? {
+                    ParsedRecord::TransactionDetail(td) => td,
+                    _ => unreachable!(),
+                };
+                let (group, account, transaction) =
+                    (de.groups_num - 1, de.accounts_num - 1, de.transaction_details_num);
+                let (_, control_total) = td.clone()
+                    .convert(de.account_currency, de.version, None, &mut Err)
+                    .map_err(|err| {
+                        de.error(ConvertError::TransactionDetail {
+                            group,
+                            account,
+                            transaction,
+                            err,
+                            position: None,
+                        })
+                    })?;
+                de.account_control_total += control_total;
+                de.transaction_details_num += 1;
+                let fields = vec![
+                    ("type_code", Value::U64(td.type_code.into())),
+                    ("amount", opt(td.amount.map(Value::I64))),
+                    ("funds_type", opt(td.funds_type.map(funds))),
+                    ("bank_ref_num", opt(td.bank_ref_num.map(Value::Str))),
+                    ("customer_ref_num", opt(td.customer_ref_num.map(Value::Str))),
+                    (
+                        "text",
+                        opt(td.text.map(|text| {
+                            Value::Seq(text.into_iter().map(Value::Str).collect())
+                        })),
+                    ),
+                ];
+                seed.deserialize(Value::Map(fields)).map(Some)
+            }
+            (kind, _) => Err(self.de.unexpected(kind.progress())),
+        }
+    }
+}
+
+/// A field's value.
+#[derive(Debug, Clone)]
+enum Value<'de> {
+    Null,
+    Str(&'de str),
+    String(String),
+    I64(i64),
+    U64(u64),
+    Seq(Vec<Value<'de>>),
+    Map(Fields<'de>),
+}
+
+impl<'de> From<u64> for Value<'de> {
+    fn from(n: u64) -> Self {
+        Value::U64(n)
+    }
+}
+
+fn opt<'de, T: Into<Value<'de>>>(value: Option<T>) -> Value<'de> {
+    value.map_or(Value::Null, Into::into)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Str(s) => visitor.visit_borrowed_str(s),
+            Value::String(s) => visitor.visit_string(s),
+            Value::I64(n) => visitor.visit_i64(n),
+            Value::U64(n) => visitor.visit_u64(n),
+            Value::Seq(values) => {
+                let mut seq = SeqDeserializer::new(values.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(fields) => {
+                let mut map = MapDeserializer::new(fields.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+fn funds<'de>(funds: ast::ParsedFundsType) -> Value<'de> {
+    use ast::ParsedFundsType as PFT;
+
+    let (code, mut fields): (&str, Fields) = match funds {
+        PFT::Unknown => ("Z", Vec::new()),
+        PFT::ImmediateAvail => ("0", Vec::new()),
+        PFT::OneDayAvail => ("1", Vec::new()),
+        PFT::TwoOrMoreDaysAvail => ("2", Vec::new()),
+        PFT::DistributedAvailS {
+            immediate,
+            one_day,
+            more_than_one_day,
+        } => {
+            (
+                "S",
+                vec![
+                    ("immediate", opt(immediate.map(Value::I64))),
+                    ("one_day", opt(one_day.map(Value::I64))),
+                    ("more_than_one_day", opt(more_than_one_day.map(Value::I64))),
+                ],
+            )
+        }
+        PFT::ValueDated { date: d, time: t } => {
+            (
+                "V",
+                vec![
+                    ("date", Value::String(date(&d))),
+                    ("time", opt(t.as_ref().map(|t| Value::String(time(t))))),
+                ],
+            )
+        }
+        PFT::DistributedAvailD { num, dists } => {
+            let dists = dists
+                .into_iter()
+                .map(|dist| {
+                    Value::Map(vec![
+                        ("days", Value::U64(dist.days.into())),
+                        ("amount", Value::I64(dist.amount)),
+                    ])
+                })
+                .collect();
+            ("D", vec![("num", Value::U64(num as u64)), ("dists", Value::Seq(dists))])
+        }
+    };
+    fields.insert(0, ("code", Value::Str(code)));
+    Value::Map(fields)
+}
+
+/// A date already checked by `ast::convert`, which decides its century.
+fn date(date: &ast::Date) -> String {
+    convert::chrono_date(date).expect("date checked by conversion").to_string()
+}
+
+/// The date and optional time fields of a converted date and time.
+fn date_time<'de>(date_time: BaiDateOrTime) -> (Value<'de>, Value<'de>) {
+    let (date, time) = match date_time {
+        BaiDateOrTime::Date(date) => (date, None),
+        BaiDateOrTime::DateTime(dt) => (dt.date(), Some(dt.format("%H:%M:%S").to_string())),
+        BaiDateOrTime::DateEndOfDay(date) => (date, Some("24:00:00".to_owned())),
+    };
+    (Value::String(date.to_string()), opt(time.map(Value::String)))
+}
+
+fn time(time: &ast::Time) -> String {
+    match *time {
+        ast::Time {
+            hour: 99,
+            minute: 99,
+        } => "24:00:00".to_owned(),
+        ast::Time { hour, minute } => format!("{:02}:{:02}:00", hour, minute),
+    }
+}
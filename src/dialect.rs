@@ -0,0 +1,192 @@
+//! Profiles of the ways some banks' files deviate from the spec.
+
+use std::collections::HashMap;
+use std::str;
+use std::sync::Arc;
+
+use ast::convert::ConvertError;
+use data::TypeCodeRegistry;
+
+/// The deviations from the spec to expect in a file. Each one is off unless a profile turns it
+/// on, so implementations only need the methods for their own quirks.
+pub trait Dialect: Send + Sync {
+    /// Records that end without a record separator (`/`).
+    fn missing_record_sep(&self) -> bool {
+        false
+    }
+
+    /// Records with a field separator left before the record separator, as in `...,2,/`.
+    fn trailing_field_sep(&self) -> bool {
+        false
+    }
+
+    /// Dates with four-digit years (YYYYMMDD). Only the last two digits are kept, so centuries
+    /// are still inferred.
+    fn four_digit_year(&self) -> bool {
+        false
+    }
+
+    /// Group headers with spaces for an as-of time instead of leaving it out.
+    fn blank_as_of_time(&self) -> bool {
+        false
+    }
+
+    /// Transaction detail text that starts with a `/`, such as `/BNF/...`, which would otherwise
+    /// be read as the end of the record.
+    fn slash_in_text(&self) -> bool {
+        false
+    }
+
+    /// Transaction details without a customer reference number field, so their text follows the
+    /// bank reference number.
+    fn no_customer_ref(&self) -> bool {
+        false
+    }
+
+    /// Whether `err` should only be a warning, even when processing strictly. This is asked about
+    /// header and transaction detail errors as well as mismatched trailers.
+    fn tolerate(&self, _err: &ConvertError) -> bool {
+        false
+    }
+
+    /// The bank's own type codes, which take precedence over the spec's.
+    fn type_codes(&self) -> Option<&TypeCodeRegistry> {
+        None
+    }
+}
+
+/// Files as the spec describes them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Spec;
+
+impl Dialect for Spec {}
+
+/// A profile that turns on any of the built-in deviations, starting from none of them with
+/// `Quirks::default()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Quirks {
+    pub missing_record_sep: bool,
+    pub trailing_field_sep: bool,
+    pub four_digit_year: bool,
+    pub blank_as_of_time: bool,
+    pub slash_in_text: bool,
+    pub no_customer_ref: bool,
+}
+
+impl Dialect for Quirks {
+    fn missing_record_sep(&self) -> bool {
+        self.missing_record_sep
+    }
+
+    fn trailing_field_sep(&self) -> bool {
+        self.trailing_field_sep
+    }
+
+    fn four_digit_year(&self) -> bool {
+        self.four_digit_year
+    }
+
+    fn blank_as_of_time(&self) -> bool {
+        self.blank_as_of_time
+    }
+
+    fn slash_in_text(&self) -> bool {
+        self.slash_in_text
+    }
+
+    fn no_customer_ref(&self) -> bool {
+        self.no_customer_ref
+    }
+}
+
+/// A bank's quirks along with the type codes it defines.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Profile {
+    pub quirks: Quirks,
+    pub type_codes: TypeCodeRegistry,
+}
+
+impl Dialect for Profile {
+    fn missing_record_sep(&self) -> bool {
+        self.quirks.missing_record_sep
+    }
+
+    fn trailing_field_sep(&self) -> bool {
+        self.quirks.trailing_field_sep
+    }
+
+    fn four_digit_year(&self) -> bool {
+        self.quirks.four_digit_year
+    }
+
+    fn blank_as_of_time(&self) -> bool {
+        self.quirks.blank_as_of_time
+    }
+
+    fn slash_in_text(&self) -> bool {
+        self.quirks.slash_in_text
+    }
+
+    fn no_customer_ref(&self) -> bool {
+        self.quirks.no_customer_ref
+    }
+
+    fn type_codes(&self) -> Option<&TypeCodeRegistry> {
+        Some(&self.type_codes)
+    }
+}
+
+/// Dialects registered by the sender ident in the file header, with a default for other
+/// senders.
+#[derive(Clone)]
+pub struct Dialects {
+    default: Arc<Dialect>,
+    senders: HashMap<String, Arc<Dialect>>,
+}
+
+impl Default for Dialects {
+    fn default() -> Self {
+        Dialects::new(Spec)
+    }
+}
+
+impl Dialects {
+    pub fn new<D: Dialect + 'static>(default: D) -> Self {
+        Dialects {
+            default: Arc::new(default),
+            senders: HashMap::new(),
+        }
+    }
+
+    /// Expects `dialect` in files from `sender_ident`.
+    pub fn register<D: Dialect + 'static>(&mut self, sender_ident: &str, dialect: D) {
+        self.senders.insert(sender_ident.to_owned(), Arc::new(dialect));
+    }
+
+    /// The dialect to expect in files from `sender_ident`.
+    pub fn get(&self, sender_ident: &str) -> Arc<Dialect> {
+        self.senders.get(sender_ident).unwrap_or(&self.default).clone()
+    }
+
+    /// The dialect to expect in `file`, going by the sender ident in its file header.
+    pub fn for_file(&self, file: &[u8]) -> Arc<Dialect> {
+        match sender_ident(file) {
+            Some(sender_ident) => self.get(sender_ident),
+            None => self.default.clone(),
+        }
+    }
+}
+
+/// Reads the sender ident field out of the file header at the start of `file`.
+pub fn sender_ident(file: &[u8]) -> Option<&str> {
+    if !file.starts_with(b"01,") {
+        return None;
+    }
+    file[3..]
+        .split(|&c| c == b',' || c == b'/' || c == b'\n')
+        .next()
+        .and_then(|ident| str::from_utf8(ident).ok())
+        .map(str::trim)
+}
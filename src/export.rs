@@ -0,0 +1,360 @@
+//! A stable schema for exporting files to JSON and YAML, for consumers outside Rust.
+//!
+//! The serde derives on `data` follow the shape of its types, which can change between
+//! releases. The types here don't: fields are only ever added, and anything else bumps
+//! `FORMAT_VERSION`, which every export carries. `resources/export-schema.json` is the JSON
+//! Schema for the current version.
+//!
+//! Type codes carry their number along with the spec's description, level and direction.
+//! Dates and times are ISO 8601, with an end-of-day time written as `24:00:00`. Amounts are
+//! decimal strings in whole units of their currency, so they survive consumers that read JSON
+//! numbers as floats.
+
+use std::convert::TryFrom;
+#[cfg(any(feature = "json", feature = "yaml"))]
+use std::io;
+
+use chrono::{NaiveDate, NaiveDateTime};
+#[cfg(feature = "json")]
+use serde_json;
+#[cfg(feature = "yaml")]
+use serde_yaml;
+
+use data::{self, BaiDateOrTime, BaiDateTime};
+
+/// The version of the schema, in the `format_version` field of every export.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct File {
+    pub format_version: u32,
+    pub sender: String,
+    pub receiver: String,
+    pub created: String,
+    pub file_ident: u32,
+    /// "BAI2" or "BTRS3".
+    pub version: &'static str,
+    pub groups: Vec<Group>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+    pub ultimate_receiver: Option<String>,
+    pub originator: Option<String>,
+    pub status: Coded,
+    pub as_of: String,
+    pub as_of_modifier: Option<Coded>,
+    pub currency: String,
+    pub accounts: Vec<Account>,
+}
+
+/// A numbered field value along with its name, such as a group status.
+#[derive(Debug, Clone, Serialize)]
+pub struct Coded {
+    pub code: u8,
+    pub name: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Account {
+    pub number: String,
+    pub currency: String,
+    pub infos: Vec<AccountInfo>,
+    pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountInfo {
+    pub code: TypeCode,
+    pub amount: Option<Amount>,
+    /// The amount, negative for debit summaries. Status amounts are already signed.
+    pub signed_amount: Option<Amount>,
+    pub item_count: Option<u32>,
+    pub funds: Option<Funds>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transaction {
+    pub code: TypeCode,
+    pub amount: Option<Amount>,
+    /// The amount, negative for debits, or null if it's neither a credit nor a debit.
+    pub signed_amount: Option<Amount>,
+    pub funds: Option<Funds>,
+    pub bank_reference: Option<String>,
+    pub customer_reference: Option<String>,
+    pub text: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeCode {
+    pub code: u16,
+    /// The description from the spec or the registry the code was converted with, or null for
+    /// codes neither lists.
+    pub name: Option<&'static str>,
+    /// "status", "summary" or "detail".
+    pub level: &'static str,
+    /// "credit", "debit" or "non_monetary".
+    pub direction: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Amount {
+    pub value: String,
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Funds {
+    Unknown,
+    Immediate,
+    OneDay,
+    TwoOrMoreDays,
+    Distributed {
+        immediate: Option<Amount>,
+        one_day: Option<Amount>,
+        two_or_more_days: Option<Amount>,
+    },
+    ValueDated { available: String },
+    DistributedByDays { distributions: Vec<Distribution> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Distribution {
+    pub days: u32,
+    pub amount: Amount,
+}
+
+/// Files convert unless a debit's amount is `i64::MIN`, which can't be signed.
+impl<'a> TryFrom<&'a data::File> for File {
+    type Error = data::AmountOverflow;
+    fn try_from(file: &data::File) -> Result<File, data::AmountOverflow> {
+        Ok(File {
+            format_version: FORMAT_VERSION,
+            sender: file.sender.0.clone(),
+            receiver: file.receiver.0.clone(),
+            created: date_time(&file.creation),
+            file_ident: file.ident.0,
+            version: match file.version {
+                data::Version::Bai2 => "BAI2",
+                data::Version::Btrs3 => "BTRS3",
+            },
+            groups: file.groups.iter().map(Group::try_from).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a data::Group> for Group {
+    type Error = data::AmountOverflow;
+    fn try_from(group: &data::Group) -> Result<Group, data::AmountOverflow> {
+        use data::AsOfDateModifier as AODM;
+        use data::GroupStatus as GS;
+
+        let group_cur = group.currency_def();
+        Ok(Group {
+            ultimate_receiver: group.ultimate_receiver.as_ref().map(|p| p.0.clone()),
+            originator: group.originator.as_ref().map(|p| p.0.clone()),
+            status: Coded {
+                code: group.status.into(),
+                name: match group.status {
+                    GS::Update => "update",
+                    GS::Deletion => "deletion",
+                    GS::Correction => "correction",
+                    GS::TestOnly => "test_only",
+                },
+            },
+            as_of: date_or_time(&group.as_of),
+            as_of_modifier: group.as_of_date_mod.map(|m| {
+                Coded {
+                    code: m.into(),
+                    name: match m {
+                        AODM::InterimPrevious => "interim_previous_day",
+                        AODM::FinalPrevious => "final_previous_day",
+                        AODM::InterimSame => "interim_same_day",
+                        AODM::FinalSame => "final_same_day",
+                    },
+                }
+            }),
+            currency: group_cur.to_string(),
+            accounts: group
+                .accounts
+                .iter()
+                .map(|account| Account::new(account, group_cur))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl Account {
+    fn new(
+        account: &data::Account,
+        group_cur: ::penny::Currency,
+    ) -> Result<Account, data::AmountOverflow> {
+        Ok(Account {
+            number: account.customer_account.0.clone(),
+            currency: account.currency_def(group_cur).to_string(),
+            infos: account
+                .infos
+                .iter()
+                .map(AccountInfo::try_from)
+                .collect::<Result<_, _>>()?,
+            transactions: account
+                .transaction_details
+                .iter()
+                .map(Transaction::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a data::AccountInfo> for AccountInfo {
+    type Error = data::AmountOverflow;
+    fn try_from(info: &data::AccountInfo) -> Result<AccountInfo, data::AmountOverflow> {
+        let signed_amount = info.signed_amount()?.map(Amount::from);
+        Ok(match *info {
+            data::AccountInfo::Status { code, amount } => {
+                AccountInfo {
+                    code: TypeCode::new(data::TypeCode::Status(code)),
+                    amount: amount.map(Amount::from),
+                    signed_amount,
+                    item_count: None,
+                    funds: None,
+                }
+            }
+            data::AccountInfo::Summary {
+                code,
+                amount,
+                item_count,
+                ref funds,
+            } => {
+                AccountInfo {
+                    code: TypeCode::new(data::TypeCode::Summary(code)),
+                    amount: amount.map(Amount::from),
+                    signed_amount,
+                    item_count,
+                    funds: funds.as_ref().map(Funds::from),
+                }
+            }
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a data::TransactionDetail> for Transaction {
+    type Error = data::AmountOverflow;
+    fn try_from(detail: &data::TransactionDetail) -> Result<Transaction, data::AmountOverflow> {
+        Ok(Transaction {
+            code: TypeCode::new(data::TypeCode::Detail(detail.code)),
+            amount: detail.amount.map(Amount::from),
+            signed_amount: detail.signed_amount()?.map(Amount::from),
+            funds: detail.funds.as_ref().map(Funds::from),
+            bank_reference: detail.bank_ref_num.as_ref().map(|r| r.0.clone()),
+            customer_reference: detail.customer_ref_num.as_ref().map(|r| r.0.clone()),
+            text: detail.text.clone().unwrap_or_default(),
+        })
+    }
+}
+
+impl TypeCode {
+    fn new(code: data::TypeCode) -> TypeCode {
+        let (level, direction) = match code {
+            data::TypeCode::Status(_) => ("status", data::Direction::NonMonetary),
+            data::TypeCode::Summary(c) => ("summary", c.direction()),
+            data::TypeCode::Detail(c) => ("detail", c.direction()),
+        };
+        TypeCode {
+            code: code.into(),
+            name: code.info().map(|info| info.description),
+            level,
+            direction: match direction {
+                data::Direction::Credit => "credit",
+                data::Direction::Debit => "debit",
+                data::Direction::NonMonetary => "non_monetary",
+            },
+        }
+    }
+}
+
+impl From<data::Amount> for Amount {
+    fn from(amount: data::Amount) -> Amount {
+        Amount {
+            value: amount.to_decimal(),
+            currency: amount.currency.to_string(),
+        }
+    }
+}
+
+impl<'a> From<&'a data::FundsType> for Funds {
+    fn from(funds: &data::FundsType) -> Funds {
+        use data::FundsType as FT;
+
+        match *funds {
+            FT::Unknown => Funds::Unknown,
+            FT::ImmediateAvail => Funds::Immediate,
+            FT::OneDayAvail => Funds::OneDay,
+            FT::TwoOrMoreDaysAvail => Funds::TwoOrMoreDays,
+            FT::DistributedAvailS {
+                immediate,
+                one_day,
+                more_than_one_day,
+            } => {
+                Funds::Distributed {
+                    immediate: immediate.map(Amount::from),
+                    one_day: one_day.map(Amount::from),
+                    two_or_more_days: more_than_one_day.map(Amount::from),
+                }
+            }
+            FT::ValueDated(ref available) => {
+                Funds::ValueDated { available: date_or_time(available) }
+            }
+            FT::DistributedAvailD(ref dists) => {
+                Funds::DistributedByDays {
+                    distributions: dists
+                        .iter()
+                        .map(|dist| {
+                            Distribution {
+                                days: dist.days,
+                                amount: dist.amount.into(),
+                            }
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+}
+
+fn date(date: &NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn naive_date_time(date_time: &NaiveDateTime) -> String {
+    date_time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn date_time(date_time: &BaiDateTime) -> String {
+    match *date_time {
+        BaiDateTime::DateTime(ref dt) => naive_date_time(dt),
+        BaiDateTime::DateEndOfDay(ref d) => format!("{}T24:00:00", date(d)),
+    }
+}
+
+fn date_or_time(date_or_time: &BaiDateOrTime) -> String {
+    match *date_or_time {
+        BaiDateOrTime::Date(ref d) => date(d),
+        BaiDateOrTime::DateTime(ref dt) => naive_date_time(dt),
+        BaiDateOrTime::DateEndOfDay(ref d) => format!("{}T24:00:00", date(d)),
+    }
+}
+
+/// Writes `file` in the export schema as pretty-printed JSON.
+#[cfg(feature = "json")]
+pub fn write_json<W: io::Write>(file: &data::File, w: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(w, &File::try_from(file)?).map_err(io::Error::from)
+}
+
+/// Writes `file` in the export schema as YAML.
+#[cfg(feature = "yaml")]
+pub fn write_yaml<W: io::Write>(file: &data::File, w: W) -> io::Result<()> {
+    serde_yaml::to_writer(w, &File::try_from(file)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
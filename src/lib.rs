@@ -9,13 +9,24 @@ extern crate itertools;
 extern crate nom;
 extern crate penny;
 #[cfg(feature = "serde")]
+#[macro_use]
 extern crate serde;
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "serde_yaml")]
+extern crate serde_yaml;
 #[cfg(test)]
 extern crate test;
+#[cfg(feature = "toml")]
+extern crate toml;
+#[cfg(all(test, feature = "json"))]
+extern crate valico;
 extern crate void;
+#[cfg(feature = "camt")]
+extern crate xml;
 
 macro_rules! enum_mapping {
     ($(#[$attr:meta])* pub $name:ident($ty:ty) {
@@ -74,8 +85,27 @@ macro_rules! enum_mapping {
 }
 
 pub mod ast;
+pub mod build;
+#[cfg(feature = "camt")]
+pub mod camt;
+pub mod csv;
 pub mod data;
+#[cfg(feature = "serde-serialize")]
+pub mod de;
+pub mod dialect;
+#[cfg(feature = "serde-serialize")]
+pub mod export;
 pub mod parse;
+pub mod read;
+#[cfg(feature = "serde-serialize")]
+pub mod ser;
+pub mod swift;
+pub mod write;
+
+#[cfg(feature = "serde-serialize")]
+pub use de::from_slice;
+#[cfg(feature = "serde-serialize")]
+pub use ser::{to_vec, to_writer};
 
 #[cfg(test)]
 mod tests {
@@ -86,7 +116,7 @@ mod tests {
 
     macro_rules! benchmark_file {
         ($file_name:ident, $file_path:expr,
-         $process:ident, $parse:ident, $ast_parse:ident, $convert:ident) => {
+         $process:ident, $parse:ident, $ast_parse:ident, $convert:ident, $write:ident) => {
             static $file_name: &'static str = include_str!($file_path);
 
             #[bench]
@@ -126,20 +156,37 @@ mod tests {
             fn $convert(b: &mut Bencher) {
                 let bytes = $file_name.bytes().collect::<Vec<_>>();
 
-                let raw: Result<_, _> = parse::file(bytes.as_slice()).to_result();
+                let raw = parse::Records::new(bytes.as_slice())
+                    .map(|r| r.map(|(_, raw, continuations)| (raw, continuations)))
+                    .collect::<Result<Vec<_>, _>>();
+                let raw = raw.unwrap();
                 use ast::parse::Parsed;
-                let parsed =
-                    raw.map(|r| r.iter().map(|r| ast::Record::parse(r)) .collect::<Vec<_>>());
-                let parsed = parsed.unwrap();
+                let parsed = raw.iter()
+                    .map(|&(ref r, continuations)| {
+                        ast::Record::parse(r).map(|r| (r, continuations))
+                    })
+                    .collect::<Vec<_>>();
                 b.iter(|| {
                     let parsed = parsed.to_vec();
                     let mut converter = ast::convert::Converter::default();
-                    let result = parsed.into_iter().fold_results(None, |acc, r| {
-                        converter.process(r).expand().or(acc)
+                    let result = parsed.into_iter().fold_results(None, |acc, (r, continuations)| {
+                        converter.process_continued(r, continuations).expand().or(acc)
                     });
                     result.unwrap().unwrap()
                 })
             }
+
+            #[bench]
+            fn $write(b: &mut Bencher) {
+                let bytes = $file_name.bytes().collect::<Vec<_>>();
+
+                let file = data::File::process(bytes.as_slice()).unwrap();
+                b.iter(|| {
+                    let mut out = Vec::new();
+                    file.write_to(&mut out).unwrap();
+                    out
+                })
+            }
         };
     }
 
@@ -149,6 +196,1392 @@ mod tests {
         process_spec_example,
         parse_spec_example,
         ast_parse_spec_example,
-        convert_spec_example
+        convert_spec_example,
+        write_spec_example
     );
+
+    #[test]
+    fn round_trip_spec_example() {
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        file.write_to(&mut out).unwrap();
+        let round_tripped = data::File::process(&out).unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", round_tripped));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let text = vec![
+            "A".repeat(60),
+            "B".repeat(write::DEFAULT_TEXT_LINE_LEN),
+            "C D".to_owned(),
+        ];
+        file.groups[0].accounts[0].transaction_details[0].text = Some(text.clone());
+        let mut out = Vec::new();
+        file.write_to(&mut out).unwrap();
+        let round_tripped = data::File::process(&out).unwrap();
+        let detail = &round_tripped.groups[0].accounts[0].transaction_details[0];
+        assert_eq!(detail.text, Some(text));
+
+        let long = "E".repeat(write::DEFAULT_TEXT_LINE_LEN + 1);
+        file.groups[0].accounts[0].transaction_details[0].text = Some(vec![long.clone()]);
+        let mut out = Vec::new();
+        assert!(file.write_to(&mut out).is_err());
+        let wrapped = write::wrap_text(&format!("{} F G", long), write::DEFAULT_TEXT_LINE_LEN);
+        assert_eq!(wrapped.len(), 2);
+        assert!(wrapped.iter().all(|l| l.len() <= write::DEFAULT_TEXT_LINE_LEN));
+    }
+
+    #[test]
+    fn long_field_is_an_error() {
+        use std::io;
+
+        let mut file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let long = "1".repeat(usize::from(write::DEFAULT_PHYSICAL_RECORD_LEN) - 3);
+        file.groups[0].accounts[0].transaction_details[0].bank_ref_num =
+            Some(data::ReferenceNum(long.clone()));
+        let mut out = Vec::new();
+        let err = file.write_to(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut out = Vec::new();
+        write::Writer::with_physical_record_len(&mut out, None).write_file(&file).unwrap();
+        let round_tripped = data::File::process(&out).unwrap();
+        let detail = &round_tripped.groups[0].accounts[0].transaction_details[0];
+        assert_eq!(detail.bank_ref_num.as_ref().map(|r| &r.0), Some(&long));
+    }
+
+    #[test]
+    fn build_spec_example() {
+        use std::convert::TryFrom;
+        use chrono::NaiveDate;
+        use ast::convert::GroupConvError;
+        use build::{AccountBuilder, BuildError, FileBuilder, GroupBuilder, WriteError};
+        use data::{AsOfDateModifier, DetailCode, FundsType, GroupStatus, StatusCode};
+
+        let status = |code| StatusCode::try_from(code).unwrap();
+        let usd = |value| Some(data::Amount::new(value, penny::Currency::USD));
+        let group = || {
+            GroupBuilder::new(
+                GroupStatus::Update,
+                NaiveDate::from_ymd(2004, 6, 20).and_hms(23, 59, 0),
+            ).ultimate_receiver("031001234")
+                .originator("122099999")
+                .as_of_date_mod(AsOfDateModifier::FinalPrevious)
+        };
+        let account = AccountBuilder::new("0123456789")
+            .status(status(10), 4350000)
+            .status(status(40), 2830000)
+            .status(status(72), 1020000)
+            .status(status(74), 500000)
+            .detail(DetailCode::try_from(115).unwrap(), 450000)
+            .funds(FundsType::DistributedAvailS {
+                immediate: usd(100000),
+                one_day: usd(200000),
+                more_than_one_day: usd(150000),
+            });
+        let file = || {
+            FileBuilder::new(
+                "122099999",
+                "123456789",
+                NaiveDate::from_ymd(2004, 6, 21).and_hms(2, 0, 0),
+                1,
+            )
+        };
+
+        let mut out = Vec::new();
+        file().group(group().account(account.clone())).write_to(&mut out).unwrap();
+        let built = data::File::process(&out).unwrap();
+        let spec = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let (built, spec) = (&built.groups[0], &spec.groups[0]);
+        assert_eq!(format!("{}", built).lines().next(), format!("{}", spec).lines().next());
+        assert_eq!(format!("{:?}", built.accounts), format!("{:?}", &spec.accounts[..1]));
+        assert_eq!(built.control_total(), 9150000);
+        assert!(String::from_utf8(out).unwrap().contains("\n49,9150000,3/\n"));
+
+        let item_count = account.clone().status(status(15), 0).item_count(2);
+        match file().group(group().account(item_count)).build() {
+            Err(BuildError::Convert(ast::convert::ConvertError::Account {
+                err: ast::convert::AccountConvError::AccountInfo(4, e),
+                ..
+            })) => assert_eq!(e.to_string(), "status type codes can't have an item count"),
+            r => panic!("expected a status item count error, got {:?}", r),
+        }
+        let text = AccountBuilder::new("0123456789").text("LOCK BOX");
+        match file().group(group().account(text)).write_to(&mut Vec::new()) {
+            Err(WriteError::Build(BuildError::Misplaced { modifier, .. })) => {
+                assert_eq!(modifier, "text")
+            }
+            r => panic!("expected a misplaced modifier, got {:?}", r),
+        }
+
+        let btrs = || file().version(data::Version::Btrs3);
+        assert!(btrs().group(group()).build().is_ok());
+        let deletion = GroupBuilder::new(GroupStatus::Deletion, NaiveDate::from_ymd(2004, 6, 20));
+        match btrs().group(deletion).build() {
+            Err(BuildError::Convert(ast::convert::ConvertError::Group {
+                err: GroupConvError::RetiredStatus(GroupStatus::Deletion),
+                ..
+            })) => {}
+            r => panic!("expected a retired group status error, got {:?}", r),
+        }
+        let interim = group().as_of_date_mod(AsOfDateModifier::InterimPrevious);
+        match btrs().group(interim).build() {
+            Err(BuildError::Convert(ast::convert::ConvertError::Group {
+                err: GroupConvError::RetiredAsOfDateMod(AsOfDateModifier::InterimPrevious),
+                ..
+            })) => {}
+            r => panic!("expected a retired as-of date modifier error, got {:?}", r),
+        }
+    }
+
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    #[test]
+    fn export_spec_example() {
+        let eod = SPEC_EXAMPLE.replacen(",040621,0200,", ",040621,9999,", 1);
+        let file = data::File::process(eod.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        export::write_json(&file, &mut out).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(json["format_version"], export::FORMAT_VERSION);
+        assert_eq!(json["created"], "2004-06-21T24:00:00");
+        let group = &json["groups"][0];
+        assert_eq!(group["as_of"], "2004-06-20T23:59:00");
+        assert_eq!(group["as_of_modifier"]["name"], "final_previous_day");
+        let account = &group["accounts"][0];
+        assert_eq!(account["infos"][0]["amount"]["value"], "43500.00");
+        let total_debits = &group["accounts"][1]["infos"][2];
+        assert_eq!(total_debits["code"]["code"], 400);
+        assert_eq!(total_debits["amount"]["value"], "20000.00");
+        assert_eq!(total_debits["signed_amount"]["value"], "-20000.00");
+        let transaction = &account["transactions"][0];
+        assert_eq!(transaction["code"]["code"], 115);
+        assert_eq!(transaction["code"]["name"], "Lockbox Deposit");
+        assert_eq!(transaction["code"]["direction"], "credit");
+        assert_eq!(transaction["funds"]["type"], "distributed");
+        assert_eq!(transaction["funds"]["two_or_more_days"]["value"], "1500.00");
+        assert_eq!(account["transactions"].as_array().unwrap().len(), 1);
+        let value_dated = &json["groups"][1]["accounts"][0]["transactions"][0];
+        assert_eq!(value_dated["funds"]["available"], "2004-06-22");
+        assert_eq!(value_dated["bank_reference"], "SP4738");
+        assert_eq!(value_dated["text"][0], "PROCEEDS OF LETTER OF CREDIT FROM THE ARAMCO OIL CO");
+
+        let mut yaml = Vec::new();
+        export::write_yaml(&file, &mut yaml).unwrap();
+        let yaml = String::from_utf8(yaml).unwrap();
+        assert!(yaml.contains("format_version: 1\n"));
+        assert!(yaml.contains("name: Lockbox Deposit\n"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn export_schema_spec_example() {
+        let schema = include_str!("../resources/export-schema.json");
+        let mut scope = valico::json_schema::Scope::new();
+        let schema = scope
+            .compile_and_return(serde_json::from_str(schema).unwrap(), false)
+            .unwrap();
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        export::write_json(&file, &mut out).unwrap();
+        let json = serde_json::from_slice(&out).unwrap();
+        let state = schema.validate(&json);
+        assert!(state.is_valid(), "export doesn't match the schema: {:?}", state.errors);
+    }
+
+    #[cfg(feature = "serde-serialize")]
+    #[test]
+    fn from_slice_spec_example() {
+        #[derive(Deserialize)]
+        struct Statement<'a> {
+            sender_ident: &'a str,
+            creation_date: String,
+            groups: Vec<Group>,
+            records_num: usize,
+        }
+        #[derive(Deserialize)]
+        struct Group {
+            as_of_time: Option<String>,
+            accounts: Vec<Account>,
+        }
+        #[derive(Deserialize)]
+        struct Account {
+            customer_account_num: String,
+            infos: Vec<Info>,
+            transaction_details: Vec<Transaction>,
+            control_total: i64,
+        }
+        #[derive(Deserialize)]
+        struct Info {
+            type_code: u16,
+            amount: Option<i64>,
+        }
+        #[derive(Deserialize)]
+        struct Transaction {
+            type_code: u16,
+            amount: Option<i64>,
+            funds_type: Option<Funds>,
+            text: Option<Vec<String>>,
+        }
+        #[derive(Deserialize)]
+        #[serde(tag = "code")]
+        enum Funds {
+            #[serde(rename = "0")]
+            Immediate,
+            #[serde(rename = "1")]
+            OneDay,
+            S { more_than_one_day: Option<i64> },
+            V { date: String },
+            D,
+        }
+
+        let statement: Statement = from_slice(SPEC_EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(statement.sender_ident, "122099999");
+        assert_eq!(statement.creation_date, "2004-06-21");
+        assert_eq!(statement.records_num, 31);
+        assert_eq!(statement.groups.len(), 4);
+        let group = &statement.groups[0];
+        assert_eq!(group.as_of_time.as_ref().map(|t| &**t), Some("23:59:00"));
+        let account = &group.accounts[0];
+        assert_eq!(account.customer_account_num, "0123456789");
+        assert_eq!((account.infos[0].type_code, account.infos[0].amount), (10, Some(4350000)));
+        let transaction = &account.transaction_details[0];
+        assert_eq!((transaction.type_code, transaction.amount), (115, Some(450000)));
+        match transaction.funds_type {
+            Some(Funds::S { more_than_one_day }) => assert_eq!(more_than_one_day, Some(150000)),
+            _ => panic!("expected distributed availability"),
+        }
+        assert_eq!(account.control_total, 9150000);
+        let transaction = &statement.groups[1].accounts[0].transaction_details[0];
+        match transaction.funds_type {
+            Some(Funds::V { ref date }) => assert_eq!(date, "2004-06-22"),
+            _ => panic!("expected value dated funds"),
+        }
+        assert!(transaction.text.as_ref().unwrap()[0].starts_with("PROCEEDS OF LETTER"));
+
+        let broken = SPEC_EXAMPLE.replacen("49,", "49,1", 1);
+        match from_slice::<Statement>(broken.as_bytes()) {
+            Err(de::Error::Process(data::FileProcessError::Conversion(
+                _,
+                ast::convert::ConvertError::Account {
+                    err: ast::convert::AccountConvError::ControlTotal { expected, actual },
+                    ..
+                },
+            ))) => assert_eq!((expected, actual), (19150000, 9150000)),
+            r => panic!("expected a control total error, got {:?}", r.err()),
+        }
+
+        // Records are checked as the converter checks them.
+        use ast::convert::{ChronoError, ConvertError as CE, FileConvError, GroupConvError,
+                           TransactionDetailConvError};
+        let error = |input: &str| match from_slice::<Statement>(input.as_bytes()) {
+            Err(de::Error::Process(data::FileProcessError::Conversion(p, err))) => (p.line, err),
+            r => panic!("expected a conversion error, got {:?}", r.err()),
+        };
+        match error(&SPEC_EXAMPLE.replacen("040621,0200", "040231,0200", 1)) {
+            (1, CE::File(FileConvError::Creation(ChronoError::InvalidDate), _)) => {}
+            r => panic!("expected an invalid date, got {:?}", r),
+        }
+        match error(&SPEC_EXAMPLE.replacen("16,115,450000", "16,010,450000", 1)) {
+            (5, CE::TransactionDetail { err: TransactionDetailConvError::DetailCode(10), .. }) => {}
+            r => panic!("expected a bad detail code, got {:?}", r),
+        }
+        let btrs = SPEC_EXAMPLE
+            .replacen(",65,,2/", ",65,,3/", 1)
+            .replacen("02,031001234,122099999,1,", "02,031001234,122099999,4,", 1);
+        match error(&btrs) {
+            (2, CE::Group { err: GroupConvError::RetiredStatus(_), .. }) => {}
+            r => panic!("expected a retired group status, got {:?}", r),
+        }
+
+        // Nothing may follow the file trailer.
+        let trailing = format!("{}{}", SPEC_EXAMPLE, SPEC_EXAMPLE.lines().next().unwrap());
+        match from_slice::<Statement>(trailing.as_bytes()) {
+            Err(de::Error::TrailingRecords(p)) => assert_eq!(p.line, 32),
+            r => panic!("expected trailing records, got {:?}", r.err()),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_vec_spec_example() {
+        use chrono::NaiveDate;
+
+        let value: serde_json::Value = from_slice(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let out = to_vec(&value).unwrap();
+        let spec = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let file = data::File::process(&out).unwrap();
+        assert_eq!(format!("{:?}", file.groups), format!("{:?}", spec.groups));
+        assert!(out.starts_with(b"01,122099999,123456789,040621,0200,1,80,,2/\n"));
+
+        #[derive(Serialize)]
+        struct Statement {
+            sender_ident: &'static str,
+            receiver_ident: &'static str,
+            creation_date: NaiveDate,
+            creation_time: &'static str,
+            ident_num: u32,
+            groups: Vec<Group>,
+        }
+        #[derive(Serialize)]
+        struct Group {
+            status: u8,
+            as_of_date: NaiveDate,
+            currency: &'static str,
+            accounts: Vec<Account>,
+        }
+        #[derive(Serialize)]
+        struct Account {
+            customer_account_num: &'static str,
+            transaction_details: Vec<Transaction>,
+        }
+        #[derive(Serialize)]
+        struct Transaction {
+            type_code: u16,
+            amount: i64,
+            funds_type: &'static str,
+            text: &'static str,
+        }
+
+        let statement = Statement {
+            sender_ident: "SIM",
+            receiver_ident: "TREASURY",
+            creation_date: NaiveDate::from_ymd(2018, 1, 31),
+            creation_time: "24:00",
+            ident_num: 7,
+            groups: vec![Group {
+                status: 1,
+                as_of_date: NaiveDate::from_ymd(2018, 1, 30),
+                currency: "USD",
+                accounts: vec![Account {
+                    customer_account_num: "42",
+                    transaction_details: vec![
+                        Transaction {
+                            type_code: 195,
+                            amount: 12345,
+                            funds_type: "0",
+                            text: "WIRE",
+                        },
+                        Transaction {
+                            type_code: 495,
+                            amount: 345,
+                            funds_type: "1",
+                            text: "OUTGOING WIRE",
+                        },
+                    ],
+                }],
+            }],
+        };
+        let out = to_vec(&statement).unwrap();
+        let file = data::File::process(&out).unwrap();
+        assert_eq!(file.control_total(), 12690);
+        let account = &file.groups[0].accounts[0];
+        assert_eq!(account.transaction_details[1].text, Some(vec!["OUTGOING WIRE".to_owned()]));
+        assert!(String::from_utf8(out).unwrap().ends_with("99,12690,1,8/\n"));
+    }
+
+    #[cfg(feature = "serde-serialize")]
+    #[test]
+    fn to_writer_streams() {
+        #[derive(Serialize)]
+        struct Statement {
+            sender_ident: &'static str,
+            receiver_ident: &'static str,
+            creation_date: &'static str,
+            creation_time: &'static str,
+            ident_num: u32,
+            groups: Vec<Group>,
+            block_size: Option<u32>,
+        }
+        #[derive(Serialize)]
+        struct Group {
+            status: u8,
+            as_of_date: &'static str,
+            accounts: Vec<Account>,
+        }
+        #[derive(Serialize)]
+        struct Account {
+            customer_account_num: &'static str,
+            transaction_details: Vec<Transaction>,
+        }
+        #[derive(Serialize)]
+        struct Transaction {
+            type_code: u16,
+            amount: i64,
+        }
+
+        let transaction = |type_code| Transaction { type_code, amount: 100 };
+        let mut statement = Statement {
+            sender_ident: "SIM",
+            receiver_ident: "TREASURY",
+            creation_date: "2018-01-31",
+            creation_time: "12:00",
+            ident_num: 7,
+            groups: vec![Group {
+                status: 1,
+                as_of_date: "2018-01-30",
+                accounts: vec![Account {
+                    customer_account_num: "42",
+                    transaction_details: vec![transaction(195), transaction(1000)],
+                }],
+            }],
+            block_size: None,
+        };
+
+        // Records are written as they're reached, before the bad type code.
+        let mut out = Vec::new();
+        match to_writer(&mut out, &statement) {
+            Err(ser::Error::Invalid("type_code", ref code)) => assert_eq!(code, "1000"),
+            r => panic!("expected an invalid type code, got {:?}", r),
+        }
+        assert!(String::from_utf8(out).unwrap().ends_with("03,42,,,,,/\n16,195,100,,,,/\n"));
+
+        statement.groups[0].accounts[0].transaction_details.pop();
+        let out = to_vec(&statement).unwrap();
+        assert_eq!(data::File::process(&out).unwrap().control_total(), 100);
+        statement.block_size = Some(10);
+        match to_vec(&statement) {
+            Err(ser::Error::Order(ref field, "groups")) => assert_eq!(field, "block_size"),
+            r => panic!("expected a field after the groups, got {:?}", r.err()),
+        }
+    }
+
+    #[test]
+    fn csv_spec_example() {
+        use csv::{Column, CsvOptions};
+
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        csv::write_transactions(&file, &CsvOptions::default(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let rows: Vec<&str> = out.lines().collect();
+        assert_eq!(rows.len(), 1 + 4);
+        assert_eq!(
+            rows[0],
+            "sender,as_of_date,group_status,account_num,currency,type_code,description,\
+             signed_amount,funds_type,bank_ref_num,customer_ref_num,text"
+        );
+        assert_eq!(
+            rows[2],
+            "122099999,2004-06-20,Update,9876543210,USD,115,Lockbox Deposit,5000.00,\
+             distributed,,,LOCK BOX NO.68751"
+        );
+
+        let options = CsvOptions {
+            info_columns: vec![Column::AccountNum, Column::Level, Column::SignedAmount],
+            headers: false,
+            ..CsvOptions::default()
+        };
+        let mut out = Vec::new();
+        csv::write_infos(&file, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().next(), Some("0123456789,status,43500.00"));
+        assert!(out.contains("9876543210,summary,-20000.00\r\n"));
+        assert_eq!("Signed_Amount ".parse(), Ok(Column::SignedAmount));
+    }
+
+    #[cfg(feature = "camt")]
+    #[test]
+    fn camt053_spec_example() {
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        camt::write_camt053(&file, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+
+        assert!(xml.contains("xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.02\""));
+        assert_eq!(xml.matches("<Stmt>").count(), 5);
+        assert_eq!(xml.matches("<Ntry>").count(), 4);
+        assert!(xml.contains("<Cd>OPBD</Cd>"));
+        let balance = "<Amt Ccy=\"USD\">5000.00</Amt>\n        <CdtDbtInd>DBIT</CdtDbtInd>";
+        assert!(xml.contains(balance));
+        assert!(xml.contains("<TtlDbtNtries>\n          <Sum>20000.00</Sum>"));
+        assert!(xml.contains("<AcctSvcrRef>SP4738</AcctSvcrRef>"));
+        assert!(xml.contains("<EndToEndId>YRC065321</EndToEndId>"));
+        assert!(xml.contains("<Cd>LBOX</Cd>"));
+        assert!(xml.contains("<AddtlNtryInf>PROCEEDS OF LETTER OF CREDIT FROM THE ARAMCO OIL CO"));
+    }
+
+    #[cfg(feature = "camt")]
+    #[test]
+    fn camt052_spec_example() {
+        let interim = SPEC_EXAMPLE.replacen(",040620,2359,,2/", ",040620,1130,,3/", 1);
+        let file = data::File::process(interim.as_bytes()).unwrap();
+
+        let mut out = Vec::new();
+        camt::write_camt052(&file, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.052.001.02\""));
+        assert!(xml.contains("<BkToCstmrAcctRpt>"));
+        assert_eq!(xml.matches("<Rpt>").count(), 2);
+        assert!(xml.contains(
+            "<Id>1-1-1</Id>\n      <CreDtTm>2004-06-20T11:30:00</CreDtTm>\n      <FrToDt>\n        \
+             <FrDtTm>2004-06-20T00:00:00</FrDtTm>\n        <ToDtTm>2004-06-20T11:30:00</ToDtTm>"
+        ));
+
+        let mut out = Vec::new();
+        camt::write_camt053(&file, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert_eq!(xml.matches("<Stmt>").count(), 3);
+        assert!(!xml.contains("<Id>1-1-1</Id>"));
+
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        assert!(camt::write_camt052(&file, Vec::new()).is_err());
+
+        let unbalanced = SPEC_EXAMPLE.replacen("03,0975312468,,010,", "03,0975312468,,020,", 1);
+        let file = data::File::process(unbalanced.as_bytes()).unwrap();
+        let err = camt::write_camt053(&file, Vec::new()).unwrap_err();
+        let missing = err.get_ref().and_then(|e| e.downcast_ref::<camt::MissingBalance>());
+        assert_eq!(missing.map(|m| &*(m.0).0), Some("0975312468"));
+    }
+
+    #[test]
+    fn mt940_spec_example() {
+        let interim = SPEC_EXAMPLE.replacen(",040620,2359,,2/", ",040620,1130,,3/", 1);
+        let file = data::File::process(interim.as_bytes()).unwrap();
+
+        let mut out = Vec::new();
+        swift::write_mt940(&file, &mut out).unwrap();
+        let mt940 = String::from_utf8(out).unwrap();
+        assert_eq!(mt940.matches("\r\n-\r\n").count(), 3);
+        assert!(mt940.contains(":20:1-2-1\r\n:25:4589761203\r\n:28C:1/1\r\n:60F:C040620USD"));
+        assert!(mt940.contains(
+            ":61:0406220620C200000,00NCOLYRC065321//SP4738\r\n\
+             :86:PROCEEDS OF LETTER OF CREDIT FROM THE ARAMCO OIL CO\r\n"
+        ));
+        assert!(mt940.lines().all(|line| line.len() <= 4 + 65));
+        assert!(mt940.lines().filter(|l| l.starts_with(":28C:")).all(|l| l == ":28C:1/1"));
+
+        let mut out = Vec::new();
+        swift::write_mt942(&file, &mut out).unwrap();
+        let mt942 = String::from_utf8(out).unwrap();
+        assert!(mt942.contains(
+            ":20:1-1-1\r\n:25:0123456789\r\n:28C:1/1\r\n:34F:USD0,\r\n:13D:0406201130+0000\r\n"
+        ));
+        assert!(mt942.contains(":61:0406200620C4500,00NLBXNONREF\r\n"));
+        assert!(mt942.contains(":86:LOCK BOX NO.68751\r\n:90C:1USD5000,00\r\n-\r\n"));
+
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        assert!(swift::write_mt942(&file, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn swift_transaction_types_round_trip() {
+        use std::convert::TryFrom;
+
+        // Every type read as a code the spec defines is written back as the same type.
+        let types = [
+            "LBX", "DCR", "RTI", "COL", "INT", "DIV", "COM", "CHG", "FEX", "SEC", "LDP", "CHK",
+            "TRF", "STO", "DDT", "MSC",
+        ];
+        for &tp in &types {
+            for &credit in &[true, false] {
+                let code = u16::from(swift::detail_code(tp, credit));
+                if code != u16::from(data::DetailCode::custom(credit)) {
+                    assert_eq!((code, swift::transaction_type(code)), (code, tp));
+                }
+            }
+        }
+        // And every code is read back as one with the type it was written as.
+        for code in 0..1000 {
+            let credit = match data::DetailCode::try_from(code).map(|c| c.direction()) {
+                Ok(data::Direction::Credit) => true,
+                Ok(data::Direction::Debit) => false,
+                _ => continue,
+            };
+            let tp = swift::transaction_type(code);
+            let read = u16::from(swift::detail_code(tp, credit));
+            if read != u16::from(data::DetailCode::custom(credit)) {
+                assert_eq!((code, swift::transaction_type(read)), (code, tp));
+            }
+        }
+        assert_eq!(swift::transaction_type(238), "DIV");
+        assert_eq!(swift::transaction_type(227), "STO");
+    }
+
+    #[test]
+    fn import_spec_example() {
+        use chrono::NaiveDate;
+
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+
+        let mut mt940 = Vec::new();
+        swift::write_mt940(&file, &mut mt940).unwrap();
+        // What's written has no header blocks to name the parties.
+        match swift::read_mt940(mt940.as_slice()) {
+            Err(swift::ReadError::UnknownParty("sender")) => {}
+            r => panic!("expected an unknown sender, got {:?}", r.map(|f| f.sender)),
+        }
+        let parties = |file: &data::File| (file.sender.0.clone(), file.receiver.0.clone());
+        let (sender, receiver) = parties(&file);
+        #[cfg_attr(not(feature = "camt"), allow(unused_mut))]
+        let mut imported = vec![
+            swift::read_mt940_with_parties(
+                mt940.as_slice(),
+                data::Party(sender.clone()),
+                data::Party(receiver.clone()),
+            ).unwrap(),
+        ];
+        #[cfg(feature = "camt")]
+        {
+            let mut camt053 = Vec::new();
+            camt::write_camt053(&file, &mut camt053).unwrap();
+            imported.push(camt::read_camt053(camt053.as_slice()).unwrap());
+
+            let camt053 = String::from_utf8(camt053).unwrap();
+            let start = camt053.find("<MsgRcpt>").unwrap();
+            let end = camt053.find("</MsgRcpt>").unwrap() + "</MsgRcpt>".len();
+            let unaddressed = format!("{}{}", &camt053[..start], &camt053[end..]);
+            match camt::read_camt053(unaddressed.as_bytes()) {
+                Err(camt::ReadError::UnknownParty("receiver")) => {}
+                r => panic!("expected an unknown receiver, got {:?}", r.map(|f| f.receiver)),
+            }
+            let read = camt::read_camt053_with_parties(
+                unaddressed.as_bytes(),
+                data::Party(sender.clone()),
+                data::Party(receiver.clone()),
+            ).unwrap();
+            assert_eq!(parties(&read), parties(&file));
+
+            let unnumbered = camt053.replacen("-1</MsgId>", "-A</MsgId>", 1);
+            match camt::read_camt053(unnumbered.as_bytes()) {
+                Err(camt::ReadError::UnknownIdent) => {}
+                r => panic!("expected an unknown ident, got {:?}", r.map(|f| f.ident)),
+            }
+            let zoned = camt053.replacen(":00</CreDtTm>", ":00+0\u{e9}00</CreDtTm>", 1);
+            assert!(camt::read_camt053(zoned.as_bytes()).is_err());
+        }
+        assert_eq!(parties(&imported[0]), parties(&file));
+        for imported in &imported {
+            assert_eq!(imported.ident.0, 1);
+            assert_eq!(imported.groups.len(), 1);
+            let group = &imported.groups[0];
+            assert_eq!(group.as_of.clone().date(), NaiveDate::from_ymd_opt(2004, 6, 20).unwrap());
+            assert_eq!(group.accounts.len(), 5);
+            let account = &group.accounts[2];
+            assert_eq!(account.customer_account.0, "4589761203");
+            assert_eq!(account.infos[0].signed_amount().unwrap().unwrap().value, 10000000);
+
+            let details: Vec<_> = group
+                .accounts
+                .iter()
+                .flat_map(|a| &a.transaction_details)
+                .collect();
+            let original: Vec<_> = file.groups
+                .iter()
+                .flat_map(|g| &g.accounts)
+                .flat_map(|a| &a.transaction_details)
+                .collect();
+            assert_eq!(details.len(), 4);
+            for (detail, original) in details.iter().zip(&original) {
+                assert_eq!(detail.code, original.code);
+                assert_eq!(detail.amount, original.amount);
+            }
+            let detail = details[2];
+            assert_eq!(detail.bank_ref_num.as_ref().unwrap().0, "SP4738");
+            assert_eq!(detail.customer_ref_num.as_ref().unwrap().0, "YRC065321");
+            match detail.funds {
+                Some(data::FundsType::ValueDated(ref date)) => {
+                    assert_eq!(date.clone().date(), NaiveDate::from_ymd_opt(2004, 6, 22).unwrap())
+                }
+                ref funds => panic!("{:?}", funds),
+            }
+            assert_eq!(
+                detail.text.as_ref().unwrap().join(" "),
+                "PROCEEDS OF LETTER OF CREDIT FROM THE ARAMCO OIL CO"
+            );
+            imported.write_to(&mut Vec::new()).unwrap();
+        }
+
+        let unmapped = ":25:1\r\n:60F:C040620USD1,\r\n:61:040620D1,NXYZNONREF\r\n\
+                        :62F:C040620USD0,\r\n";
+        let file = swift::read_mt940_with_parties(
+            unmapped.as_bytes(),
+            data::Party(sender),
+            data::Party(receiver),
+        ).unwrap();
+        let detail = &file.groups[0].accounts[0].transaction_details[0];
+        assert_eq!(u16::from(detail.code), 999);
+        assert!(swift::read_mt940(&b":25:1\r\n"[..]).is_err());
+    }
+
+    /// Validates against the schemas in `resources/`, `camt.053.001.02.xsd` and
+    /// `camt.052.001.02.xsd`, with `xmllint`.
+    #[cfg(feature = "camt")]
+    #[test]
+    fn camt_validates_against_xsd() {
+        use std::io::{self, Write};
+        use std::path::{Path, PathBuf};
+        use std::process::{Command, Stdio};
+
+        type WriteFn = fn(&data::File, &mut io::Write) -> io::Result<()>;
+        fn validate(xsd: &Path, file: &data::File, write: WriteFn) {
+            let mut xmllint = Command::new("xmllint")
+                .arg("--noout")
+                .arg("--schema")
+                .arg(xsd)
+                .arg("-")
+                .stdin(Stdio::piped())
+                .spawn()
+                .unwrap();
+            write(file, xmllint.stdin.as_mut().unwrap()).unwrap();
+            xmllint.stdin.take().unwrap().flush().unwrap();
+            assert!(xmllint.wait().unwrap().success());
+        }
+
+        let resources = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let xsds: Vec<PathBuf> = ["camt.053.001.02.xsd", "camt.052.001.02.xsd"]
+            .iter()
+            .map(|name| resources.join(name))
+            .collect();
+        for xsd in &xsds {
+            assert!(xsd.is_file(), "{} is missing", xsd.display());
+        }
+        assert!(
+            Command::new("xmllint").arg("--version").output().is_ok(),
+            "camt validation needs xmllint"
+        );
+
+        let long = "9".repeat(40);
+        let interim = SPEC_EXAMPLE
+            .replacen(",040620,2359,,2/", ",040620,1130,,3/", 1)
+            .replacen("01,122099999,123456789,", &format!("01,122099999,{},", long), 1)
+            .replacen("03,0123456789,,010,+4350000,,,040,", "03,0123456789,,020,+4350000,,,050,", 1)
+            .replacen("03,4589761203,", &format!("03,{},", long), 1);
+        let file = data::File::process(interim.as_bytes()).unwrap();
+        validate(&xsds[0], &file, |file, w| camt::write_camt053(file, w));
+        validate(&xsds[1], &file, |file, w| camt::write_camt052(file, w));
+    }
+
+    #[test]
+    fn read_spec_example() {
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let read = data::File::from_source(&mut SPEC_EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", read));
+
+        let unterminated = SPEC_EXAMPLE.trim_right().as_bytes();
+        let read = read::RecordReader::new(unterminated).read_file().unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", read));
+    }
+
+    #[test]
+    fn error_position_spec_example() {
+        let corrupt = SPEC_EXAMPLE.replace("16,195,10000000", "16,195,1000x000");
+        let expected = parse::Position {
+            record_code: Some(16),
+            offset: corrupt.find("1000x000").unwrap(),
+            line: 18,
+            column: 8,
+        };
+        let err = data::File::process(corrupt.as_bytes()).unwrap_err();
+        assert_eq!(err.position(), expected);
+        match read::RecordReader::new(corrupt.as_bytes()).read_file() {
+            Err(read::ReadError::Process(data::FileProcessError::FieldParse(p, e))) => {
+                assert_eq!((p, e.position()), (expected, Some(expected)))
+            }
+            r => panic!("expected a field parse error, got {:?}", r),
+        }
+
+        let corrupt = SPEC_EXAMPLE.replace("49,9150000,4/", "49,9150001,4/");
+        match data::File::process(corrupt.as_bytes()) {
+            Err(data::FileProcessError::Conversion(p, e)) => {
+                assert_eq!((p.line, p.offset), (6, corrupt.find("49,9150001").unwrap()));
+                assert_eq!(e.position(), Some(p));
+            }
+            r => panic!("expected a conversion error, got {:?}", r),
+        }
+
+        let truncated = &SPEC_EXAMPLE[..SPEC_EXAMPLE.find("\n99,").unwrap() + 1];
+        let err = data::File::process(truncated.as_bytes()).unwrap_err();
+        assert_eq!(err.position().line, 31);
+    }
+
+    #[test]
+    fn error_display_spec_example() {
+        let corrupt = SPEC_EXAMPLE.replace("16,195,10000000", "16,195,1000x000");
+        let err = data::File::process(corrupt.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 18, column 8 (16 record): transaction detail: amount is not a valid number: \
+             invalid digit found in string"
+        );
+
+        let corrupt = SPEC_EXAMPLE.replace("49,9150000,4/", "49,9150001,4/");
+        let err = data::File::from_source(&mut corrupt.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 6, column 1 (49 record): account trailer control total 9150001 does not match \
+             computed 9150000 (group 1, account 1)"
+        );
+        assert!(::std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn iterate_records() {
+        let records = read::RecordReader::new(SPEC_EXAMPLE.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 24);
+        match records[0].parsed() {
+            ast::ParsedRecord::FileHeader(_) => {}
+            r => panic!("expected a file header, got {:?}", r),
+        }
+        match records[23].parsed() {
+            ast::ParsedRecord::FileTrailer(_) => {}
+            r => panic!("expected a file trailer, got {:?}", r),
+        }
+        assert_eq!(records[2].continuations(), 1);
+        assert_eq!(records[23].position().line, 31);
+
+        let broken = SPEC_EXAMPLE.replacen("16,115", "16,1x5", 1);
+        let results: Vec<_> = read::RecordReader::new(broken.as_bytes()).collect();
+        assert_eq!(results.len(), 24);
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    }
+
+    #[test]
+    fn layout_spec_example() {
+        let file = data::File::process_checked(SPEC_EXAMPLE.as_bytes()).unwrap();
+
+        let long = SPEC_EXAMPLE.replace("PROCEEDS OF", "PROCEEDS OF THE IRREVOCABLE STANDBY");
+        data::File::process(long.as_bytes()).unwrap();
+        let err = data::File::process_checked(long.as_bytes()).unwrap_err();
+        assert_eq!((err.position().line, err.position().column), (17, 66));
+        let mut reader = read::RecordReader::new(long.as_bytes());
+        reader.set_check_layout(true);
+        match reader.read_file() {
+            Err(read::ReadError::Process(err)) => assert_eq!(err.position().line, 17),
+            r => panic!("expected a layout error, got {:?}", r),
+        }
+
+        let fixed = |text: &str| {
+            text.lines().map(|line| format!("{:65}", line)).collect::<String>()
+        };
+        let read = read::RecordReader::fixed_length(fixed(SPEC_EXAMPLE).as_bytes())
+            .read_file()
+            .unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", read));
+
+        let blocked = fixed(&SPEC_EXAMPLE.replace(",65,,2/", ",65,4,2/"));
+        let mut reader = read::RecordReader::fixed_length(blocked.as_bytes());
+        reader.set_check_layout(true);
+        match reader.read_file() {
+            Err(read::ReadError::Process(data::FileProcessError::Layout(
+                parse::LayoutError::BlockSize(_, 31, 4),
+            ))) => {}
+            r => panic!("expected a block size error, got {:?}", r),
+        }
+        let padded = blocked + &" ".repeat(65);
+        let mut reader = read::RecordReader::fixed_length(padded.as_bytes());
+        reader.set_check_layout(true);
+        reader.read_file().unwrap();
+    }
+
+    #[test]
+    fn lenient_spec_example() {
+        let sloppy = SPEC_EXAMPLE
+            .replace("49,4000000,5/", "49,4000000,5")
+            .replace("16,195,10000000", "16,800,10000000")
+            .replace("D,3,0,", "D,2,0,")
+            .replace("110,5000000/", "110,-5000000/")
+            .replace("11800000", "1800000")
+            .replace("345450000", "335450000");
+        let lenient = data::ParseOptions {
+            strictness: data::Strictness::Lenient,
+            ..data::ParseOptions::default()
+        };
+
+        assert!(data::File::process(sloppy.as_bytes()).is_err());
+        let (file, warnings) = data::File::process_with(sloppy.as_bytes(), &lenient).unwrap();
+        let lines = warnings.iter().map(|w| w.position().line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![11, 18, 22, 27]);
+        match warnings[0] {
+            data::Warning(data::FileProcessError::MissingRecordSep(p)) => assert_eq!(p.column, 13),
+            ref w => panic!("expected a missing record separator, got {:?}", w),
+        }
+        assert_eq!(
+            u16::from(file.groups[1].accounts[0].transaction_details[1].code),
+            800
+        );
+        match file.groups[3].accounts[0].infos[2] {
+            data::AccountInfo::Summary { amount, .. } => {
+                assert_eq!(amount.map(|a| a.value), Some(-5000000))
+            }
+            ref info => panic!("expected a summary, got {:?}", info),
+        }
+
+        let unterminated = &b"49,4000000,5\n"[..];
+        match parse::record(unterminated) {
+            nom::IResult::Error(kind) => {
+                assert_eq!(parse::describe_error(&kind), "missing record separator")
+            }
+            r => panic!("expected a missing record separator, got {:?}", r),
+        }
+        assert!(parse::record_lenient(unterminated, &dialect::Spec).is_done());
+        let quirks = dialect::Quirks {
+            missing_record_sep: true,
+            ..dialect::Quirks::default()
+        };
+        assert!(parse::record_with(unterminated, &quirks).is_done());
+
+        let mut reader = read::RecordReader::new(sloppy.as_bytes());
+        reader.set_strictness(data::Strictness::Lenient);
+        let read = reader.read_file().unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", read));
+        let read_lines = reader
+            .take_warnings()
+            .iter()
+            .map(|w| w.position().line)
+            .collect::<Vec<_>>();
+        assert_eq!(read_lines, lines);
+    }
+
+    #[test]
+    fn recovery_spec_example() {
+        let broken = SPEC_EXAMPLE
+            .replace("16,115,450000,S,", "16,899,450000,S,")
+            .replace("03,0975312468,,010,500000,", "03,0975312468,,010,5x0000,")
+            .replace("49,11800000,3/", "49,11800001,3/");
+
+        assert!(data::File::process(broken.as_bytes()).is_err());
+        let options = data::ParseOptions::default();
+        let dialects = dialect::Dialects::default();
+        let recovered = data::File::process_recovering(broken.as_bytes(), &options, &dialects);
+        let lines = recovered
+            .errors
+            .iter()
+            .map(|e| e.position().line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![5, 22, 29]);
+        let file = recovered.file.unwrap();
+        let accounts = file.groups
+            .iter()
+            .map(|g| g.accounts.len())
+            .collect::<Vec<_>>();
+        assert_eq!(accounts, vec![1, 1, 0, 0]);
+
+        let mut reader = read::RecordReader::new(broken.as_bytes());
+        reader.set_recovery(true);
+        let read = reader.read_file().unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", read));
+        let read_lines = reader
+            .take_errors()
+            .iter()
+            .map(|e| e.position().line)
+            .collect::<Vec<_>>();
+        assert_eq!(read_lines, lines);
+    }
+
+    #[test]
+    fn amounts_spec_example() {
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let account = &file.groups[0].accounts[0];
+        assert_eq!(account.infos[0].amount().unwrap().to_string(), "43,500.00 USD");
+        let detail = &file.groups[1].accounts[0].transaction_details[0];
+        assert_eq!(detail.amount.unwrap().to_string(), "200,000.00 USD");
+
+        let currency = |code: &str| code.parse::<penny::Currency>().unwrap();
+        assert_eq!(data::Amount::new(4350000, currency("JPY")).to_string(), "4,350,000 JPY");
+        assert_eq!(data::Amount::new(-4350000, currency("BHD")).to_string(), "-4,350.000 BHD");
+        assert_eq!(data::Amount::new(5, currency("EUR")).to_string(), "0.05 EUR");
+    }
+
+    #[test]
+    fn btrs_spec_example() {
+        let btrs = SPEC_EXAMPLE.replacen(",65,,2/", ",65,,3/", 1);
+        let lenient = data::ParseOptions {
+            strictness: data::Strictness::Lenient,
+            ..data::ParseOptions::default()
+        };
+
+        // Distributed availability and group statuses other than update are retired.
+        let err = data::File::process(btrs.as_bytes()).unwrap_err();
+        assert_eq!(err.position().line, 22);
+        let (file, warnings) = data::File::process_with(btrs.as_bytes(), &lenient).unwrap();
+        let lines = warnings.iter().map(|w| w.position().line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![22, 26]);
+        assert_eq!(file.version, data::Version::Btrs3);
+        let mut out = Vec::new();
+        file.write_to(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap().lines().next(),
+            Some("01,122099999,123456789,040621,0200,1,80,,3/")
+        );
+
+        let bai2 = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(bai2.version, data::Version::Bai2);
+        assert!(data::DetailCode::from_version(925, data::Version::Bai2).is_ok());
+        assert!(data::DetailCode::from_version(925, data::Version::Btrs3).is_err());
+        assert!(data::SummaryCode::from_version(925, data::Version::Btrs3).is_ok());
+
+        // BTRS adds codes BAI2 doesn't have, and both keep 900-919 for custom statuses.
+        let btrs = btrs
+            .replacen("16,115,450000,S,", "16,158,450000,S,", 1)
+            .replacen("03,0123456789,,010,", "03,0123456789,,905,", 1);
+        let (file, warnings) = data::File::process_with(btrs.as_bytes(), &lenient).unwrap();
+        assert_eq!(warnings.len(), 2);
+        let account = &file.groups[0].accounts[0];
+        assert_eq!(
+            account.transaction_details[0].code,
+            data::DetailCode::Credit(data::CreditDetail::RealTimePaymentCredit)
+        );
+        match account.infos[0] {
+            data::AccountInfo::Status { code, .. } => {
+                assert_eq!(code, data::StatusCode::Account(data::AccountStatus::Custom(905)))
+            }
+            ref info => panic!("expected a status, got {:?}", info),
+        }
+        assert_eq!(data::TypeCode::lookup(158).unwrap().version, data::Version::Btrs3);
+        assert!(data::DetailCode::from_version(158, data::Version::Bai2).is_err());
+        assert!(data::DetailCode::from_version(458, data::Version::Btrs3).is_ok());
+        assert!(data::StatusCode::from_version(905, data::Version::Btrs3).is_ok());
+    }
+
+    #[test]
+    fn dialect_spec_example() {
+        use ast::convert::{ConvertError, FileConvError, GroupConvError};
+        use dialect::{Dialect, Dialects, Quirks};
+
+        let quirky = SPEC_EXAMPLE
+            .replace(",040621,0200,1,65,,2/", ",20040621,0200,1,65,,2,/")
+            .replacen(",040620,2359,", ",040620,    ,", 1)
+            .replace("49,9150000,4/", "49,9150000,4")
+            .replace("16,195,10000000,1,,,/", "16,195,10000000,1,,,/BNF/ACME");
+        let options = data::ParseOptions::default();
+        let quirks = Quirks {
+            missing_record_sep: true,
+            trailing_field_sep: true,
+            four_digit_year: true,
+            blank_as_of_time: true,
+            slash_in_text: true,
+            ..Quirks::default()
+        };
+
+        assert!(data::File::process(quirky.as_bytes()).is_err());
+        let mut dialects = Dialects::default();
+        dialects.register("122099999", quirks);
+        let (file, warnings) =
+            data::File::process_with_dialects(quirky.as_bytes(), &options, &dialects).unwrap();
+        assert!(warnings.is_empty());
+        let spec = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(file.creation.to_string(), spec.creation.to_string());
+        assert_eq!(file.groups[0].as_of.to_string(), "2004-06-20");
+        assert_eq!(
+            file.groups[1].accounts[0].transaction_details[1].text,
+            Some(vec!["/BNF/ACME".to_owned()])
+        );
+
+        let mut reader = read::RecordReader::new(quirky.as_bytes());
+        reader.set_dialects(dialects);
+        let read = reader.read_file().unwrap();
+        assert_eq!(format!("{:?}", file), format!("{:?}", read));
+
+        let no_customer_ref = Quirks {
+            no_customer_ref: true,
+            ..Quirks::default()
+        };
+        let detail = b"16,195,10000000,1,REF123,PROCEEDS/\n49,10000000,2/";
+        let mut records = parse::Records::new(detail);
+        records.set_dialect(&no_customer_ref);
+        match records.next() {
+            Some(Ok((_, ast::RawRecord::TransactionDetail(td), _))) => {
+                assert_eq!(td.bank_ref_num, Some(&b"REF123"[..]));
+                assert_eq!(td.customer_ref_num, None);
+                assert_eq!(td.text, Some(vec![&b"PROCEEDS/"[..]]));
+            }
+            r => panic!("expected a transaction detail, got {:?}", r),
+        }
+
+        struct Tolerant;
+        impl Dialect for Tolerant {
+            fn tolerate(&self, err: &ConvertError) -> bool {
+                match *err {
+                    ConvertError::TransactionDetail { .. } |
+                    ConvertError::Group { err: GroupConvError::ControlTotal { .. }, .. } |
+                    ConvertError::File(FileConvError::RecordsNum { .. }, _) => true,
+                    _ => false,
+                }
+            }
+        }
+        let custom = SPEC_EXAMPLE
+            .replace("16,195,10000000", "16,800,10000000")
+            .replace("98,180000000,1,8/", "98,180000001,1,8/")
+            .replace("99,345450000,4,31/", "99,345450000,4,30/");
+        assert!(data::File::process(custom.as_bytes()).is_err());
+        let dialects = Dialects::new(Tolerant);
+        let (_, warnings) =
+            data::File::process_with_dialects(custom.as_bytes(), &options, &dialects).unwrap();
+        let lines = warnings.iter().map(|w| w.position().line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![18, 20, 31]);
+    }
+
+    #[test]
+    fn type_codes_spec_example() {
+        use std::convert::TryFrom;
+        use data::{Direction, Level, TypeCode};
+
+        let file = data::File::process(SPEC_EXAMPLE.as_bytes()).unwrap();
+        let code = file.groups[0].accounts[0].transaction_details[0].code;
+        assert_eq!(code.to_string(), "115 Lockbox Deposit");
+        let info = code.info().unwrap();
+        assert_eq!(info.level, Level::Detail);
+        assert_eq!(info.direction, Direction::Credit);
+        assert_eq!(info.section, "Lockbox");
+        assert!(!info.loan);
+
+        let info = TypeCode::lookup(760).unwrap();
+        assert_eq!(
+            (info.level, info.direction, info.loan),
+            (Level::Summary, Direction::Debit, true)
+        );
+        assert_eq!(TypeCode::lookup(890).unwrap().direction, Direction::NonMonetary);
+        assert_eq!(TypeCode::lookup(925), None);
+
+        for code in 0..1000 {
+            let known = match TypeCode::lookup(code).map(|info| info.level) {
+                Some(Level::Status) => data::StatusCode::try_from(code).is_ok(),
+                Some(Level::Summary) => data::SummaryCode::try_from(code).is_ok(),
+                Some(Level::Detail) => data::DetailCode::try_from(code).is_ok(),
+                None => continue,
+            };
+            assert!(known, "{:03} is listed but not defined", code);
+        }
+    }
+
+    #[test]
+    fn type_code_registry_spec_example() {
+        use std::convert::TryFrom;
+        use data::{CustomCode, DetailCode, Direction, Level, StatusCode, SummaryCode, TypeCode,
+                   TypeCodeRegistry};
+        use dialect::{Dialects, Profile};
+
+        let custom = SPEC_EXAMPLE
+            .replace("16,115,450000,", "16,925,450000,")
+            .replace("16,195,", "16,197,")
+            .replace("03,4589761203,,010,", "03,4589761203,,098,");
+        for &code in &[197, 98] {
+            assert_eq!(TypeCode::lookup(code), None);
+        }
+        assert!(data::File::process(custom.as_bytes()).is_err());
+
+        let csv = "code,level,direction,description\n\
+                   # Our own codes\n\
+                   925,detail,debit,\"Sweep, Outgoing\"\n\
+                   197,Detail,CR,Loan Proceeds\n\
+                   072,summary,credit,Float Credits\n";
+        let mut type_codes = TypeCodeRegistry::from_csv(csv).unwrap();
+        type_codes.insert(CustomCode {
+            code: 98,
+            level: Level::Status,
+            direction: Direction::NonMonetary,
+            description: "Target Balance".to_owned(),
+        }).unwrap();
+        assert_eq!(type_codes.len(), 4);
+        assert_eq!(type_codes.get(925, Level::Detail).unwrap().description, "Sweep, Outgoing");
+        assert_eq!(type_codes.get(925, Level::Detail).unwrap().direction, Direction::Debit);
+        assert!(TypeCodeRegistry::from_csv("925,detail,sideways,Sweep").is_err());
+        for &bad in &["925,summary,na,Memo", "905,status,credit,Memo", "1000,detail,debit,Memo"] {
+            assert!(TypeCodeRegistry::from_csv(bad).is_err(), "{} was registered", bad);
+        }
+
+        let mut dialects = Dialects::default();
+        dialects.register(
+            "122099999",
+            Profile {
+                type_codes: type_codes.clone(),
+                ..Profile::default()
+            },
+        );
+        let options = data::ParseOptions::default();
+        let (file, warnings) =
+            data::File::process_with_dialects(custom.as_bytes(), &options, &dialects).unwrap();
+        assert!(warnings.is_empty());
+        let code = file.groups[0].accounts[0].transaction_details[0].code;
+        assert_eq!(code, DetailCode::Registered(type_codes.get(925, Level::Detail).unwrap()));
+        assert_eq!(code.to_string(), "925 Sweep, Outgoing");
+        assert_eq!(code.direction(), Direction::Debit);
+        assert_eq!(type_codes.description(TypeCode::Detail(code)), Some("Sweep, Outgoing"));
+        let code = file.groups[1].accounts[0].transaction_details[1].code;
+        assert_eq!(code.info().unwrap().description, "Loan Proceeds");
+        assert_eq!(code.direction(), Direction::Credit);
+        match file.groups[1].accounts[0].infos[0] {
+            data::AccountInfo::Status { code, .. } => {
+                assert_eq!(code, StatusCode::Registered(code.info().unwrap()));
+                assert_eq!(u16::from(code), 98);
+                assert_eq!(code.info().unwrap().description, "Target Balance");
+            }
+            ref info => panic!("expected a status, got {:?}", info),
+        }
+        // 072 is a status code in the spec, but the registry comes first.
+        match file.groups[0].accounts[0].infos[2] {
+            data::AccountInfo::Summary { code, .. } => {
+                assert_eq!(code, SummaryCode::Registered(code.info().unwrap()));
+                assert_eq!(code.to_string(), "072 Float Credits");
+                assert_eq!(code.direction(), Direction::Credit);
+            }
+            ref info => panic!("expected a summary, got {:?}", info),
+        }
+        let ledger = TypeCode::Status(StatusCode::try_from(10).unwrap());
+        assert_eq!(type_codes.description(ledger), Some("Opening Ledger"));
+    }
+
+    #[cfg(feature = "toml-registry")]
+    #[test]
+    fn type_code_registry_from_toml() {
+        use data::{Direction, Level, TypeCodeRegistry};
+
+        let toml = r#"
+            [[codes]]
+            code = 98 # undefined by the spec
+            level = "status"
+            direction = 'non-monetary'
+            description = "Target \u0042alance"
+
+            [[codes]]
+            code = 925
+            level = "Detail"
+            direction = "DB"
+            description = '''
+Sweep, Outgoing'''
+        "#;
+        let type_codes = TypeCodeRegistry::from_toml(toml).unwrap();
+        assert_eq!(type_codes.len(), 2);
+        assert_eq!(type_codes.get(98, Level::Status).unwrap().description, "Target Balance");
+        assert_eq!(type_codes.get(925, Level::Detail).unwrap().description, "Sweep, Outgoing");
+        assert_eq!(type_codes.get(925, Level::Detail).unwrap().direction, Direction::Debit);
+
+        let inline = "codes = [{ code = 197, level = \"detail\", direction = \"cr\" }]";
+        let type_codes = TypeCodeRegistry::from_toml(inline).unwrap();
+        assert_eq!(type_codes.get(197, Level::Detail).unwrap().direction, Direction::Credit);
+
+        for &bad in &[
+            "[[codes]]\nlevel = \"detail\"",
+            "[[codes]]\ncode = 925\nlevel = \"detail\"\ndirection = \"sideways\"",
+            "[[codes]]\ncode = 905\nlevel = \"status\"\ndirection = \"credit\"",
+            "[[codes]]\ncode = 925\nlevel = \"detail\"\ndirection = \"credit\"\ncolor = 1",
+            "[[bank]]\nname = \"Example\"",
+        ] {
+            assert!(TypeCodeRegistry::from_toml(bad).is_err(), "{:?} was registered", bad);
+        }
+        let err = TypeCodeRegistry::from_toml("[[codes]]\ncode = = 925").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        let err = TypeCodeRegistry::from_toml("[[codes]]\ncode = \"925\"").unwrap_err();
+        assert!(err.to_string().contains("codes.code"), "{}", err);
+    }
+
+    #[test]
+    fn signed_amount_spec_example() {
+        use data::AccountInfo;
+
+        let debit = SPEC_EXAMPLE.replace("16,195,", "16,495,");
+        let file = data::File::process(debit.as_bytes()).unwrap();
+        let details = &file.groups[1].accounts[0].transaction_details;
+        assert_eq!(details[0].signed_amount().unwrap().unwrap().value, 20000000);
+        assert_eq!(details[1].signed_amount().unwrap().unwrap().value, -10000000);
+        let net: i64 = details
+            .iter()
+            .filter_map(|td| td.signed_amount().unwrap())
+            .map(|a| a.value)
+            .sum();
+        assert_eq!(net, 10000000);
+
+        let infos = &file.groups[0].accounts[1].infos;
+        let signed: Vec<_> = infos
+            .iter()
+            .map(|info| info.signed_amount().unwrap().map(|a| a.value))
+            .collect();
+        assert_eq!(
+            signed,
+            vec![
+                Some(-500000),
+                Some(1000000),
+                Some(-2000000),
+                Some(500000),
+                Some(1000000),
+                Some(500000),
+                Some(500000),
+                Some(-1500000),
+            ]
+        );
+        match infos[2] {
+            AccountInfo::Summary { amount, .. } => assert_eq!(amount.unwrap().value, 2000000),
+            ref info => panic!("expected a summary, got {:?}", info),
+        }
+
+        let mut non_monetary = details[1].clone();
+        non_monetary.code = data::DetailCode::NonMonetary;
+        assert_eq!(non_monetary.signed_amount(), Ok(None));
+
+        let mut overflowing = details[1].clone();
+        overflowing.amount = Some(data::Amount::new(i64::min_value(), penny::Currency::USD));
+        assert!(overflowing.signed_amount().is_err());
+        let mut file = file;
+        file.groups[1].accounts[0].transaction_details[1] = overflowing;
+        let mut out = Vec::new();
+        let err = csv::write_transactions(&file, &Default::default(), &mut out).unwrap_err();
+        assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn records_num_spec_example() {
+        use ast::convert::{AccountConvError, ConvertError, FileConvError, GroupConvError};
+        use data::FileProcessError as FPE;
+
+        let corrupt = SPEC_EXAMPLE.replace("49,9150000,4/", "49,9150000,3/");
+        match data::File::process(corrupt.as_bytes()) {
+            Err(FPE::Conversion(p, ConvertError::Account {
+                err: AccountConvError::RecordsNum { expected: 3, actual: 4 },
+                ..
+            })) => assert_eq!(p.line, 6),
+            r => panic!("expected an account records number error, got {:?}", r),
+        }
+
+        let corrupt = SPEC_EXAMPLE.replace("98,180000000,1,8/", "98,180000000,1,7/");
+        match data::File::from_source(&mut corrupt.as_bytes()) {
+            Err(read::ReadError::Process(FPE::Conversion(p, ConvertError::Group {
+                group: 1,
+                err: GroupConvError::RecordsNum { expected: 7, actual: 8 },
+                position,
+            }))) => assert_eq!((p.line, position), (20, Some(p))),
+            r => panic!("expected a group records number error, got {:?}", r),
+        }
+
+        let corrupt = SPEC_EXAMPLE.replace("99,345450000,4,31/", "99,345450000,4,30/");
+        match data::File::process(corrupt.as_bytes()) {
+            Err(FPE::Conversion(_, ConvertError::File(FileConvError::RecordsNum {
+                expected: 30,
+                actual: 31,
+            }, position))) => assert_eq!(position.map(|p| p.line), Some(31)),
+            r => panic!("expected a file records number error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn visit_spec_example() {
+        #[derive(Default)]
+        struct Counter {
+            accounts: usize,
+            transaction_details: usize,
+            amount: i64,
+        }
+        impl ast::convert::Visitor for Counter {
+            fn visit_account(&mut self, _account: &data::Account) {
+                self.accounts += 1;
+            }
+            fn visit_transaction_detail(&mut self, td: &data::TransactionDetail) {
+                self.transaction_details += 1;
+                self.amount += td.amount.map_or(0, |a| a.value);
+            }
+        }
+
+        let mut counter = Counter::default();
+        let file = read::RecordReader::new(SPEC_EXAMPLE.as_bytes())
+            .visit_file(&mut counter)
+            .unwrap();
+        assert!(file.groups.is_empty());
+        assert_eq!(counter.accounts, 5);
+        assert_eq!(counter.transaction_details, 4);
+        assert_eq!(counter.amount, 30950000);
+    }
 }
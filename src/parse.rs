@@ -1,13 +1,18 @@
+use std::error;
+use std::fmt;
+
 use nom::{self, ErrorKind, IResult};
 
 use ast;
+use dialect::{Dialect, Spec};
 
+const UNEXPECTED_CHAR: u32 = 100;
 fn u8_char(input: &[u8], ch: u8) -> IResult<&[u8], u8> {
     if let Some(first) = input.first() {
         if *first == ch {
             IResult::Done(&input[1..], ch)
         } else {
-            IResult::Error(error_position!(ErrorKind::Custom(100), input))
+            IResult::Error(error_position!(ErrorKind::Custom(UNEXPECTED_CHAR), input))
         }
     } else {
         IResult::Incomplete(nom::Needed::Size(1))
@@ -52,12 +57,44 @@ named!(
     field_sep<FieldSep>,
     alt!(
         value!(FieldSep::Normal, field_sep_char) |
-        value!(FieldSep::Continuation, tuple!(record_sep, call!(nom::eol), continuation))
+        value!(
+            FieldSep::Continuation,
+            tuple!(opt!(record_sep), call!(nom::eol), continuation)
+        )
     )
 );
 named!(record_sep<u8>, terminated!(record_sep_char, many0!(space_char)));
+const MISSING_RECORD_SEP: u32 = 101;
+/// Ends a record at a record separator, or at the end of a line that isn't continued. Records
+/// missing their separator are caught by `missing_record_sep`.
+fn record_end(input: &[u8]) -> IResult<&[u8], ()> {
+    if let IResult::Done(rest, _) = record_sep(input) {
+        return IResult::Done(rest, ());
+    }
+    if input.is_empty() {
+        return IResult::Done(input, ());
+    }
+    match nom::eol(input) {
+        IResult::Done(rest, _) if !rest.starts_with(b"88,") => IResult::Done(input, ()),
+        IResult::Done(..) => {
+            IResult::Error(error_position!(ErrorKind::Custom(UNEXPECTED_CHAR), input))
+        }
+        IResult::Error(e) => IResult::Error(e),
+        IResult::Incomplete(n) => IResult::Incomplete(n),
+    }
+}
+/// Like `record_end`, but also takes a field separator before the end of the record if
+/// `dialect` expects one.
+fn record_end_with<'a>(input: &'a [u8], dialect: &Dialect) -> IResult<&'a [u8], ()> {
+    if dialect.trailing_field_sep() && input.first() == Some(&FIELD_SEP_CHAR) {
+        if let IResult::Done(rest, ()) = record_end(&input[1..]) {
+            return IResult::Done(rest, ());
+        }
+    }
+    record_end(input)
+}
 fn is_field_char(c: u8) -> bool {
-    c != FIELD_SEP_CHAR && c != RECORD_SEP_CHAR
+    c != FIELD_SEP_CHAR && c != RECORD_SEP_CHAR && c != b'\n' && c != b'\r'
 }
 named!(field_inner, take_while1!(is_field_char));
 
@@ -140,6 +177,20 @@ macro_rules! many0_cond(
 );
 named!(text<Vec<&[u8]>>, preceded!(peek!(text_start_char), many0_cond!(text_line)));
 
+/// Text that starts with a record separator, if `dialect` expects it, and has more than spaces
+/// after it.
+fn slash_text<'a>(input: &'a [u8], dialect: &Dialect) -> IResult<&'a [u8], Vec<&'a [u8]>> {
+    let line_len = input.iter().position(|&c| !is_text_char(c)).unwrap_or(input.len());
+    let line = &input[..line_len];
+    if dialect.slash_in_text() && line.first() == Some(&RECORD_SEP_CHAR) &&
+        line[1..].iter().any(|&c| c != b' ')
+    {
+        text(input)
+    } else {
+        IResult::Error(error_position!(ErrorKind::Custom(UNEXPECTED_CHAR), input))
+    }
+}
+
 named!(
     distributed_avail_distribution_inner<ast::RawDistributedAvailDistribution>,
     do_parse!(
@@ -152,6 +203,10 @@ named!(
     )
 );
 
+// Errors in the fields after an S, V or D funds type.
+const S_FUNDS_TYPE: u32 = 105;
+const V_FUNDS_TYPE: u32 = 106;
+const D_FUNDS_TYPE: u32 = 107;
 named!(
     funds_type_inner<ast::RawFundsType>,
     alt!(
@@ -159,7 +214,7 @@ named!(
         value!(ast::RawFundsType::ImmediateAvail, call!(u8_char, b'0')) |
         value!(ast::RawFundsType::OneDayAvail, call!(u8_char, b'1')) |
         value!(ast::RawFundsType::TwoOrMoreDaysAvail, call!(u8_char, b'2')) |
-        preceded!(call!(u8_char, b'S'), return_error!(ErrorKind::Custom(105), do_parse!(
+        preceded!(call!(u8_char, b'S'), return_error!(ErrorKind::Custom(S_FUNDS_TYPE), do_parse!(
             field_sep >>
             immediate: opt!(field_inner) >> field_sep >>
             one_day: opt!(field_inner) >> field_sep >>
@@ -170,7 +225,7 @@ named!(
                 more_than_one_day,
             })
         ))) |
-        preceded!(call!(u8_char, b'V'), return_error!(ErrorKind::Custom(106), do_parse!(
+        preceded!(call!(u8_char, b'V'), return_error!(ErrorKind::Custom(V_FUNDS_TYPE), do_parse!(
             field_sep >>
             date: field_inner >> field_sep >>
             time: opt!(field_inner) >>
@@ -179,7 +234,7 @@ named!(
                 time,
             })
         ))) |
-        preceded!(call!(u8_char, b'D'), return_error!(ErrorKind::Custom(107), do_parse!(
+        preceded!(call!(u8_char, b'D'), return_error!(ErrorKind::Custom(D_FUNDS_TYPE), do_parse!(
             field_sep >>
             num: field_inner >> field_sep >>
             dists: separated_nonempty_list!(field_sep, distributed_avail_distribution_inner) >>
@@ -207,9 +262,38 @@ named!(
     )
 );
 
-named!(
-    pub record<ast::RawRecord>,
+pub fn record(input: &[u8]) -> IResult<&[u8], ast::RawRecord> {
+    record_with(input, &Spec)
+}
+
+/// Parses a record, allowing for the deviations from the spec that `dialect` expects.
+pub fn record_with<'a>(
+    input: &'a [u8],
+    dialect: &Dialect,
+) -> IResult<&'a [u8], ast::RawRecord<'a>> {
+    match record_lenient(input, dialect) {
+        IResult::Done(rest, record) => {
+            let consumed = &input[..input.len() - rest.len()];
+            match missing_record_sep(consumed, &record) {
+                Some(end) if !dialect.missing_record_sep() => {
+                    let kind = ErrorKind::Custom(MISSING_RECORD_SEP);
+                    IResult::Error(error_position!(kind, &input[end..]))
+                }
+                _ => IResult::Done(rest, record),
+            }
+        }
+        result => result,
+    }
+}
+
+/// Like `record_with`, but also parses records missing their record separators, for callers
+/// that report them with `missing_record_sep` when processing leniently.
+pub fn record_lenient<'a>(
+    input: &'a [u8],
+    dialect: &Dialect,
+) -> IResult<&'a [u8], ast::RawRecord<'a>> {
     alt!(
+        input,
         preceded!(tag!(b"01"), return_error!(ErrorKind::Custom(1), do_parse!(
             field_sep >>
             sender_ident: field_inner >> field_sep >>
@@ -220,7 +304,7 @@ named!(
             physical_record_len: opt!(field_inner) >> field_sep >>
             block_size: opt!(field_inner) >> field_sep >>
             version_number: field_inner >>
-            record_sep >>
+            call!(record_end_with, dialect) >>
             (ast::RawRecord::FileHeader(ast::RawFileHeader {
                 sender_ident,
                 receiver_ident,
@@ -241,7 +325,7 @@ named!(
             as_of_time: opt!(field_inner) >> field_sep >>
             currency: opt!(field_inner) >> field_sep >>
             as_of_date_mod: opt!(field_inner) >>
-            record_sep >>
+            call!(record_end_with, dialect) >>
             (ast::RawRecord::GroupHeader(ast::RawGroupHeader {
                 ultimate_receiver_ident,
                 originator_ident,
@@ -257,7 +341,7 @@ named!(
             customer_account_num: field_inner >> field_sep >>
             currency: opt!(field_inner) >> field_sep >>
             infos: separated_nonempty_list!(field_sep, account_info_inner) >>
-            record_sep >>
+            call!(record_end_with, dialect) >>
             (ast::RawRecord::AccountIdent(ast::RawAccountIdent {
                 customer_account_num,
                 currency,
@@ -270,14 +354,21 @@ named!(
             amount: opt!(field_inner) >> field_sep >>
             funds_type: opt!(funds_type_inner) >> field_sep >>
             bank_ref_num: opt!(field_inner) >> field_sep >>
-            customer_ref_num: opt!(field_inner) >> field_sep >>
-            txt: alt!(value!(None, record_sep) | map!(text, Some)) >>
+            customer_ref_num: cond!(
+                !dialect.no_customer_ref(),
+                terminated!(opt!(field_inner), field_sep)
+            ) >>
+            txt: alt!(
+                map!(call!(slash_text, dialect), Some) |
+                value!(None, call!(record_end_with, dialect)) |
+                map!(text, Some)
+            ) >>
             (ast::RawRecord::TransactionDetail(ast::RawTransactionDetail {
                 type_code,
                 amount,
                 funds_type,
                 bank_ref_num,
-                customer_ref_num,
+                customer_ref_num: customer_ref_num.and_then(|c| c),
                 text: txt,
             }))
         ))) |
@@ -285,7 +376,7 @@ named!(
             field_sep >>
             control_total: field_inner >> field_sep >>
             records_num: field_inner >>
-            record_sep >>
+            call!(record_end_with, dialect) >>
             (ast::RawRecord::AccountTrailer(ast::RawAccountTrailer {
                 control_total,
                 records_num,
@@ -296,7 +387,7 @@ named!(
             control_total: field_inner >> field_sep >>
             accounts_num: field_inner >> field_sep >>
             records_num: field_inner >>
-            record_sep >>
+            call!(record_end_with, dialect) >>
             (ast::RawRecord::GroupTrailer(ast::RawGroupTrailer {
                 control_total,
                 accounts_num,
@@ -308,7 +399,7 @@ named!(
             control_total: field_inner >> field_sep >>
             groups_num: field_inner >> field_sep >>
             records_num: field_inner >>
-            record_sep >>
+            call!(record_end_with, dialect) >>
             (ast::RawRecord::FileTrailer(ast::RawFileTrailer {
                 control_total,
                 groups_num,
@@ -316,9 +407,357 @@ named!(
             }))
         )))
     )
-);
+}
 
 named!(
     pub file<Vec<ast::RawRecord>>,
     many0!(terminated!(record, end_of_line))
 );
+
+/// Describes a record parsing error.
+pub fn describe_error(kind: &ErrorKind) -> String {
+    match *kind {
+        ErrorKind::Custom(code @ 1...99) => format!("malformed {:02} record", code),
+        ErrorKind::Custom(MISSING_RECORD_SEP) => "missing record separator".to_owned(),
+        ErrorKind::Custom(S_FUNDS_TYPE) |
+        ErrorKind::Custom(V_FUNDS_TYPE) |
+        ErrorKind::Custom(D_FUNDS_TYPE) => "malformed funds type".to_owned(),
+        ErrorKind::Custom(UNEXPECTED_CHAR) => "unexpected character".to_owned(),
+        ErrorKind::Custom(code) => format!("malformed record (error {})", code),
+        ErrorKind::Complete => "record ended unexpectedly".to_owned(),
+        ErrorKind::Eof => "unexpected data after the end of the record".to_owned(),
+        ref kind => format!("malformed record ({})", kind.description()),
+    }
+}
+
+/// Where something is in the input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Position {
+    /// The type code of the record, such as 16 for a transaction detail, if it has one.
+    pub record_code: Option<u8>,
+    /// Zero-based byte offset from the start of the input.
+    pub offset: usize,
+    /// One-based physical record (line) number.
+    pub line: usize,
+    /// One-based byte column in the physical record.
+    pub column: usize,
+}
+
+impl Position {
+    pub fn start() -> Self {
+        Position {
+            record_code: None,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// The position after `consumed`, which starts at this position.
+    pub fn advance(self, consumed: &[u8]) -> Self {
+        let mut position = Position {
+            offset: self.offset + consumed.len(),
+            ..self
+        };
+        match consumed.iter().rposition(|&c| c == b'\n') {
+            Some(i) => {
+                position.line += consumed.iter().filter(|&&c| c == b'\n').count();
+                position.column = consumed.len() - i;
+            }
+            None => position.column += consumed.len(),
+        }
+        position
+    }
+
+    /// The position of `field`, a slice of `input`, which starts at this position.
+    pub fn locate(self, input: &[u8], field: &[u8]) -> Self {
+        let start = input.as_ptr() as usize;
+        let field_start = field.as_ptr() as usize;
+        if field_start >= start && field_start <= start + input.len() {
+            self.advance(&input[..field_start - start])
+        } else {
+            self
+        }
+    }
+
+    /// This position at the start of `record`, tagged with its type code.
+    pub fn record(self, record: &[u8]) -> Self {
+        Position {
+            record_code: record_code(record),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if let Some(code) = self.record_code {
+            write!(f, " ({:02} record)", code)?;
+        }
+        Ok(())
+    }
+}
+
+fn record_code(record: &[u8]) -> Option<u8> {
+    match (record.get(0), record.get(1)) {
+        (Some(&a @ b'0'...b'9'), Some(&b @ b'0'...b'9')) => Some((a - b'0') * 10 + (b - b'0')),
+        _ => None,
+    }
+}
+
+/// The physical record layout declared in a file header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Layout {
+    pub physical_record_len: Option<u16>,
+    pub block_size: Option<u16>,
+}
+
+impl Layout {
+    pub fn from_header(header: &ast::ParsedFileHeader) -> Self {
+        Layout {
+            physical_record_len: header.physical_record_len,
+            block_size: header.block_size,
+        }
+    }
+
+    /// Checks that every physical record in `input`, which starts at `position`, fits within
+    /// the physical record length.
+    pub fn check_lines(&self, position: Position, input: &[u8]) -> Result<(), LayoutError> {
+        let max = match self.physical_record_len {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+        let mut position = position;
+        for line in input.split(|&c| c == b'\n') {
+            let len = if line.last() == Some(&b'\r') {
+                line.len() - 1
+            } else {
+                line.len()
+            };
+            if len > usize::from(max) {
+                return Err(LayoutError::PhysicalRecordLen(
+                    position.advance(&line[..usize::from(max)]),
+                    len,
+                    max,
+                ));
+            }
+            position = position.advance(line).advance(b"\n");
+        }
+        Ok(())
+    }
+
+    /// Checks that `physical_records` physical records fill whole blocks, ending at `position`.
+    pub fn check_blocks(&self, position: Position, physical_records: usize) -> Result<(), LayoutError> {
+        match self.block_size {
+            Some(block_size) if block_size > 0 &&
+                                physical_records % usize::from(block_size) != 0 => {
+                Err(LayoutError::BlockSize(position, physical_records, block_size))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Physical records that don't match the layout declared in the file header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum LayoutError {
+    /// A physical record's length, and the declared maximum.
+    PhysicalRecordLen(Position, usize, u16),
+    /// A fixed-length physical record's length cut short by the end of the input, and the
+    /// declared length.
+    PartialRecord(Position, usize, u16),
+    /// The number of physical records, which don't fill whole blocks of the declared size.
+    BlockSize(Position, usize, u16),
+    /// Fixed-length input without a declared physical record length.
+    NoPhysicalRecordLen(Position),
+}
+impl LayoutError {
+    pub fn position(&self) -> Position {
+        match *self {
+            LayoutError::PhysicalRecordLen(p, _, _) |
+            LayoutError::PartialRecord(p, _, _) |
+            LayoutError::BlockSize(p, _, _) |
+            LayoutError::NoPhysicalRecordLen(p) => p,
+        }
+    }
+}
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LayoutError::PhysicalRecordLen(_, len, max) => {
+                write!(
+                    f,
+                    "physical record is {} characters long, longer than the declared {}",
+                    len,
+                    max
+                )
+            }
+            LayoutError::PartialRecord(_, len, max) => {
+                write!(f, "physical record is only {} of the declared {} characters", len, max)
+            }
+            LayoutError::BlockSize(_, records, block_size) => {
+                write!(
+                    f,
+                    "{} physical records don't fill whole blocks of the declared {}",
+                    records,
+                    block_size
+                )
+            }
+            LayoutError::NoPhysicalRecordLen(_) => {
+                f.write_str("fixed-length input needs a physical record length in the file header")
+            }
+        }
+    }
+}
+impl error::Error for LayoutError {}
+
+/// Finds the end of the first physical record in `record`, which was parsed as `raw`, that
+/// should've ended with a record separator but didn't.
+pub fn missing_record_sep(record: &[u8], raw: &ast::RawRecord) -> Option<usize> {
+    let text = match *raw {
+        ast::RawRecord::TransactionDetail(ast::RawTransactionDetail { text: Some(ref text), .. }) => {
+            &text[..]
+        }
+        _ => &[],
+    };
+    let mut start = 0;
+    for line in record.split(|&c| c == b'\n') {
+        let line_start = start;
+        start += line.len() + 1;
+        let line = if line.last() == Some(&b'\r') {
+            &line[..line.len() - 1]
+        } else {
+            line
+        };
+        // Text runs to the end of its line, so text lines don't end with separators.
+        let line_end = line[line.len()..].as_ptr();
+        if text.iter().any(|t| t[t.len()..].as_ptr() == line_end) {
+            continue;
+        }
+        let trimmed = match line.iter().rposition(|&c| c != b' ') {
+            Some(i) => &line[..i + 1],
+            None => continue,
+        };
+        if trimmed.last() != Some(&RECORD_SEP_CHAR) {
+            return Some(line_start + trimmed.len());
+        }
+    }
+    None
+}
+
+/// The number of 88 continuation records in `record`.
+pub fn continuations(record: &[u8]) -> usize {
+    record.windows(4).filter(|w| w == b"\n88,").count()
+}
+
+/// Parses records one at a time from a whole file, keeping track of where each one starts and
+/// how many 88 records it was continued with.
+pub struct Records<'a> {
+    input: &'a [u8],
+    position: Position,
+    recovering: bool,
+    lenient: bool,
+    dialect: &'a Dialect,
+}
+
+impl<'a> Records<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Records {
+            input,
+            position: Position::start(),
+            recovering: false,
+            lenient: false,
+            dialect: &Spec,
+        }
+    }
+
+    /// Creates an iterator that skips past records that can't be parsed, along with their 88
+    /// records, instead of stopping at them.
+    pub fn recovering(input: &'a [u8]) -> Self {
+        Records {
+            recovering: true,
+            ..Records::new(input)
+        }
+    }
+
+    /// Sets whether records missing their record separators are parsed anyway, for callers that
+    /// report them themselves with `missing_record_sep`.
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Sets the deviations from the spec to allow for in the records that are left.
+    pub fn set_dialect(&mut self, dialect: &'a Dialect) {
+        self.dialect = dialect;
+    }
+
+    /// The position of the rest of the input.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<(Position, ast::RawRecord<'a>, usize), (Position, ErrorKind)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+        let input = self.input;
+        let position = self.position.record(input);
+        // The final record doesn't need to be followed by a line ending.
+        let parsed = if self.lenient {
+            record_lenient(input, self.dialect)
+        } else {
+            record_with(input, self.dialect)
+        };
+        let result = match parsed {
+            IResult::Done(rest, record) => {
+                match end_of_line(rest) {
+                    IResult::Done(rest, _) => Ok((rest, record)),
+                    IResult::Incomplete(_) if rest.is_empty() => Ok((rest, record)),
+                    IResult::Incomplete(_) => Err(ErrorKind::Complete),
+                    IResult::Error(kind) => Err(kind),
+                }
+            }
+            IResult::Error(kind) => Err(kind),
+            IResult::Incomplete(_) => Err(ErrorKind::Complete),
+        };
+        Some(match result {
+            Ok((rest, record)) => {
+                let consumed = &input[..input.len() - rest.len()];
+                self.position = self.position.advance(consumed);
+                self.input = rest;
+                Ok((position, record, continuations(consumed)))
+            }
+            Err(kind) => {
+                if self.recovering {
+                    let skipped = &input[..unparsed_len(input)];
+                    self.position = self.position.advance(skipped);
+                    self.input = &input[skipped.len()..];
+                } else {
+                    self.input = &[];
+                }
+                Err((position, kind))
+            }
+        })
+    }
+}
+
+/// The length of the record at the start of `input` that couldn't be parsed, up to the first
+/// line ending that isn't followed by an 88 record.
+fn unparsed_len(input: &[u8]) -> usize {
+    let mut end = 0;
+    while let Some(line_len) = input[end..].iter().position(|&c| c == b'\n') {
+        end += line_len + 1;
+        if !input[end..].starts_with(b"88,") {
+            return end;
+        }
+    }
+    input.len()
+}
@@ -0,0 +1,495 @@
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::mem;
+use std::str;
+use std::sync::Arc;
+
+use nom::{ErrorKind, IResult};
+
+use ast::{self, ParsedRecord, RawRecord};
+use ast::convert::{Converter, ConverterOutput, Visitor};
+use ast::parse::Parsed;
+use data::{self, FileProcessError, Strictness, Warning};
+use dialect::{Dialect, Dialects, Spec};
+use parse::{self, Layout, LayoutError, Position};
+
+const CONTINUATION: &[u8] = b"88,";
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Process(FileProcessError),
+}
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref e) => write!(f, "error reading input: {}", e),
+            ReadError::Process(ref e) => e.fmt(f),
+        }
+    }
+}
+impl error::Error for ReadError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            ReadError::Io(ref e) => Some(e),
+            ReadError::Process(ref e) => Some(e),
+        }
+    }
+}
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+impl From<FileProcessError> for ReadError {
+    fn from(e: FileProcessError) -> Self {
+        ReadError::Process(e)
+    }
+}
+
+/// Reads records one logical record at a time, joined with their 88 continuation records, so
+/// only a single record is held in memory at once.
+pub struct RecordReader<R> {
+    inner: R,
+    record: Vec<u8>,
+    next_line: Vec<u8>,
+    position: Position,
+    record_position: Position,
+    next_position: Position,
+    continuations: usize,
+    fixed_length: bool,
+    check_layout: bool,
+    layout: Layout,
+    physical_records: usize,
+    strictness: Strictness,
+    warnings: Vec<Warning>,
+    recovery: bool,
+    errors: Vec<FileProcessError>,
+    dialects: Dialects,
+    dialect: Arc<Dialect>,
+    io_failed: bool,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(inner: R) -> Self {
+        RecordReader {
+            inner,
+            record: Vec::new(),
+            next_line: Vec::new(),
+            position: Position::start(),
+            record_position: Position::start(),
+            next_position: Position::start(),
+            continuations: 0,
+            fixed_length: false,
+            check_layout: false,
+            layout: Layout::default(),
+            physical_records: 0,
+            strictness: Strictness::Strict,
+            warnings: Vec::new(),
+            recovery: false,
+            errors: Vec::new(),
+            dialects: Dialects::default(),
+            dialect: Arc::new(Spec),
+            io_failed: false,
+        }
+    }
+
+    /// Creates a reader for fixed-length, space-padded physical records without line endings,
+    /// as split by the physical record length in the file header.
+    pub fn fixed_length(inner: R) -> Self {
+        RecordReader {
+            fixed_length: true,
+            ..RecordReader::new(inner)
+        }
+    }
+
+    /// Sets whether physical records are checked against the physical record length and block
+    /// size in the file header. Block sizes are only checked for fixed-length input.
+    pub fn set_check_layout(&mut self, check_layout: bool) {
+        self.check_layout = check_layout;
+    }
+
+    /// Sets whether spec violations that still leave a usable file are errors, or are only
+    /// collected as warnings.
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+
+    /// Takes the warnings tolerated while reading leniently so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        mem::replace(&mut self.warnings, Vec::new())
+    }
+
+    /// Sets whether reading a file carries on past records that can't be parsed or converted,
+    /// skipping the accounts and groups they're in. Errors reading the input or in its layout
+    /// still end reading.
+    pub fn set_recovery(&mut self, recovery: bool) {
+        self.recovery = recovery;
+    }
+
+    /// Takes the errors recovered from so far.
+    pub fn take_errors(&mut self) -> Vec<FileProcessError> {
+        mem::replace(&mut self.errors, Vec::new())
+    }
+
+    /// Sets the dialects to expect, picked by the sender ident in each file header.
+    pub fn set_dialects(&mut self, dialects: Dialects) {
+        self.dialects = dialects;
+    }
+
+    /// The layout declared in the file header, once it's been read.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// The position of the last record read.
+    pub fn position(&self) -> Position {
+        self.record_position
+    }
+
+    /// The number of 88 records the last record read was continued with.
+    pub fn continuations(&self) -> usize {
+        self.continuations
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next non-empty physical record into `next_line`, returning `false` at the end
+    /// of the input.
+    fn read_line(&mut self) -> Result<bool, ReadError> {
+        loop {
+            let start = self.position;
+            self.next_line.clear();
+            if self.fixed_length {
+                if !self.read_fixed_line()? {
+                    return Ok(false);
+                }
+            } else {
+                if self.inner.read_until(b'\n', &mut self.next_line)? == 0 {
+                    return Ok(false);
+                }
+                self.physical_records += 1;
+                self.position = start.advance(&self.next_line);
+            }
+            if self.next_line.iter().any(|&c| c != b'\n' && c != b'\r') {
+                self.next_position = start;
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Reads a fixed-length physical record into `next_line`, replacing its padding with a line
+    /// ending.
+    fn read_fixed_line(&mut self) -> Result<bool, ReadError> {
+        let start = self.position;
+        let len = match self.layout.physical_record_len {
+            Some(len) => len,
+            None => {
+                // The file header has to fit in the first physical record, so its physical
+                // record length can be read before splitting anything.
+                if self.inner.read_until(b'/', &mut self.next_line)? == 0 {
+                    return Ok(false);
+                }
+                let len = header_physical_record_len(&self.next_line)
+                    .ok_or(LayoutError::NoPhysicalRecordLen(start))
+                    .map_err(FileProcessError::Layout)?;
+                if self.next_line.len() > usize::from(len) {
+                    let position = start.advance(&self.next_line[..usize::from(len)]);
+                    let err = LayoutError::PhysicalRecordLen(position, self.next_line.len(), len);
+                    return Err(FileProcessError::Layout(err).into());
+                }
+                self.layout.physical_record_len = Some(len);
+                len
+            }
+        };
+        let rest = usize::from(len) - self.next_line.len();
+        (&mut self.inner).take(rest as u64).read_to_end(&mut self.next_line)?;
+        if self.next_line.is_empty() {
+            return Ok(false);
+        }
+        self.physical_records += 1;
+        self.position = Position {
+            offset: start.offset + self.next_line.len(),
+            line: start.line + 1,
+            column: 1,
+            ..start
+        };
+        if self.next_line.len() < usize::from(len) && self.check_layout &&
+            self.next_line.iter().any(|&c| !is_padding(c))
+        {
+            let err = LayoutError::PartialRecord(start, self.next_line.len(), len);
+            return Err(FileProcessError::Layout(err).into());
+        }
+        while self.next_line.last().map_or(false, |&c| is_padding(c)) {
+            self.next_line.pop();
+        }
+        self.next_line.push(b'\n');
+        Ok(true)
+    }
+
+    /// Where `position`, found in `record`, is in the input. Fixed-length records lose their
+    /// padding in `record`, so their offsets need fixing up.
+    fn input_position(&self, position: Position) -> Position {
+        match (self.fixed_length, self.layout.physical_record_len) {
+            (true, Some(len)) => {
+                Position {
+                    offset: self.record_position.offset +
+                        (position.line - self.record_position.line) * usize::from(len) +
+                        position.column - 1,
+                    ..position
+                }
+            }
+            _ => position,
+        }
+    }
+
+    pub fn read_raw_record(&mut self) -> Result<Option<RawRecord>, ReadError> {
+        if !self.next_record()? {
+            return Ok(None);
+        }
+        self.raw_record().map(Some)
+    }
+
+    pub fn read_record(&mut self) -> Result<Option<ParsedRecord>, ReadError> {
+        if !self.next_record()? {
+            return Ok(None);
+        }
+        self.parsed_record().map(Some)
+    }
+
+    fn parsed_record(&self) -> Result<ParsedRecord, ReadError> {
+        let raw = self.raw_record()?;
+        ast::Record::parse_with(&raw, &*self.dialect).map_err(|e| {
+            let position = ast::Record::locate(&raw, &e).map_or(self.record_position, |field| {
+                self.input_position(self.record_position.locate(&self.record, field))
+            });
+            FileProcessError::FieldParse(position, e.at(position)).into()
+        })
+    }
+
+    /// Reads the next logical record into `record`, returning `false` at the end of the input.
+    fn next_record(&mut self) -> Result<bool, ReadError> {
+        self.record.clear();
+        if self.next_line.is_empty() && !self.read_line()? {
+            return Ok(false);
+        }
+        mem::swap(&mut self.record, &mut self.next_line);
+        self.record_position = self.next_position.record(&self.record);
+        self.continuations = 0;
+        while self.read_line()? {
+            if !self.next_line.starts_with(CONTINUATION) {
+                break;
+            }
+            self.record.extend_from_slice(&self.next_line);
+            self.continuations += 1;
+        }
+        // The grammar needs to see past the end of the record to know that it isn't continued,
+        // so end the record with a blank line.
+        if self.record.last() != Some(&b'\n') {
+            self.record.push(b'\n');
+        }
+        self.record.push(b'\n');
+
+        if self.record_position.record_code == Some(1) {
+            self.dialect = self.dialects.for_file(&self.record);
+            if let Ok(ParsedRecord::FileHeader(header)) = self.parsed_record() {
+                let layout = Layout::from_header(&header);
+                self.layout = Layout {
+                    physical_record_len: self.layout
+                        .physical_record_len
+                        .or(layout.physical_record_len),
+                    ..layout
+                };
+            }
+        }
+        let missing = match self.raw_record() {
+            Ok(_) if self.dialect.missing_record_sep() => None,
+            Ok(raw) => parse::missing_record_sep(&self.record, &raw),
+            Err(_) => None,
+        };
+        if let Some(end) = missing {
+            let position = self.input_position(self.record_position.advance(&self.record[..end]));
+            let err = FileProcessError::MissingRecordSep(position);
+            match self.strictness {
+                Strictness::Strict => return Err(err.into()),
+                Strictness::Lenient => self.warnings.push(Warning(err)),
+            }
+        }
+        if self.check_layout && !self.fixed_length {
+            self.layout
+                .check_lines(self.record_position, &self.record)
+                .map_err(FileProcessError::Layout)?;
+        }
+        Ok(true)
+    }
+
+    fn raw_record(&self) -> Result<RawRecord, ReadError> {
+        let position = self.record_position;
+        let (rest, record) = match parse::record_lenient(&self.record, &*self.dialect) {
+            IResult::Done(rest, record) => (rest, record),
+            IResult::Error(kind) => return Err(FileProcessError::Parse(position, kind).into()),
+            IResult::Incomplete(_) => {
+                return Err(FileProcessError::Parse(position, ErrorKind::Complete).into())
+            }
+        };
+        if rest.iter().any(|&c| c != b'\n' && c != b'\r') {
+            let position = position.advance(&self.record[..self.record.len() - rest.len()]);
+            let position = self.input_position(position);
+            return Err(FileProcessError::Parse(position, ErrorKind::Eof).into());
+        }
+        Ok(record)
+    }
+
+    /// Converts records until the file trailer, leaving any further input unread.
+    pub fn read_file(&mut self) -> Result<data::File, ReadError> {
+        self.convert(Converter::default())
+    }
+
+    /// Like `read_file`, but hands each part of the file to `visitor` as it's read instead of
+    /// building it up. The returned `data::File` only holds the file header.
+    pub fn visit_file<V: Visitor>(&mut self, visitor: V) -> Result<data::File, ReadError> {
+        self.convert(Converter::visiting(visitor))
+    }
+
+    fn convert<V: Visitor>(&mut self, mut converter: Converter<V>) -> Result<data::File, ReadError> {
+        converter.set_strictness(self.strictness);
+        converter.set_recovery(self.recovery);
+        while self.next_record()? {
+            let position = self.record_position;
+            if position.record_code == Some(1) {
+                converter.set_dialect(self.dialect.clone());
+            }
+            let output = match self.parsed_record() {
+                Ok(record) => converter.process_continued(record, self.continuations),
+                Err(ReadError::Process(e)) if self.recovery => {
+                    match converter.skip_unparsed(position.record_code) {
+                        ConverterOutput::Done => return Err(e.into()),
+                        output => {
+                            self.errors.push(e);
+                            output
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            };
+            self.warnings.extend(converter.take_warnings().into_iter().map(|e| {
+                Warning(FileProcessError::Conversion(position, e.at(position)))
+            }));
+            self.errors.extend(converter.take_errors().into_iter().map(|e| {
+                FileProcessError::Conversion(position, e.at(position))
+            }));
+            match output {
+                ConverterOutput::Active => {}
+                ConverterOutput::Ok(file) => {
+                    // Blocks can only be checked once the padding after the trailer has been
+                    // read, which it has if nothing else follows.
+                    if self.check_layout && self.fixed_length && self.next_line.is_empty() {
+                        self.layout
+                            .check_blocks(self.position, self.physical_records)
+                            .map_err(FileProcessError::Layout)?;
+                    }
+                    return Ok(file);
+                }
+                ConverterOutput::Err(e) => {
+                    let position = self.record_position;
+                    return Err(FileProcessError::Conversion(position, e.at(position)).into());
+                }
+                ConverterOutput::Done => unreachable!(),
+            }
+        }
+        Err(FileProcessError::UnfinishedConversion(self.position).into())
+    }
+}
+
+/// A logical record read by iterating over a `RecordReader`, owning its text so it can outlive
+/// the next read.
+#[derive(Clone)]
+pub struct Record {
+    text: Vec<u8>,
+    position: Position,
+    continuations: usize,
+    dialect: Arc<Dialect>,
+}
+
+impl Record {
+    /// The record's text, joined with its 88 continuation records.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// The position of the record in the input.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// The number of 88 records the record was continued with.
+    pub fn continuations(&self) -> usize {
+        self.continuations
+    }
+
+    pub fn parsed(&self) -> ParsedRecord {
+        // The reader only hands out records it's already parsed with the same dialect.
+        let raw = match parse::record_lenient(&self.text, &*self.dialect) {
+            IResult::Done(_, raw) => raw,
+            _ => unreachable!("record was checked when it was read"),
+        };
+        ast::Record::parse_with(&raw, &*self.dialect).expect("record was checked when it was read")
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("text", &String::from_utf8_lossy(&self.text))
+            .field("position", &self.position)
+            .field("continuations", &self.continuations)
+            .finish()
+    }
+}
+
+/// Iterates over the records, checking that each parses. Records that can't be parsed are
+/// yielded as errors and iteration carries on after them, but it ends after an error reading
+/// the input.
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = Result<Record, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.io_failed {
+            return None;
+        }
+        let record = match self.next_record() {
+            Ok(false) => return None,
+            Ok(true) => self.parsed_record().map(|_| ()),
+            Err(e) => Err(e),
+        };
+        Some(match record {
+            Ok(()) => Ok(Record {
+                text: self.record.clone(),
+                position: self.record_position,
+                continuations: self.continuations,
+                dialect: self.dialect.clone(),
+            }),
+            Err(ReadError::Io(e)) => {
+                self.io_failed = true;
+                Err(ReadError::Io(e))
+            }
+            Err(e) => Err(e),
+        })
+    }
+}
+
+fn is_padding(c: u8) -> bool {
+    c == b' ' || c == b'\n' || c == b'\r'
+}
+
+/// Reads the physical record length field out of a file header.
+fn header_physical_record_len(header: &[u8]) -> Option<u16> {
+    header
+        .split(|&c| c == b',' || c == b'/')
+        .nth(6)
+        .and_then(|len| str::from_utf8(len).ok())
+        .and_then(|len| len.trim().parse().ok())
+}
@@ -0,0 +1,979 @@
+//! Serializing your own types straight to files with serde, without building a `data::File`
+//! first.
+//!
+//! Values take the shape `de` reads: a file is a map of its header's fields and `groups`, a group
+//! a map of its header's fields and `accounts`, and an account a map of its identifier's fields,
+//! `infos` and `transaction_details`. Dates are ISO 8601 strings such as `NaiveDate` serializes
+//! to, times are `HH:MM` or `HH:MM:SS` with `24:00` for the end of the day, and amounts are
+//! integers in the minor units of their currency. Funds types are either their letter or digit,
+//! or maps with it under `code` along with their fields.
+//!
+//! Each record is written as soon as it's been serialized, so a record's fields must come before
+//! its groups, accounts or transaction details. Maps that sort their keys, like `serde_json`'s
+//! without its `preserve_order` feature, won't do.
+//!
+//! Missing and null fields are written empty, except for those every record needs. Trailers are
+//! computed while writing, so any `control_total` or record, account and group counts after the
+//! children are ignored, as is `physical_record_len`, which comes from the `Writer`.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::ser::{self, Impossible, Serialize};
+
+use write::Writer;
+
+/// Serializes `value` as a whole file to `writer`.
+pub fn to_writer<W: io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<(), Error> {
+    value.serialize(&mut Serializer::new(writer))
+}
+
+/// Serializes `value` as a whole file.
+pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    to_writer(&mut out, value)?;
+    Ok(out)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A field every record of its kind needs was missing or null.
+    Missing(&'static str),
+    /// A field's value can't be written, like text where a number belongs.
+    Invalid(&'static str, String),
+    /// A field came after the records it needs to be written before.
+    Order(String, &'static str),
+    Message(String),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "error writing output: {}", e),
+            Error::Missing(field) => write!(f, "missing field {}", field),
+            Error::Invalid(field, ref value) => write!(f, "invalid {}: {}", field, value),
+            Error::Order(ref field, children) => {
+                write!(f, "field {} must come before {}", field, children)
+            }
+            Error::Message(ref msg) => f.write_str(msg),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    File,
+    Group,
+    Account,
+    TransactionDetail,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::File => "a file",
+            Kind::Group => "a group",
+            Kind::Account => "an account",
+            Kind::TransactionDetail => "a transaction detail",
+        }
+    }
+
+    /// The field holding this kind's children, and their kind.
+    fn children(self) -> Option<(&'static str, Kind)> {
+        match self {
+            Kind::File => Some(("groups", Kind::Group)),
+            Kind::Group => Some(("accounts", Kind::Account)),
+            Kind::Account => Some(("transaction_details", Kind::TransactionDetail)),
+            Kind::TransactionDetail => None,
+        }
+    }
+}
+
+/// Fields that may follow a record's children, as they're recomputed for its trailer.
+const TRAILER_FIELDS: &[&str] = &["control_total", "groups_num", "accounts_num", "records_num"];
+
+/// What the serializer is to be given next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Record(Kind),
+    Children(Kind),
+}
+
+/// The control total and counts of a record being written, for its trailer.
+#[derive(Debug, Default)]
+struct Totals {
+    control_total: i64,
+    records_num: usize,
+    children_num: usize,
+}
+
+pub struct Serializer<W> {
+    writer: Writer<W>,
+    expect: Expect,
+    /// The totals of the file, group and account being written, outermost first.
+    totals: Vec<Totals>,
+}
+
+impl<W: io::Write> Serializer<W> {
+    pub fn new(inner: W) -> Self {
+        Serializer::from_writer(Writer::new(inner))
+    }
+
+    /// Writes records with `writer`, and so with its physical record length.
+    pub fn from_writer(writer: Writer<W>) -> Self {
+        Serializer {
+            writer,
+            expect: Expect::Record(Kind::File),
+            totals: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> Writer<W> {
+        self.writer
+    }
+
+    fn totals(&mut self) -> &mut Totals {
+        self.totals.last_mut().expect("a record is being written")
+    }
+
+    fn write_file_header(&mut self, file: &[(String, Value)]) -> Result<(), Error> {
+        let mut fields = vec![
+            required(file, "sender_ident")?,
+            required(file, "receiver_ident")?,
+            date(file, "creation_date")?.ok_or(Error::Missing("creation_date"))?,
+            time(file, "creation_time")?.ok_or(Error::Missing("creation_time"))?,
+            required(file, "ident_num")?,
+        ];
+        fields.push(self.writer.physical_record_len().map_or_else(String::new, |l| l.to_string()));
+        fields.push(text(file, "block_size")?.unwrap_or_default());
+        fields.push(text(file, "version_number")?.unwrap_or_else(|| "2".to_owned()));
+        self.totals().records_num += self.writer.write_record("01", &fields)?;
+        Ok(())
+    }
+
+    fn write_group_header(&mut self, group: &[(String, Value)]) -> Result<(), Error> {
+        let fields = vec![
+            text(group, "ultimate_receiver_ident")?.unwrap_or_default(),
+            text(group, "originator_ident")?.unwrap_or_default(),
+            required(group, "status")?,
+            date(group, "as_of_date")?.ok_or(Error::Missing("as_of_date"))?,
+            time(group, "as_of_time")?.unwrap_or_default(),
+            text(group, "currency")?.unwrap_or_default(),
+            text(group, "as_of_date_mod")?.unwrap_or_default(),
+        ];
+        self.totals().records_num += self.writer.write_record("02", &fields)?;
+        Ok(())
+    }
+
+    fn write_account_ident(&mut self, account: &[(String, Value)]) -> Result<(), Error> {
+        let mut control_total = 0;
+        let mut fields = vec![
+            required(account, "customer_account_num")?,
+            text(account, "currency")?.unwrap_or_default(),
+        ];
+        let infos = seq(account, "infos")?;
+        for info in infos {
+            let info = map(info, "infos")?;
+            let amount = amount(info, "amount")?;
+            control_total += amount.unwrap_or(0);
+            fields.push(type_code(info)?);
+            fields.push(amount.map_or_else(String::new, |a| a.to_string()));
+            fields.push(text(info, "item_count")?.unwrap_or_default());
+            push_funds(&mut fields, field(info, "funds_type"))?;
+        }
+        if infos.is_empty() {
+            // The record needs at least one (empty) account info.
+            fields.extend(vec![String::new(); 4]);
+        }
+        let records_num = self.writer.write_record("03", &fields)?;
+        let totals = self.totals();
+        totals.control_total += control_total;
+        totals.records_num += records_num;
+        Ok(())
+    }
+
+    fn write_transaction_detail(
+        &mut self,
+        transaction_detail: &[(String, Value)],
+    ) -> Result<(), Error> {
+        let amount = amount(transaction_detail, "amount")?;
+        let mut fields = vec![
+            type_code(transaction_detail)?,
+            amount.map_or_else(String::new, |a| a.to_string()),
+        ];
+        push_funds(&mut fields, field(transaction_detail, "funds_type"))?;
+        fields.push(text(transaction_detail, "bank_ref_num")?.unwrap_or_default());
+        fields.push(text(transaction_detail, "customer_ref_num")?.unwrap_or_default());
+        let text = match field(transaction_detail, "text") {
+            None => Vec::new(),
+            Some(Value::Str(line)) => vec![line.clone()],
+            Some(Value::Seq(lines)) => {
+                lines
+                    .iter()
+                    .map(|line| match *line {
+                        Value::Str(ref line) => Ok(line.clone()),
+                        ref other => Err(Error::Invalid("text", other.to_string())),
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            Some(other) => return Err(Error::Invalid("text", other.to_string())),
+        };
+        let records_num = if text.is_empty() {
+            fields.push(String::new());
+            self.writer.write_record("16", &fields)?
+        } else {
+            self.writer.write_text_record("16", &fields, &text)?
+        };
+        let totals = self.totals();
+        totals.control_total += amount.unwrap_or(0);
+        totals.records_num += records_num;
+        Ok(())
+    }
+
+    fn write_header(&mut self, kind: Kind, fields: &[(String, Value)]) -> Result<(), Error> {
+        match kind {
+            Kind::File => self.write_file_header(fields),
+            Kind::Group => self.write_group_header(fields),
+            Kind::Account => self.write_account_ident(fields),
+            Kind::TransactionDetail => self.write_transaction_detail(fields),
+        }
+    }
+
+    /// Writes the trailer closing `kind`, if it has one, and adds its totals to its parent's.
+    fn write_trailer(&mut self, kind: Kind) -> Result<(), Error> {
+        let mut totals = self.totals.pop().expect("a record is being written");
+        let records_num = totals.records_num + 1;
+        match kind {
+            Kind::File => {
+                self.writer.write_record(
+                    "99",
+                    &[
+                        totals.control_total.to_string(),
+                        totals.children_num.to_string(),
+                        records_num.to_string(),
+                    ],
+                )?;
+                self.expect = Expect::Record(Kind::File);
+                return Ok(());
+            }
+            Kind::Group => {
+                totals.records_num += self.writer.write_record(
+                    "98",
+                    &[
+                        totals.control_total.to_string(),
+                        totals.children_num.to_string(),
+                        records_num.to_string(),
+                    ],
+                )?;
+            }
+            Kind::Account => {
+                totals.records_num += self.writer.write_record(
+                    "49",
+                    &[totals.control_total.to_string(), records_num.to_string()],
+                )?;
+            }
+            Kind::TransactionDetail => {}
+        }
+        let parent = self.totals();
+        parent.control_total += totals.control_total;
+        parent.records_num += totals.records_num;
+        parent.children_num += 1;
+        Ok(())
+    }
+
+    fn unexpected<T>(&self) -> Result<T, Error> {
+        Err(Error::Message(match self.expect {
+            Expect::Record(kind) => {
+                format!("{} must be serialized as a map or struct", kind.name())
+            }
+            Expect::Children(kind) => {
+                format!("children of {} must be serialized as a sequence", kind.name())
+            }
+        }))
+    }
+}
+
+/// A field's value, as serialized.
+#[derive(Debug, Clone)]
+enum Value {
+    Null,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+    Seq(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => b.fmt(f),
+            Value::I64(n) => n.fmt(f),
+            Value::U64(n) => n.fmt(f),
+            Value::F64(n) => n.fmt(f),
+            Value::Str(ref s) => write!(f, "{:?}", s),
+            Value::Seq(ref values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    value.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            Value::Map(ref fields) => {
+                f.write_str("{")?;
+                for (i, &(ref key, ref value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{:?}:{}", key, value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn field<'v>(map: &'v [(String, Value)], name: &str) -> Option<&'v Value> {
+    match map.iter().find(|&&(ref key, _)| key == name) {
+        None | Some(&(_, Value::Null)) => None,
+        Some(&(_, ref value)) => Some(value),
+    }
+}
+
+fn text(map: &[(String, Value)], name: &'static str) -> Result<Option<String>, Error> {
+    match field(map, name) {
+        None => Ok(None),
+        Some(Value::Str(s)) => Ok(Some(s.clone())),
+        Some(Value::I64(n)) => Ok(Some(n.to_string())),
+        Some(Value::U64(n)) => Ok(Some(n.to_string())),
+        Some(other) => Err(Error::Invalid(name, other.to_string())),
+    }
+}
+
+fn required(map: &[(String, Value)], name: &'static str) -> Result<String, Error> {
+    text(map, name)?.ok_or(Error::Missing(name))
+}
+
+fn amount(map: &[(String, Value)], name: &'static str) -> Result<Option<i64>, Error> {
+    match field(map, name) {
+        None => Ok(None),
+        Some(&Value::I64(n)) => Ok(Some(n)),
+        Some(&Value::U64(n)) if n <= i64::max_value() as u64 => Ok(Some(n as i64)),
+        Some(other) => Err(Error::Invalid(name, other.to_string())),
+    }
+}
+
+fn type_code(map: &[(String, Value)]) -> Result<String, Error> {
+    match field(map, "type_code") {
+        None => Ok(String::new()),
+        Some(&Value::U64(code)) if code <= 999 => Ok(format!("{:03}", code)),
+        Some(&Value::I64(code @ 0...999)) => Ok(format!("{:03}", code)),
+        Some(other) => Err(Error::Invalid("type_code", other.to_string())),
+    }
+}
+
+fn date(map: &[(String, Value)], name: &'static str) -> Result<Option<String>, Error> {
+    text(map, name)?.map_or(Ok(None), |date| match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+        Ok(date) => Ok(Some(date.format("%y%m%d").to_string())),
+        Err(_) => Err(Error::Invalid(name, date)),
+    })
+}
+
+fn time(map: &[(String, Value)], name: &'static str) -> Result<Option<String>, Error> {
+    text(map, name)?.map_or(Ok(None), |time| {
+        if time == "24:00" || time == "24:00:00" {
+            return Ok(Some("9999".to_owned()));
+        }
+        match NaiveTime::parse_from_str(&time, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(&time, "%H:%M")) {
+            Ok(time) => Ok(Some(time.format("%H%M").to_string())),
+            Err(_) => Err(Error::Invalid(name, time)),
+        }
+    })
+}
+
+fn seq<'v>(map: &'v [(String, Value)], name: &'static str) -> Result<&'v [Value], Error> {
+    match field(map, name) {
+        None => Ok(&[]),
+        Some(Value::Seq(values)) => Ok(values),
+        Some(other) => Err(Error::Invalid(name, other.to_string())),
+    }
+}
+
+fn map<'v>(value: &'v Value, name: &'static str) -> Result<&'v [(String, Value)], Error> {
+    match *value {
+        Value::Map(ref map) => Ok(map),
+        ref other => Err(Error::Invalid(name, other.to_string())),
+    }
+}
+
+fn push_funds(fields: &mut Vec<String>, funds: Option<&Value>) -> Result<(), Error> {
+    let code_only;
+    let funds = match funds {
+        None => {
+            fields.push(String::new());
+            return Ok(());
+        }
+        Some(Value::Str(code)) => {
+            code_only = vec![("code".to_owned(), Value::Str(code.clone()))];
+            &code_only[..]
+        }
+        Some(funds) => map(funds, "funds_type")?,
+    };
+    let code = required(funds, "code")?;
+    match &*code {
+        "Z" | "0" | "1" | "2" => fields.push(code),
+        "S" => {
+            fields.push(code);
+            for name in &["immediate", "one_day", "more_than_one_day"] {
+                fields.push(amount(funds, name)?.map_or_else(String::new, |a| a.to_string()));
+            }
+        }
+        "V" => {
+            fields.push(code);
+            fields.push(date(funds, "date")?.ok_or(Error::Missing("date"))?);
+            fields.push(time(funds, "time")?.unwrap_or_default());
+        }
+        "D" => {
+            let dists = seq(funds, "dists")?;
+            fields.push(code);
+            fields.push(dists.len().to_string());
+            for dist in dists {
+                let dist = map(dist, "dists")?;
+                fields.push(required(dist, "days")?);
+                fields.push(amount(dist, "amount")?.ok_or(Error::Missing("amount"))?.to_string());
+            }
+        }
+        _ => return Err(Error::Invalid("funds_type", code)),
+    }
+    Ok(())
+}
+
+/// A file, group, account or transaction detail being serialized. Its record is written once
+/// its fields have been, before any of its children.
+pub struct Record<'a, W: 'a> {
+    ser: &'a mut Serializer<W>,
+    kind: Kind,
+    fields: Vec<(String, Value)>,
+    key: Option<String>,
+    written: bool,
+}
+
+impl<'a, W: io::Write> Record<'a, W> {
+    fn new(ser: &'a mut Serializer<W>, kind: Kind) -> Self {
+        ser.totals.push(Totals::default());
+        Record {
+            ser,
+            kind,
+            fields: Vec::new(),
+            key: None,
+            written: false,
+        }
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        let children = self.kind.children();
+        match children {
+            Some((name, kind)) if key == name => {
+                if !self.written {
+                    self.ser.write_header(self.kind, &self.fields)?;
+                    self.written = true;
+                }
+                self.ser.expect = Expect::Children(kind);
+                value.serialize(&mut *self.ser)
+            }
+            Some((name, _)) if self.written => {
+                if TRAILER_FIELDS.contains(&&*key) {
+                    return Ok(());
+                }
+                match value.serialize(ValueSerializer)? {
+                    Value::Null => Ok(()),
+                    _ => Err(Error::Order(key, name)),
+                }
+            }
+            _ => {
+                let value = value.serialize(ValueSerializer)?;
+                self.fields.push((key, value));
+                Ok(())
+            }
+        }
+    }
+
+    fn end(self) -> Result<(), Error> {
+        if !self.written {
+            self.ser.write_header(self.kind, &self.fields)?;
+        }
+        self.ser.write_trailer(self.kind)
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeMap for Record<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key_string(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(
+            || Error::Message("value serialized before its key".to_owned()),
+        )?;
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Record::end(self)
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeStruct for Record<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Record::end(self)
+    }
+}
+
+/// The groups of a file, the accounts of a group or the transaction details of an account, each
+/// written as it's serialized.
+pub struct Children<'a, W: 'a> {
+    ser: &'a mut Serializer<W>,
+    kind: Kind,
+}
+
+impl<'a, W: io::Write> ser::SerializeSeq for Children<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.ser.expect = Expect::Record(self.kind);
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTuple for Children<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Children<'a, W>;
+    type SerializeTuple = Children<'a, W>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Record<'a, W>;
+    type SerializeStruct = Record<'a, W>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Record<'a, W>, Error> {
+        match self.expect {
+            Expect::Record(kind) => Ok(Record::new(self, kind)),
+            Expect::Children(_) => self.unexpected(),
+        }
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Record<'a, W>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Children<'a, W>, Error> {
+        match self.expect {
+            Expect::Children(kind) => Ok(Children { ser: self, kind }),
+            Expect::Record(_) => self.unexpected(),
+        }
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Children<'a, W>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        // Missing children are no children.
+        match self.expect {
+            Expect::Children(_) => Ok(()),
+            Expect::Record(_) => self.unexpected(),
+        }
+    }
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.serialize_none()
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_i8(self, _v: i8) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_i16(self, _v: i16) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_i32(self, _v: i32) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_i64(self, _v: i64) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_u8(self, _v: u8) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_u16(self, _v: u16) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_u32(self, _v: u32) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_u64(self, _v: u64) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_f32(self, _v: f32) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_f64(self, _v: f64) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_char(self, _v: char) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_str(self, _v: &str) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        self.unexpected()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        self.unexpected()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        self.unexpected()
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        self.unexpected()
+    }
+}
+
+fn key_string<T: ?Sized + Serialize>(key: &T) -> Result<String, Error> {
+    match key.serialize(ValueSerializer)? {
+        Value::Str(key) => Ok(key),
+        other => Err(Error::Invalid("key", other.to_string())),
+    }
+}
+
+fn variant_with_data<T>(variant: &'static str) -> Result<T, Error> {
+    Err(Error::Message(format!("enum variant {} with data can't be written", variant)))
+}
+
+/// Serializes a record's field into a `Value`.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = Impossible<Value, Error>;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = MapBuilder;
+    type SerializeStructVariant = Impossible<Value, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::I64(v.into()))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::I64(v.into()))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::I64(v.into()))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::I64(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::U64(v.into()))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::U64(v.into()))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::U64(v.into()))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::U64(v))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::F64(v.into()))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::F64(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::Str(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::Str(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Seq(v.iter().map(|&b| Value::U64(b.into())).collect()))
+    }
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::Str(variant.to_owned()))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Value, Error> {
+        variant_with_data(variant)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, Error> {
+        Ok(SeqBuilder(Vec::with_capacity(len.unwrap_or(0))))
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        variant_with_data(variant)
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<MapBuilder, Error> {
+        Ok(MapBuilder {
+            fields: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapBuilder, Error> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        variant_with_data(variant)
+    }
+}
+
+struct SeqBuilder(Vec<Value>);
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct MapBuilder {
+    fields: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key_string(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(
+            || Error::Message("value serialized before its key".to_owned()),
+        )?;
+        self.fields.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Map(self.fields))
+    }
+}
+
+impl ser::SerializeStruct for MapBuilder {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.fields.push((key.to_owned(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Map(self.fields))
+    }
+}
@@ -0,0 +1,802 @@
+//! SWIFT MT940 customer statements, and MT942 interim transaction reports for intraday groups.
+//!
+//! Every account becomes a message of its own, written as the fields of the message's text
+//! block and ended by a line holding only `-`, the way banks hand statements to accounting
+//! software. Groups with an interim as-of-date modifier are written by `write_mt942`, and the
+//! rest by `write_mt940`.
+//!
+//! The opening and closing ledger statuses (010 and 015) become `:60F:` and `:62F:`. A
+//! statement missing one of them has it worked out from the other and the account's credits
+//! and debits. The closing available status (045) becomes `:64:`. Transaction details that are
+//! credits or debits become `:61:` statement lines, with their text as the `:86:` after them;
+//! the rest, which SWIFT has no place for, are left out.
+//!
+//! Everything written is in the SWIFT `x` character set, with anything outside it replaced by
+//! a space, and no line is longer than 65 characters.
+//!
+//! `read_mt940` goes the other way, turning statements from banks that only offer SWIFT into a
+//! file, with their transaction types mapped back onto detail codes.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::io;
+
+use chrono::NaiveDate;
+use penny::Currency;
+
+use data::{self, AccountStatus, Amount, AsOfDateModifier, BaiDateTime, Direction, StatusCode};
+use write;
+
+/// The longest line of a field.
+const MAX_LINE_LEN: usize = 65;
+/// The most lines an `:86:` can have.
+const MAX_INFO_LINES: usize = 6;
+/// The longest `16x` reference.
+const MAX_REF_LEN: usize = 16;
+/// The longest `35x` account identification.
+const MAX_ACCOUNT_LEN: usize = 35;
+/// The largest `5n` statement number.
+const MAX_NUMBER: u32 = 99_999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Message {
+    /// MT940, for final groups.
+    Statement,
+    /// MT942, for interim groups.
+    Report,
+}
+
+impl Message {
+    fn name(self) -> &'static str {
+        match self {
+            Message::Statement => "MT940",
+            Message::Report => "MT942",
+        }
+    }
+
+    fn includes(self, group: &data::Group) -> bool {
+        let interim = group.as_of_date_mod.map(AsOfDateModifier::is_interim) == Some(true);
+        interim == (self == Message::Report)
+    }
+}
+
+/// Writes an MT940 statement for every account in the final groups of `file`.
+pub fn write_mt940<W: io::Write>(file: &data::File, w: W) -> io::Result<()> {
+    write_messages(Message::Statement, file, w)
+}
+
+/// Writes an MT942 interim transaction report for every account in the interim groups of
+/// `file`. The group's as-of time is the report's date and time, in UTC since BAI files carry
+/// no time zone.
+pub fn write_mt942<W: io::Write>(file: &data::File, w: W) -> io::Result<()> {
+    write_messages(Message::Report, file, w)
+}
+
+fn write_messages<W: io::Write>(message: Message, file: &data::File, mut w: W) -> io::Result<()> {
+    let groups: Vec<_> = file.groups
+        .iter()
+        .enumerate()
+        .filter(|&(_, group)| message.includes(group))
+        .collect();
+    if groups.iter().all(|&(_, group)| group.accounts.is_empty()) {
+        return Err(invalid_input(
+            format!("file has no accounts in groups for {}", message.name()),
+        ));
+    }
+
+    for (i, group) in groups {
+        for (j, account) in group.accounts.iter().enumerate() {
+            let reference = format!("{}-{}-{}", file.ident.0, i + 1, j + 1);
+            let mut fields = Fields { w: &mut w };
+            fields.write("20", &truncate(&charset(&reference), MAX_REF_LEN))?;
+            fields.write(
+                "25",
+                &truncate(&charset(&account.customer_account.0), MAX_ACCOUNT_LEN),
+            )?;
+            // Every message is a whole statement, so the first and only page of it.
+            fields.write("28C", &format!("{}/1", file.ident.0 % (MAX_NUMBER + 1)))?;
+            match message {
+                Message::Statement => write_statement(&mut fields, group, account)?,
+                Message::Report => write_report(&mut fields, file, group, account)?,
+            }
+            w.write_all(b"-\r\n")?;
+        }
+    }
+    w.flush()
+}
+
+fn write_statement<W: io::Write>(
+    fields: &mut Fields<W>,
+    group: &data::Group,
+    account: &data::Account,
+) -> io::Result<()> {
+    let as_of = group.as_of.clone().date();
+    let status = |status_code: u16| {
+        account.infos.iter().filter_map(|info| match *info {
+            data::AccountInfo::Status { code, amount } if u16::from(code) == status_code => {
+                amount
+            }
+            _ => None,
+        }).next()
+    };
+    let mut net = 0;
+    for detail in &account.transaction_details {
+        if let Some(amount) = detail.signed_amount()? {
+            net += amount.value;
+        }
+    }
+    let (opening, closing) = match (status(10), status(15)) {
+        (Some(opening), Some(closing)) => (opening, closing),
+        (Some(opening), None) => (opening, Amount { value: opening.value + net, ..opening }),
+        (None, Some(closing)) => (Amount { value: closing.value - net, ..closing }, closing),
+        (None, None) => {
+            return Err(invalid_input(format!(
+                "account {} has no opening or closing ledger for MT940",
+                account.customer_account.0
+            )))
+        }
+    };
+
+    fields.write("60F", &balance(opening, &as_of)?)?;
+    for detail in &account.transaction_details {
+        write_statement_line(fields, detail, &as_of)?;
+    }
+    fields.write("62F", &balance(closing, &as_of)?)?;
+    if let Some(available) = status(45) {
+        fields.write("64", &balance(available, &as_of)?)?;
+    }
+    Ok(())
+}
+
+fn write_report<W: io::Write>(
+    fields: &mut Fields<W>,
+    file: &data::File,
+    group: &data::Group,
+    account: &data::Account,
+) -> io::Result<()> {
+    let currency = account.currency_def(group.currency_def());
+    let as_of = group.as_of.clone().date();
+    let as_of_time = group.as_of.clone().date_time().unwrap_or_else(|| file.creation.clone());
+
+    fields.write("34F", &format!("{}0,", currency))?;
+    fields.write("13D", &format!("{}+0000", date_time(&as_of_time)))?;
+    for detail in &account.transaction_details {
+        write_statement_line(fields, detail, &as_of)?;
+    }
+    for &(tag, direction) in &[("90D", Direction::Debit), ("90C", Direction::Credit)] {
+        let amounts: Vec<_> = account
+            .transaction_details
+            .iter()
+            .filter(|detail| detail.code.direction() == direction)
+            .filter_map(|detail| detail.amount)
+            .collect();
+        if amounts.is_empty() {
+            continue;
+        }
+        let mut total = 0;
+        for amount in &amounts {
+            total += amount.abs()?.value;
+        }
+        let total = Amount {
+            value: total,
+            currency,
+        };
+        fields.write(tag, &format!("{}{}{}", amounts.len(), currency, amount(total)?))?;
+    }
+    Ok(())
+}
+
+/// Writes a `:61:` and, when the detail has text, the `:86:` after it.
+fn write_statement_line<W: io::Write>(
+    fields: &mut Fields<W>,
+    detail: &data::TransactionDetail,
+    as_of: &NaiveDate,
+) -> io::Result<()> {
+    let mark = match detail.code.direction() {
+        Direction::Credit => "C",
+        Direction::Debit => "D",
+        Direction::NonMonetary => return Ok(()),
+    };
+    let value = match detail.amount {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let value_date = match detail.funds {
+        Some(data::FundsType::ValueDated(ref avail)) => avail.clone().date(),
+        _ => *as_of,
+    };
+    let customer_ref = detail.customer_ref_num.as_ref().map_or("NONREF", |r| &r.0);
+    let mut line = format!(
+        "{}{}{}{}N{}{}",
+        value_date.format("%y%m%d"),
+        as_of.format("%m%d"),
+        mark,
+        amount(value)?,
+        transaction_type(detail.code.into()),
+        truncate(&charset(customer_ref), MAX_REF_LEN),
+    );
+    if let Some(ref bank_ref) = detail.bank_ref_num {
+        line.push_str("//");
+        line.push_str(&truncate(&charset(&bank_ref.0), MAX_REF_LEN));
+    }
+    fields.write("61", &line)?;
+
+    if let Some(ref text) = detail.text {
+        let text = charset(&text.join(" "));
+        let lines = wrap(text.trim(), MAX_LINE_LEN);
+        if !lines.is_empty() {
+            let lines: Vec<_> = lines.into_iter().take(MAX_INFO_LINES).collect();
+            fields.write("86", &lines.join("\r\n"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Errors from reading MT940 statements.
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// A field that's missing, out of place or malformed, with the line of the input it's on.
+    Field(usize, String),
+    NoStatements,
+    /// No header blocks named the sender or receiver, and none was given.
+    UnknownParty(&'static str),
+}
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref e) => write!(f, "error reading input: {}", e),
+            ReadError::Field(line, ref msg) => write!(f, "line {}: {}", line, msg),
+            ReadError::NoStatements => write!(f, "no MT940 statements in input"),
+            ReadError::UnknownParty(party) => {
+                write!(f, "no header blocks naming the {} of the statements", party)
+            }
+        }
+    }
+}
+impl error::Error for ReadError {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match *self {
+            ReadError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+/// Reads MT940 statements, with or without their SWIFT header blocks, into a file.
+///
+/// Every statement becomes an account, in a final previous-day group for its closing date and
+/// currency. Statements continued over several messages, with intermediate `:60M:` and `:62M:`
+/// balances, are joined back into one. The opening, closing and closing available balances
+/// become statuses 010, 015 and 045, and every `:61:` becomes a transaction detail, with its
+/// `:86:` as text and its value date as the funds type when that isn't the closing date.
+/// Transaction types map back onto the spec's detail codes where one fits, and onto
+/// `DetailCode::custom` otherwise.
+///
+/// The sender and receiver come from the header blocks of the first message, and it's an error
+/// for them to be missing; `read_mt940_with_parties` reads statements without them.
+pub fn read_mt940<R: io::Read>(r: R) -> Result<data::File, ReadError> {
+    read_mt940_parties(r, None, None)
+}
+
+/// Like `read_mt940`, but with the sender and receiver to use where the header blocks don't
+/// name them, as when statements are exported without them.
+pub fn read_mt940_with_parties<R: io::Read>(
+    r: R,
+    sender: data::Party,
+    receiver: data::Party,
+) -> Result<data::File, ReadError> {
+    read_mt940_parties(r, Some(sender), Some(receiver))
+}
+
+fn read_mt940_parties<R: io::Read>(
+    mut r: R,
+    sender: Option<data::Party>,
+    receiver: Option<data::Party>,
+) -> Result<data::File, ReadError> {
+    let mut input = Vec::new();
+    r.read_to_end(&mut input)?;
+    let input = String::from_utf8_lossy(&input);
+
+    let mut header = Header::default();
+    let mut statements: Vec<Statement> = Vec::new();
+    for message in messages(&input, &mut header)? {
+        let statement = Statement::new(&message)?;
+        match statements.last_mut() {
+            Some(last) if statement.continues(last) => {
+                last.details.extend(statement.details);
+                last.closing = statement.closing;
+                last.available = statement.available.or(last.available);
+                continue;
+            }
+            _ => {}
+        }
+        statements.push(statement);
+    }
+    let ident = match statements.first() {
+        Some(statement) => statement.number.unwrap_or(1),
+        None => return Err(ReadError::NoStatements),
+    };
+
+    let sender = header
+        .sender
+        .map(data::Party)
+        .or(sender)
+        .ok_or(ReadError::UnknownParty("sender"))?;
+    let receiver = header
+        .receiver
+        .map(data::Party)
+        .or(receiver)
+        .ok_or(ReadError::UnknownParty("receiver"))?;
+    let mut groups: Vec<data::Group> = Vec::new();
+    for statement in statements {
+        let as_of = statement.closing.date;
+        let currency = statement.closing.amount.currency;
+        let account = statement.into_account();
+        let group = groups
+            .iter_mut()
+            .find(|g| g.as_of.clone().date() == as_of && g.currency == Some(currency));
+        match group {
+            Some(group) => group.accounts.push(account),
+            None => {
+                groups.push(data::Group {
+                    ultimate_receiver: None,
+                    originator: Some(sender.clone()),
+                    status: data::GroupStatus::Update,
+                    as_of: as_of.into(),
+                    currency: Some(currency),
+                    as_of_date_mod: Some(AsOfDateModifier::FinalPrevious),
+                    accounts: vec![account],
+                })
+            }
+        }
+    }
+    let creation = groups.iter().map(|g| g.as_of.clone().date()).max().unwrap();
+
+    Ok(data::File {
+        sender,
+        receiver,
+        creation: creation.into(),
+        ident: data::FileIdent(ident),
+        version: data::Version::Bai2,
+        groups,
+    })
+}
+
+/// The sender and receiver in the basic and application header blocks.
+#[derive(Default)]
+struct Header {
+    sender: Option<String>,
+    receiver: Option<String>,
+}
+
+impl Header {
+    /// Reads the header blocks before `{4:`, unless they've been read already.
+    fn read(&mut self, blocks: &str) {
+        if self.sender.is_some() || self.receiver.is_some() {
+            return;
+        }
+        let block = |n: &str| {
+            let start = blocks.find(&format!("{{{}:", n))? + 3;
+            let len = blocks[start..].find('}')?;
+            Some(&blocks[start..start + len])
+        };
+        // The basic header has the address of the bank the message came through, and the
+        // application header the other end, as an output (O) or input (I) message.
+        let own = block("1").and_then(|b| b.get(3..11)).map(str::to_owned);
+        match block("2") {
+            Some(b) if b.starts_with('O') => {
+                self.sender = b.get(14..22).map(str::to_owned);
+                self.receiver = own;
+            }
+            Some(b) if b.starts_with('I') => {
+                self.sender = own;
+                self.receiver = b.get(4..12).map(str::to_owned);
+            }
+            _ => self.receiver = own,
+        }
+    }
+}
+
+/// A field of a message's text block, with its lines joined by `\n`.
+struct Field {
+    tag: String,
+    content: String,
+    line: usize,
+}
+
+/// Splits `input` into the fields of each message, reading any header blocks into `header`.
+fn messages(input: &str, header: &mut Header) -> Result<Vec<Vec<Field>>, ReadError> {
+    let mut messages = Vec::new();
+    let mut fields: Vec<Field> = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let mut line = line;
+        if let Some(start) = line.find("{4:") {
+            header.read(&line[..start]);
+            line = &line[start + 3..];
+        }
+        if line == "-" || line.starts_with("-}") {
+            if !fields.is_empty() {
+                messages.push(fields);
+                fields = Vec::new();
+            }
+            continue;
+        }
+        let tag_end = line.get(1..).and_then(|l| l.find(':')).map(|i| i + 1);
+        match tag_end {
+            Some(end) if line.starts_with(':') && end <= 4 => {
+                fields.push(Field {
+                    tag: line[1..end].to_owned(),
+                    content: line[end + 1..].to_owned(),
+                    line: i + 1,
+                })
+            }
+            _ if line.trim().is_empty() => {}
+            _ => {
+                match fields.last_mut() {
+                    Some(field) => {
+                        field.content.push('\n');
+                        field.content.push_str(line);
+                    }
+                    None => return Err(ReadError::Field(i + 1, "text outside a field".to_owned())),
+                }
+            }
+        }
+    }
+    if !fields.is_empty() {
+        messages.push(fields);
+    }
+    Ok(messages)
+}
+
+/// An `:60F:`, `:62F:` or `:64:` balance, or an intermediate `:60M:` or `:62M:` one.
+#[derive(Clone, Copy)]
+struct Balance {
+    intermediate: bool,
+    date: NaiveDate,
+    amount: Amount,
+}
+
+impl Balance {
+    fn new(field: &Field) -> Result<Balance, ReadError> {
+        let c = &field.content;
+        let invalid = || ReadError::Field(field.line, format!("malformed :{}: {:?}", field.tag, c));
+        let negative = match c.get(..1) {
+            Some("C") => false,
+            Some("D") => true,
+            _ => return Err(invalid()),
+        };
+        let date = c.get(1..7).and_then(date).ok_or_else(invalid)?;
+        let currency = c.get(7..10)
+            .and_then(|cur| cur.parse::<Currency>().ok())
+            .ok_or_else(invalid)?;
+        let amount = c.get(10..)
+            .and_then(|a| parse_amount(a, currency))
+            .ok_or_else(invalid)?;
+        Ok(Balance {
+            intermediate: field.tag.ends_with('M'),
+            date,
+            amount: if negative { amount.negate().map_err(|_| invalid())? } else { amount },
+        })
+    }
+}
+
+/// A statement read from a message, with the value date of each transaction.
+struct Statement {
+    number: Option<u32>,
+    account: String,
+    opening: Balance,
+    closing: Balance,
+    available: Option<Balance>,
+    details: Vec<(NaiveDate, data::TransactionDetail)>,
+}
+
+impl Statement {
+    fn new(fields: &[Field]) -> Result<Statement, ReadError> {
+        let mut number = None;
+        let mut account = None;
+        let mut opening = None;
+        let mut closing = None;
+        let mut available = None;
+        let mut details: Vec<(NaiveDate, data::TransactionDetail)> = Vec::new();
+        let mut last_tag = "";
+        for field in fields {
+            match &*field.tag {
+                "25" => account = Some(field.content.trim().to_owned()),
+                "28" | "28C" => {
+                    number = field.content.split('/').next().and_then(|n| n.parse().ok())
+                }
+                "60F" | "60M" => opening = Some(Balance::new(field)?),
+                "61" => {
+                    let currency = match opening {
+                        Some(Balance { amount, .. }) => amount.currency,
+                        None => {
+                            return Err(ReadError::Field(
+                                field.line,
+                                ":61: before the opening balance".to_owned(),
+                            ))
+                        }
+                    };
+                    details.push(statement_line(field, currency)?);
+                }
+                // Information about the statement as a whole has nowhere to go.
+                "86" if last_tag == "61" => {
+                    let detail = &mut details.last_mut().unwrap().1;
+                    let text = detail.text.get_or_insert_with(Vec::new);
+                    let lines = field.content.lines().map(str::trim).filter(|l| !l.is_empty());
+                    text.extend(
+                        lines.flat_map(|l| write::wrap_text(l, write::DEFAULT_TEXT_LINE_LEN)),
+                    );
+                }
+                "62F" | "62M" => closing = Some(Balance::new(field)?),
+                "64" => available = Some(Balance::new(field)?),
+                _ => {}
+            }
+            last_tag = &field.tag;
+        }
+
+        let line = fields.first().map_or(0, |f| f.line);
+        let missing = |tag: &str| ReadError::Field(line, format!("message has no :{}:", tag));
+        Ok(Statement {
+            number,
+            account: account.ok_or_else(|| missing("25"))?,
+            opening: opening.ok_or_else(|| missing("60F"))?,
+            closing: closing.ok_or_else(|| missing("62F"))?,
+            available,
+            details,
+        })
+    }
+
+    /// Whether this is the next page of `previous`.
+    fn continues(&self, previous: &Statement) -> bool {
+        self.opening.intermediate && previous.closing.intermediate &&
+            self.account == previous.account
+    }
+
+    fn into_account(self) -> data::Account {
+        let mut infos = vec![
+            data::AccountInfo::Status {
+                code: StatusCode::Account(AccountStatus::OpeningLedger),
+                amount: Some(self.opening.amount),
+            },
+            data::AccountInfo::Status {
+                code: StatusCode::Account(AccountStatus::ClosingLedger),
+                amount: Some(self.closing.amount),
+            },
+        ];
+        if let Some(available) = self.available {
+            infos.push(data::AccountInfo::Status {
+                code: StatusCode::Account(AccountStatus::ClosingAvail),
+                amount: Some(available.amount),
+            });
+        }
+        let as_of = self.closing.date;
+        let transaction_details = self.details
+            .into_iter()
+            .map(|(value_date, mut detail)| {
+                if value_date != as_of {
+                    detail.funds = Some(data::FundsType::ValueDated(value_date.into()));
+                }
+                detail
+            })
+            .collect();
+        data::Account {
+            customer_account: data::AccountNumber(self.account),
+            currency: None,
+            infos,
+            transaction_details,
+        }
+    }
+}
+
+/// Reads a `:61:`, with its supplementary details as the first line of text.
+fn statement_line(
+    field: &Field,
+    currency: Currency,
+) -> Result<(NaiveDate, data::TransactionDetail), ReadError> {
+    let invalid = || ReadError::Field(field.line, format!("malformed :61: {:?}", field.content));
+    let mut lines = field.content.lines();
+    let line = lines.next().unwrap_or("");
+
+    let value_date = line.get(..6).and_then(date).ok_or_else(invalid)?;
+    let mut rest = &line[6..];
+    // The entry date, which is the statement's anyway.
+    if rest.len() >= 4 && rest.as_bytes()[..4].iter().all(u8::is_ascii_digit) {
+        rest = &rest[4..];
+    }
+    // Reversals of credits are debits, and the other way around.
+    let (credit, mark_len) = match rest.get(..2) {
+        Some("RC") => (false, 2),
+        Some("RD") => (true, 2),
+        _ if rest.starts_with('C') => (true, 1),
+        _ if rest.starts_with('D') => (false, 1),
+        _ => return Err(invalid()),
+    };
+    rest = &rest[mark_len..];
+    // The funds code, the third letter of the currency.
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        rest = &rest[1..];
+    }
+    let amount_len = rest.find(|c: char| !c.is_ascii_digit() && c != ',').unwrap_or(rest.len());
+    let amount = parse_amount(&rest[..amount_len], currency).ok_or_else(invalid)?;
+    rest = &rest[amount_len..];
+    let code = match rest.get(..4) {
+        Some(tp) if tp.starts_with('N') || tp.starts_with('F') => detail_code(&tp[1..], credit),
+        Some(tp) if tp.starts_with('S') => data::DetailCode::custom(credit),
+        _ => return Err(invalid()),
+    };
+    rest = &rest[4..];
+    let (customer_ref, bank_ref) = match rest.find("//") {
+        Some(i) => (&rest[..i], Some(rest[i + 2..].trim())),
+        None => (rest, None),
+    };
+    let customer_ref = customer_ref.trim();
+
+    let reference = |r: &str| data::ReferenceNum(r.to_owned());
+    let text: Vec<_> = lines
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_owned)
+        .collect();
+    let detail = data::TransactionDetail {
+        code,
+        amount: Some(amount),
+        funds: None,
+        bank_ref_num: bank_ref.filter(|r| !r.is_empty()).map(reference),
+        customer_ref_num: Some(customer_ref)
+            .filter(|r| !r.is_empty() && *r != "NONREF")
+            .map(reference),
+        text: if text.is_empty() { None } else { Some(text) },
+    };
+    Ok((value_date, detail))
+}
+
+/// A `YYMMDD` date.
+fn date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%y%m%d").ok()
+}
+
+/// A `15d` amount.
+fn parse_amount(s: &str, currency: Currency) -> Option<Amount> {
+    let decimal = s.replace(',', ".");
+    Amount::from_decimal(decimal.trim_end_matches('.'), currency)
+}
+
+/// The detail codes written as each SWIFT transaction type. The rest are written as `MSC`.
+const TYPES: &[(&str, &[u16])] = &[
+    ("LBX", &[115, 116, 118, 415]),
+    ("DCR", &[212, 213, 512, 513]),
+    ("RTI", &[123, 168, 255, 257, 266, 268, 423, 468, 555, 557, 566, 568]),
+    ("COL", &[218, 237, 240, 242, 362, 363, 518, 533, 540, 543, 662, 663, 696]),
+    ("INT", &[244, 346, 354, 359, 544, 654, 659, 721, 727]),
+    ("DIV", &[238]),
+    ("COM", &[224, 524]),
+    ("CHG", &[398, 564, 567, 661, 698, 724, 726, 728]),
+    ("FEX", &[214, 514]),
+    ("SEC", &[232, 233, 234, 236, 249, 531, 535, 542, 549]),
+    ("LDP", &[171, 364, 481, 664]),
+    (
+        "CHK",
+        &[175, 176, 221, 222, 379, 381, 384, 386, 395, 472, 474, 475, 522, 679, 681, 684, 686],
+    ),
+    (
+        "TRF",
+        &[
+            142, 143, 145, 165, 166, 169, 191, 195, 196, 206, 208, 258, 377, 445, 447, 452, 466,
+            491, 493, 495, 496, 506, 508, 558, 677,
+        ],
+    ),
+    ("STO", &[227, 527]),
+    ("DDT", &[451, 455, 462, 469]),
+];
+
+/// The credit and debit detail codes each SWIFT transaction type is read as.
+const CODES: &[(&str, Option<u16>, Option<u16>)] = &[
+    ("LBX", Some(115), Some(415)),
+    ("DCR", Some(213), Some(513)),
+    ("RTI", Some(266), Some(566)),
+    ("COL", Some(218), Some(518)),
+    ("INT", Some(354), Some(654)),
+    ("DIV", Some(238), None),
+    ("COM", Some(224), Some(524)),
+    ("CHG", Some(398), Some(698)),
+    ("FEX", Some(214), Some(514)),
+    ("SEC", Some(233), Some(531)),
+    ("LDP", Some(171), Some(481)),
+    ("CHK", Some(175), Some(475)),
+    ("TRF", Some(195), Some(495)),
+    ("STO", Some(227), Some(527)),
+    ("DDT", None, Some(455)),
+    ("MSC", Some(399), Some(699)),
+];
+
+/// The SWIFT transaction type a detail code is written as.
+pub fn transaction_type(code: u16) -> &'static str {
+    TYPES
+        .iter()
+        .find(|&&(_, codes)| codes.contains(&code))
+        .map_or("MSC", |&(tp, _)| tp)
+}
+
+/// The detail code a SWIFT transaction type is read as, going back the way `transaction_type`
+/// goes, or a custom one for types the spec has no credit or debit code for.
+pub fn detail_code(transaction_type: &str, credit: bool) -> data::DetailCode {
+    CODES
+        .iter()
+        .find(|&&(tp, _, _)| tp == transaction_type)
+        .and_then(|&(_, credit_code, debit_code)| if credit { credit_code } else { debit_code })
+        .and_then(|code| data::DetailCode::try_from(code).ok())
+        .unwrap_or_else(|| data::DetailCode::custom(credit))
+}
+
+/// A `:60F:`, `:62F:` or `:64:` balance.
+fn balance(value: Amount, date: &NaiveDate) -> io::Result<String> {
+    let mark = if value.value < 0 { "D" } else { "C" };
+    Ok(format!("{}{}{}{}", mark, date.format("%y%m%d"), value.currency, amount(value)?))
+}
+
+/// A `15d` amount, which has no sign and always has a decimal comma.
+fn amount(value: Amount) -> io::Result<String> {
+    let decimal = value.abs()?.to_decimal();
+    Ok(if decimal.contains('.') {
+        decimal.replace('.', ",")
+    } else {
+        decimal + ","
+    })
+}
+
+/// A `:13D:` date and time, without its UTC offset.
+fn date_time(date_time: &BaiDateTime) -> String {
+    match *date_time {
+        BaiDateTime::DateTime(dt) => dt.format("%y%m%d%H%M").to_string(),
+        BaiDateTime::DateEndOfDay(d) => format!("{}2359", d.format("%y%m%d")),
+    }
+}
+
+/// Replaces every character outside the SWIFT `x` character set with a space.
+fn charset(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c,
+            '/' | '-' | '?' | ':' | '(' | ')' | '.' | ',' | '\'' | '+' | ' ' => c,
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// Splits `text` into lines no longer than `width`, between words where it can.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = write::wrap_text(text, width);
+    lines.retain(|line| !line.is_empty());
+    // A line starting with `:` or `-` would be read as a new field or the end of the message.
+    for line in &mut lines {
+        if line.starts_with(':') || line.starts_with('-') {
+            line.replace_range(..1, " ");
+        }
+    }
+    lines
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    s.chars().take(max).collect()
+}
+
+struct Fields<W> {
+    w: W,
+}
+
+impl<W: io::Write> Fields<W> {
+    fn write(&mut self, tag: &str, content: &str) -> io::Result<()> {
+        write!(self.w, ":{}:{}\r\n", tag, content)
+    }
+}
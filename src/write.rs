@@ -0,0 +1,410 @@
+use std::io;
+use std::mem;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use data::{self, BaiDateOrTime, BaiDateTime};
+
+/// The physical record length used when none is given.
+pub const DEFAULT_PHYSICAL_RECORD_LEN: u16 = 80;
+
+/// The longest text line that fits on an 88 continuation record of the default physical record
+/// length.
+pub const DEFAULT_TEXT_LINE_LEN: usize = DEFAULT_PHYSICAL_RECORD_LEN as usize - 3;
+
+const CONTINUATION: &str = "88";
+
+pub struct Writer<W> {
+    inner: W,
+    physical_record_len: Option<u16>,
+}
+
+impl<W: io::Write> Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Writer::with_physical_record_len(inner, Some(DEFAULT_PHYSICAL_RECORD_LEN))
+    }
+
+    /// A `physical_record_len` of `None` writes every logical record on a single line.
+    pub fn with_physical_record_len(inner: W, physical_record_len: Option<u16>) -> Self {
+        Writer {
+            inner,
+            physical_record_len,
+        }
+    }
+
+    pub fn physical_record_len(&self) -> Option<u16> {
+        self.physical_record_len
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes a logical record terminated by `/`, splitting it into 88 continuation records as
+    /// needed. Fields are never split, so fields too long for an 88 continuation record are an
+    /// error. Returns the number of physical records written.
+    pub fn write_record<S: AsRef<str>>(&mut self, code: &str, fields: &[S]) -> io::Result<usize> {
+        self.write_fields::<S, &str>(code, fields, None)
+    }
+
+    /// Writes a logical record whose last field is free-form text, which isn't terminated by `/`.
+    /// Each text line starts a new physical record, and is never split, since it would read back
+    /// as two lines; lines too long for an 88 continuation record are an error. Returns the
+    /// number of physical records written.
+    pub fn write_text_record<S, T>(
+        &mut self,
+        code: &str,
+        fields: &[S],
+        text: &[T],
+    ) -> io::Result<usize>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        self.write_fields(code, fields, Some(text))
+    }
+
+    fn write_fields<S, T>(
+        &mut self,
+        code: &str,
+        fields: &[S],
+        text: Option<&[T]>,
+    ) -> io::Result<usize>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        let max_len = self.physical_record_len.map(usize::from);
+        let fits = |line: &String, len: usize| max_len.map_or(true, |max| line.len() + len <= max);
+        for text_line in text.unwrap_or(&[]) {
+            let text_line = text_line.as_ref();
+            check_text(text_line)?;
+            if !fits(&CONTINUATION.to_owned(), 1 + text_line.len()) {
+                return Err(invalid_input(format!(
+                    "text {:?} doesn't fit in a physical record of {} bytes",
+                    text_line,
+                    max_len.unwrap()
+                )));
+            }
+        }
+
+        let mut records_num = 0;
+        let mut line = String::from(code);
+        for field in fields {
+            let field = field.as_ref();
+            check_field(field)?;
+            if !fits(&CONTINUATION.to_owned(), field.len() + 2) {
+                return Err(invalid_input(format!(
+                    "field {:?} doesn't fit in a physical record of {} bytes",
+                    field,
+                    max_len.unwrap()
+                )));
+            }
+            // Leave room for the separator and a closing `/`.
+            if !fits(&line, field.len() + 2) && line.len() > CONTINUATION.len() {
+                line.push('/');
+                self.write_line(&line)?;
+                records_num += 1;
+                line.clear();
+                line.push_str(CONTINUATION);
+            }
+            line.push(',');
+            line.push_str(field);
+        }
+        match text {
+            None => line.push('/'),
+            Some(text) => {
+                for (i, text_line) in text.iter().enumerate() {
+                    let text_line = text_line.as_ref();
+                    if i == 0 && fits(&line, 1 + text_line.len()) {
+                        line.push(',');
+                    } else {
+                        if i == 0 {
+                            line.push('/');
+                        }
+                        self.write_line(&line)?;
+                        records_num += 1;
+                        line.clear();
+                        line.push_str(CONTINUATION);
+                        line.push(',');
+                    }
+                    line.push_str(text_line);
+                }
+            }
+        }
+        self.write_line(&line)?;
+        Ok(records_num + 1)
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.inner.write_all(line.as_bytes())?;
+        self.inner.write_all(b"\n")
+    }
+
+    pub fn write_file(&mut self, file: &data::File) -> io::Result<()> {
+        let mut records_num = self.write_record("01", &file_header_fields(
+            file,
+            self.physical_record_len,
+        ))?;
+        let mut control_total = 0;
+        for group in &file.groups {
+            let (group_control_total, group_records_num) = self.write_group(group)?;
+            control_total += group_control_total;
+            records_num += group_records_num;
+        }
+        self.write_record(
+            "99",
+            &[
+                control_total.to_string(),
+                file.groups.len().to_string(),
+                (records_num + 1).to_string(),
+            ],
+        )?;
+        self.inner.flush()
+    }
+
+    /// Returns the control total and number of records of the written group.
+    pub fn write_group(&mut self, group: &data::Group) -> io::Result<(i64, usize)> {
+        let mut records_num = self.write_record("02", &group_header_fields(group))?;
+        let mut control_total = 0;
+        for account in &group.accounts {
+            let (account_control_total, account_records_num) = self.write_account(account)?;
+            control_total += account_control_total;
+            records_num += account_records_num;
+        }
+        records_num += self.write_record(
+            "98",
+            &[
+                control_total.to_string(),
+                group.accounts.len().to_string(),
+                (records_num + 1).to_string(),
+            ],
+        )?;
+        Ok((control_total, records_num))
+    }
+
+    /// Returns the control total and number of records of the written account.
+    pub fn write_account(&mut self, account: &data::Account) -> io::Result<(i64, usize)> {
+        let mut records_num = self.write_record("03", &account_ident_fields(account))?;
+        for transaction_detail in &account.transaction_details {
+            records_num += self.write_transaction_detail(transaction_detail)?;
+        }
+        let control_total = account.control_total();
+        records_num += self.write_record(
+            "49",
+            &[control_total.to_string(), (records_num + 1).to_string()],
+        )?;
+        Ok((control_total, records_num))
+    }
+
+    /// Returns the number of records written.
+    pub fn write_transaction_detail(
+        &mut self,
+        transaction_detail: &data::TransactionDetail,
+    ) -> io::Result<usize> {
+        let mut fields = vec![format!("{:03}", u16::from(transaction_detail.code))];
+        push_opt(&mut fields, transaction_detail.amount.map(|a| a.value));
+        push_funds(&mut fields, transaction_detail.funds.as_ref());
+        push_opt(&mut fields, transaction_detail.bank_ref_num.as_ref().map(|r| &r.0));
+        push_opt(&mut fields, transaction_detail.customer_ref_num.as_ref().map(|r| &r.0));
+        match transaction_detail.text {
+            Some(ref text) if !text.is_empty() => self.write_text_record("16", &fields, text),
+            _ => {
+                fields.push(String::new());
+                self.write_record("16", &fields)
+            }
+        }
+    }
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn check_field(field: &str) -> io::Result<()> {
+    if field.contains(|c| c == ',' || c == '/' || c == '\n' || c == '\r') {
+        Err(invalid_input(format!("field {:?} contains a separator", field)))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_text(text: &str) -> io::Result<()> {
+    if text.contains(|c| c == '\n' || c == '\r') {
+        Err(invalid_input(format!("text {:?} contains a line break", text)))
+    } else if text.starts_with('/') {
+        Err(invalid_input(format!("text {:?} starts with a record separator", text)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Splits `text` into lines no longer than `width` bytes, between words where it can, so that
+/// each fits on a physical record of its own. Text that already fits is left as it is.
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    if text.len() <= width {
+        return vec![text.to_owned()];
+    }
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word;
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(mem::replace(&mut line, String::new()));
+        }
+        while word.len() > width {
+            let split = match floor_char_boundary(word, width) {
+                0 => word.chars().next().map_or(0, char::len_utf8),
+                split => split,
+            };
+            lines.push(word[..split].to_owned());
+            word = &word[split..];
+        }
+        if !word.is_empty() {
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn floor_char_boundary(s: &str, mut i: usize) -> usize {
+    if i >= s.len() {
+        return s.len();
+    }
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+fn date(date: &NaiveDate) -> String {
+    date.format("%y%m%d").to_string()
+}
+
+fn time(time: &NaiveTime) -> String {
+    time.format("%H%M").to_string()
+}
+
+const END_OF_DAY: &str = "9999";
+
+fn date_time(date_time: &BaiDateTime) -> (String, String) {
+    match *date_time {
+        BaiDateTime::DateTime(ref dt) => (date(&dt.date()), time(&dt.time())),
+        BaiDateTime::DateEndOfDay(ref d) => (date(d), END_OF_DAY.to_owned()),
+    }
+}
+
+fn date_or_time(date_or_time: &BaiDateOrTime) -> (String, Option<String>) {
+    match *date_or_time {
+        BaiDateOrTime::Date(ref d) => (date(d), None),
+        BaiDateOrTime::DateTime(ref dt) => (date(&dt.date()), Some(time(&dt.time()))),
+        BaiDateOrTime::DateEndOfDay(ref d) => (date(d), Some(END_OF_DAY.to_owned())),
+    }
+}
+
+fn push_opt<T: ToString>(fields: &mut Vec<String>, field: Option<T>) {
+    fields.push(field.map_or_else(String::new, |f| f.to_string()));
+}
+
+fn push_funds(fields: &mut Vec<String>, funds: Option<&data::FundsType>) {
+    use data::FundsType as FT;
+    match funds {
+        None => fields.push(String::new()),
+        Some(&FT::Unknown) => fields.push("Z".to_owned()),
+        Some(&FT::ImmediateAvail) => fields.push("0".to_owned()),
+        Some(&FT::OneDayAvail) => fields.push("1".to_owned()),
+        Some(&FT::TwoOrMoreDaysAvail) => fields.push("2".to_owned()),
+        Some(&FT::DistributedAvailS {
+                 immediate,
+                 one_day,
+                 more_than_one_day,
+             }) => {
+            fields.push("S".to_owned());
+            push_opt(fields, immediate.map(|a| a.value));
+            push_opt(fields, one_day.map(|a| a.value));
+            push_opt(fields, more_than_one_day.map(|a| a.value));
+        }
+        Some(&FT::ValueDated(ref avail)) => {
+            let (date, time) = date_or_time(avail);
+            fields.push("V".to_owned());
+            fields.push(date);
+            push_opt(fields, time);
+        }
+        Some(&FT::DistributedAvailD(ref dists)) => {
+            fields.push("D".to_owned());
+            fields.push(dists.len().to_string());
+            for dist in dists {
+                fields.push(dist.days.to_string());
+                fields.push(dist.amount.value.to_string());
+            }
+        }
+    }
+}
+
+fn file_header_fields(file: &data::File, physical_record_len: Option<u16>) -> Vec<String> {
+    let (creation_date, creation_time) = date_time(&file.creation);
+    let mut fields = vec![
+        file.sender.0.clone(),
+        file.receiver.0.clone(),
+        creation_date,
+        creation_time,
+        file.ident.0.to_string(),
+    ];
+    push_opt(&mut fields, physical_record_len);
+    // Block size
+    fields.push(String::new());
+    fields.push(file.version.version_number().to_string());
+    fields
+}
+
+fn group_header_fields(group: &data::Group) -> Vec<String> {
+    let (as_of_date, as_of_time) = date_or_time(&group.as_of);
+    let mut fields = Vec::with_capacity(7);
+    push_opt(&mut fields, group.ultimate_receiver.as_ref().map(|p| &p.0));
+    push_opt(&mut fields, group.originator.as_ref().map(|p| &p.0));
+    fields.push(u8::from(group.status).to_string());
+    fields.push(as_of_date);
+    push_opt(&mut fields, as_of_time);
+    push_opt(&mut fields, group.currency);
+    push_opt(&mut fields, group.as_of_date_mod.map(u8::from));
+    fields
+}
+
+fn account_ident_fields(account: &data::Account) -> Vec<String> {
+    use data::AccountInfo as AI;
+
+    let mut fields = vec![account.customer_account.0.clone()];
+    push_opt(&mut fields, account.currency);
+    for info in &account.infos {
+        match *info {
+            AI::Status { code, amount } => {
+                fields.push(format!("{:03}", u16::from(code)));
+                push_opt(&mut fields, amount.map(|a| a.value));
+                fields.push(String::new());
+                fields.push(String::new());
+            }
+            AI::Summary {
+                code,
+                amount,
+                item_count,
+                ref funds,
+            } => {
+                fields.push(format!("{:03}", u16::from(code)));
+                push_opt(&mut fields, amount.map(|a| a.value));
+                push_opt(&mut fields, item_count);
+                push_funds(&mut fields, funds.as_ref());
+            }
+        }
+    }
+    if account.infos.is_empty() {
+        // The record needs at least one (empty) account info.
+        fields.extend(vec![String::new(); 4]);
+    }
+    fields
+}